@@ -0,0 +1,565 @@
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde_json::{Map, Value, json};
+
+/// Converts Markdown into an Atlassian Document Format document, as expected by
+/// the `description` field of the Jira REST API v3.
+///
+/// Supports paragraphs, headings, bullet and ordered lists (nested), code blocks,
+/// block quotes, rules, links and inline formatting (bold, italic, strikethrough,
+/// inline code). Images become links since ADF media requires an upload.
+pub fn markdown_to_adf(markdown: &str) -> Value {
+    let mut converter = Converter::new();
+
+    for event in Parser::new_ext(markdown, Options::ENABLE_STRIKETHROUGH) {
+        converter.handle(event);
+    }
+
+    converter.finish()
+}
+
+struct Block {
+    node: Map<String, Value>,
+    /// Paragraphs opened for inline content of tight list items or quotes.
+    implicit: bool,
+}
+
+struct Converter {
+    stack: Vec<Block>,
+    marks: Vec<Value>,
+}
+
+impl Converter {
+    fn new() -> Self {
+        let doc = json!({ "version": 1, "type": "doc", "content": [] });
+
+        Self {
+            stack: vec![Block {
+                node: into_map(doc),
+                implicit: false,
+            }],
+            marks: Vec::new(),
+        }
+    }
+
+    fn finish(mut self) -> Value {
+        while self.stack.len() > 1 {
+            self.pop_block();
+        }
+        Value::Object(self.stack.pop().expect("doc block is never popped").node)
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.text(&text),
+            Event::Code(code) => {
+                let mut marks: Vec<Value> = self
+                    .marks
+                    .iter()
+                    .filter(|m| m["type"] == "link")
+                    .cloned()
+                    .collect();
+                marks.push(json!({ "type": "code" }));
+                self.push_inline(text_node(&code, marks));
+            }
+            Event::Html(html) | Event::InlineHtml(html) => self.text(&html),
+            Event::SoftBreak => self.text(" "),
+            Event::HardBreak => self.push_inline(json!({ "type": "hardBreak" })),
+            Event::Rule => {
+                self.close_implicit();
+                self.push_node(json!({ "type": "rule" }));
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {
+                self.close_implicit();
+                self.push_block(json!({ "type": "paragraph", "content": [] }));
+            }
+            Tag::Heading { level, .. } => {
+                self.close_implicit();
+                self.push_block(json!({
+                    "type": "heading",
+                    "attrs": { "level": heading_level(level) },
+                    "content": []
+                }));
+            }
+            Tag::BlockQuote(_) => {
+                self.close_implicit();
+                self.push_block(json!({ "type": "blockquote", "content": [] }));
+            }
+            Tag::CodeBlock(kind) => {
+                self.close_implicit();
+                let mut node = json!({ "type": "codeBlock", "content": [] });
+                if let CodeBlockKind::Fenced(info) = kind
+                    && let Some(language) = info.split_whitespace().next()
+                {
+                    node["attrs"] = json!({ "language": language });
+                }
+                self.push_block(node);
+            }
+            Tag::List(start) => {
+                self.close_implicit();
+                let node = match start {
+                    Some(order) => {
+                        json!({ "type": "orderedList", "attrs": { "order": order }, "content": [] })
+                    }
+                    None => json!({ "type": "bulletList", "content": [] }),
+                };
+                self.push_block(node);
+            }
+            Tag::Item => self.push_block(json!({ "type": "listItem", "content": [] })),
+            Tag::Emphasis => self.marks.push(json!({ "type": "em" })),
+            Tag::Strong => self.marks.push(json!({ "type": "strong" })),
+            Tag::Strikethrough => self.marks.push(json!({ "type": "strike" })),
+            Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => self
+                .marks
+                .push(json!({ "type": "link", "attrs": { "href": dest_url.to_string() } })),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::CodeBlock => self.pop_block(),
+            TagEnd::BlockQuote(_) | TagEnd::List(_) | TagEnd::Item => {
+                self.close_implicit();
+                self.pop_block();
+            }
+            TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Link
+            | TagEnd::Image => {
+                self.marks.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.top_type() == "codeBlock" {
+            let content = self.top_content();
+            match content.last_mut() {
+                Some(node) => {
+                    let existing = node["text"].as_str().unwrap_or_default().to_string();
+                    node["text"] = Value::String(existing + text);
+                }
+                None => content.push(text_node(text, vec![])),
+            }
+            return;
+        }
+
+        self.push_inline(text_node(text, self.marks.clone()));
+    }
+
+    /// Adds an inline node, opening a paragraph if the current block only takes blocks.
+    fn push_inline(&mut self, node: Value) {
+        if !matches!(self.top_type(), "paragraph" | "heading") {
+            self.push_block(json!({ "type": "paragraph", "content": [] }));
+            self.stack
+                .last_mut()
+                .expect("block was just pushed")
+                .implicit = true;
+        }
+
+        let content = self.top_content();
+        // Merge with the previous text node when the marks are the same.
+        if let Some(previous) = content.last_mut()
+            && previous["type"] == "text"
+            && node["type"] == "text"
+            && previous.get("marks") == node.get("marks")
+        {
+            let merged = format!(
+                "{}{}",
+                previous["text"].as_str().unwrap_or_default(),
+                node["text"].as_str().unwrap_or_default()
+            );
+            previous["text"] = Value::String(merged);
+            return;
+        }
+
+        content.push(node);
+    }
+
+    fn push_block(&mut self, node: Value) {
+        self.stack.push(Block {
+            node: into_map(node),
+            implicit: false,
+        });
+    }
+
+    fn pop_block(&mut self) {
+        if self.stack.len() <= 1 {
+            return;
+        }
+
+        let mut block = self.stack.pop().expect("stack has more than one block");
+        if block.node["type"] == "codeBlock" {
+            trim_code_block(&mut block.node);
+        }
+        self.push_node(Value::Object(block.node));
+    }
+
+    fn close_implicit(&mut self) {
+        if self.stack.last().is_some_and(|block| block.implicit) {
+            self.pop_block();
+        }
+    }
+
+    fn push_node(&mut self, node: Value) {
+        self.top_content().push(node);
+    }
+
+    fn top_type(&self) -> &str {
+        self.stack
+            .last()
+            .and_then(|block| block.node["type"].as_str())
+            .unwrap_or_default()
+    }
+
+    fn top_content(&mut self) -> &mut Vec<Value> {
+        self.stack
+            .last_mut()
+            .and_then(|block| block.node.get_mut("content"))
+            .and_then(Value::as_array_mut)
+            .expect("container blocks have a content array")
+    }
+}
+
+fn text_node(text: &str, marks: Vec<Value>) -> Value {
+    let mut node = json!({ "type": "text", "text": text });
+    if !marks.is_empty() {
+        node["marks"] = Value::Array(marks);
+    }
+    node
+}
+
+/// Drops the trailing newline of the code and the content of empty blocks,
+/// since ADF rejects empty text nodes.
+fn trim_code_block(node: &mut Map<String, Value>) {
+    let Some(content) = node.get_mut("content").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    if let Some(text) = content.first_mut() {
+        let trimmed = text["text"]
+            .as_str()
+            .unwrap_or_default()
+            .trim_end_matches('\n')
+            .to_string();
+        text["text"] = Value::String(trimmed);
+    }
+
+    if content
+        .first()
+        .is_some_and(|text| text["text"].as_str().is_some_and(str::is_empty))
+    {
+        content.clear();
+    }
+
+    if content.is_empty() {
+        node.remove("content");
+    }
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+fn into_map(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(map) => map,
+        _ => unreachable!("ADF nodes are objects"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(content: Value) -> Value {
+        json!({ "version": 1, "type": "doc", "content": content })
+    }
+
+    #[test]
+    fn test_empty_document() {
+        assert_eq!(markdown_to_adf(""), doc(json!([])));
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let adf = markdown_to_adf("First line\ncontinued.\n\nSecond paragraph.");
+        assert_eq!(
+            adf,
+            doc(json!([
+                { "type": "paragraph", "content": [{ "type": "text", "text": "First line continued." }] },
+                { "type": "paragraph", "content": [{ "type": "text", "text": "Second paragraph." }] }
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_hard_break() {
+        let adf = markdown_to_adf("one  \ntwo");
+        assert_eq!(
+            adf["content"][0]["content"],
+            json!([
+                { "type": "text", "text": "one" },
+                { "type": "hardBreak" },
+                { "type": "text", "text": "two" }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_headings() {
+        let adf = markdown_to_adf("# Title\n\n### Sub *title*");
+        assert_eq!(
+            adf,
+            doc(json!([
+                {
+                    "type": "heading",
+                    "attrs": { "level": 1 },
+                    "content": [{ "type": "text", "text": "Title" }]
+                },
+                {
+                    "type": "heading",
+                    "attrs": { "level": 3 },
+                    "content": [
+                        { "type": "text", "text": "Sub " },
+                        { "type": "text", "text": "title", "marks": [{ "type": "em" }] }
+                    ]
+                }
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_inline_formatting() {
+        let adf = markdown_to_adf("**bold** _em_ ~~gone~~ `code` ***both***");
+        assert_eq!(
+            adf["content"][0]["content"],
+            json!([
+                { "type": "text", "text": "bold", "marks": [{ "type": "strong" }] },
+                { "type": "text", "text": " " },
+                { "type": "text", "text": "em", "marks": [{ "type": "em" }] },
+                { "type": "text", "text": " " },
+                { "type": "text", "text": "gone", "marks": [{ "type": "strike" }] },
+                { "type": "text", "text": " " },
+                { "type": "text", "text": "code", "marks": [{ "type": "code" }] },
+                { "type": "text", "text": " " },
+                {
+                    "type": "text",
+                    "text": "both",
+                    "marks": [{ "type": "em" }, { "type": "strong" }]
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        let adf = markdown_to_adf("See [the **docs**](https://example.com) and <https://a.b>");
+        assert_eq!(
+            adf["content"][0]["content"],
+            json!([
+                { "type": "text", "text": "See " },
+                {
+                    "type": "text",
+                    "text": "the ",
+                    "marks": [{ "type": "link", "attrs": { "href": "https://example.com" } }]
+                },
+                {
+                    "type": "text",
+                    "text": "docs",
+                    "marks": [
+                        { "type": "link", "attrs": { "href": "https://example.com" } },
+                        { "type": "strong" }
+                    ]
+                },
+                { "type": "text", "text": " and " },
+                {
+                    "type": "text",
+                    "text": "https://a.b",
+                    "marks": [{ "type": "link", "attrs": { "href": "https://a.b" } }]
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_code_inside_link_keeps_only_link_mark() {
+        let adf = markdown_to_adf("[**`x`**](https://e.com)");
+        assert_eq!(
+            adf["content"][0]["content"][0]["marks"],
+            json!([
+                { "type": "link", "attrs": { "href": "https://e.com" } },
+                { "type": "code" }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_image_becomes_link() {
+        let adf = markdown_to_adf("![screenshot](https://e.com/a.png)");
+        assert_eq!(
+            adf["content"][0]["content"],
+            json!([{
+                "type": "text",
+                "text": "screenshot",
+                "marks": [{ "type": "link", "attrs": { "href": "https://e.com/a.png" } }]
+            }])
+        );
+    }
+
+    #[test]
+    fn test_fenced_code_block() {
+        let adf = markdown_to_adf("```rust\nfn main() {}\n\nlet x = 1;\n```");
+        assert_eq!(
+            adf,
+            doc(json!([{
+                "type": "codeBlock",
+                "attrs": { "language": "rust" },
+                "content": [{ "type": "text", "text": "fn main() {}\n\nlet x = 1;" }]
+            }]))
+        );
+    }
+
+    #[test]
+    fn test_code_block_without_language() {
+        let adf = markdown_to_adf("    indented *code*\n");
+        assert_eq!(
+            adf,
+            doc(json!([{
+                "type": "codeBlock",
+                "content": [{ "type": "text", "text": "indented *code*" }]
+            }]))
+        );
+    }
+
+    #[test]
+    fn test_empty_code_block() {
+        let adf = markdown_to_adf("```\n```");
+        assert_eq!(adf, doc(json!([{ "type": "codeBlock" }])));
+    }
+
+    #[test]
+    fn test_tight_bullet_list() {
+        let adf = markdown_to_adf("- one\n- **two**");
+        assert_eq!(
+            adf,
+            doc(json!([{
+                "type": "bulletList",
+                "content": [
+                    {
+                        "type": "listItem",
+                        "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "one" }] }]
+                    },
+                    {
+                        "type": "listItem",
+                        "content": [{
+                            "type": "paragraph",
+                            "content": [{ "type": "text", "text": "two", "marks": [{ "type": "strong" }] }]
+                        }]
+                    }
+                ]
+            }]))
+        );
+    }
+
+    #[test]
+    fn test_loose_list() {
+        let adf = markdown_to_adf("- one\n\n- two\n");
+        let items = adf["content"][0]["content"].as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[1]["content"],
+            json!([{ "type": "paragraph", "content": [{ "type": "text", "text": "two" }] }])
+        );
+    }
+
+    #[test]
+    fn test_ordered_list_with_start() {
+        let adf = markdown_to_adf("3. three\n4. four");
+        assert_eq!(adf["content"][0]["type"], "orderedList");
+        assert_eq!(adf["content"][0]["attrs"], json!({ "order": 3 }));
+        assert_eq!(adf["content"][0]["content"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_nested_lists() {
+        let adf = markdown_to_adf("- parent\n  1. child\n  2. other\n- sibling");
+        assert_eq!(
+            adf["content"][0]["content"][0],
+            json!({
+                "type": "listItem",
+                "content": [
+                    { "type": "paragraph", "content": [{ "type": "text", "text": "parent" }] },
+                    {
+                        "type": "orderedList",
+                        "attrs": { "order": 1 },
+                        "content": [
+                            {
+                                "type": "listItem",
+                                "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "child" }] }]
+                            },
+                            {
+                                "type": "listItem",
+                                "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "other" }] }]
+                            }
+                        ]
+                    }
+                ]
+            })
+        );
+        assert_eq!(
+            adf["content"][0]["content"][1]["content"][0]["content"][0]["text"],
+            "sibling"
+        );
+    }
+
+    #[test]
+    fn test_code_block_in_list() {
+        let adf = markdown_to_adf("- step\n\n  ```sh\n  make\n  ```\n");
+        let item = &adf["content"][0]["content"][0];
+        assert_eq!(item["content"][1]["type"], "codeBlock");
+        assert_eq!(item["content"][1]["content"][0]["text"], "make");
+    }
+
+    #[test]
+    fn test_blockquote_and_rule() {
+        let adf = markdown_to_adf("> quoted\n\n---\n\nafter");
+        assert_eq!(
+            adf,
+            doc(json!([
+                {
+                    "type": "blockquote",
+                    "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "quoted" }] }]
+                },
+                { "type": "rule" },
+                { "type": "paragraph", "content": [{ "type": "text", "text": "after" }] }
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_html_is_kept_as_text() {
+        let adf = markdown_to_adf("a <b>tag</b>");
+        assert_eq!(
+            adf["content"][0]["content"],
+            json!([{ "type": "text", "text": "a <b>tag</b>" }])
+        );
+    }
+}
@@ -0,0 +1,743 @@
+use std::{
+    collections::{HashMap, hash_map::RandomState},
+    fs,
+    hash::BuildHasher,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::{FutureExt, future::BoxFuture};
+use reqwest::{Client, RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::{
+    config::{AuthMethod, Instance},
+    env::Credentials,
+};
+
+/// The token endpoint of Atlassian OAuth 2.0 (3LO) apps.
+const ATLASSIAN_TOKEN_URL: &str = "https://auth.atlassian.com/oauth/token";
+
+/// The authorization page of Atlassian OAuth 2.0 (3LO) apps.
+const ATLASSIAN_AUTHORIZE_URL: &str = "https://auth.atlassian.com/authorize";
+
+/// The callback URL `auth login` listens on, unless the instance sets one.
+const DEFAULT_REDIRECT_URI: &str = "http://localhost:8765/callback";
+
+/// The scopes needed to create tickets, and to get a refresh token.
+const DEFAULT_SCOPES: [&str; 4] = [
+    "read:jira-work",
+    "write:jira-work",
+    "read:jira-user",
+    "offline_access",
+];
+
+/// Access tokens are refreshed this long before they expire.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Error, Debug)]
+pub enum AuthError {
+    #[error("OAuth token request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("OAuth token refresh failed: {0}")]
+    Refresh(String),
+
+    #[error("No OAuth refresh token: run `tedlt auth login` or set JIRA_OAUTH_REFRESH_TOKEN")]
+    MissingRefreshToken,
+
+    #[error("Invalid OAuth redirect_uri '{0}': expected a local http:// URL with a port")]
+    InvalidRedirectUri(String),
+
+    #[error("OAuth authorization failed: {0}")]
+    Authorization(String),
+
+    #[error("Failed to receive the OAuth authorization code: {0}")]
+    Callback(#[from] io::Error),
+}
+
+/// Adds credentials to the requests sent to Jira.
+pub trait AuthStrategy: Send + Sync {
+    fn authorize(
+        &self,
+        request: RequestBuilder,
+    ) -> BoxFuture<'_, Result<RequestBuilder, AuthError>>;
+}
+
+/// Builds the strategy for the credentials of an instance.
+pub fn strategy(credentials: Credentials, instance: &Instance) -> Box<dyn AuthStrategy> {
+    match credentials {
+        Credentials::Basic { email, api_token } => Box::new(BasicAuth { email, api_token }),
+        Credentials::Bearer { token } => Box::new(BearerAuth { token }),
+        Credentials::OAuth {
+            client_id,
+            client_secret,
+            refresh_token,
+            token_url,
+        } => Box::new(OAuth::new(
+            client_id,
+            client_secret,
+            refresh_token,
+            token_url.unwrap_or_else(|| ATLASSIAN_TOKEN_URL.to_string()),
+            token_cache_path(instance.name.as_deref()),
+        )),
+    }
+}
+
+/// Logs in to an OAuth instance with the authorization code flow: `show` is
+/// given the authorization page to open, and the code the browser is then
+/// redirected with is exchanged for tokens. The tokens are cached for the next
+/// runs, and the returned strategy uses them.
+pub async fn oauth_login(
+    credentials: Credentials,
+    instance: &Instance,
+    show: impl FnOnce(&str),
+) -> Result<Box<dyn AuthStrategy>, AuthError> {
+    let AuthMethod::OAuth {
+        authorize_url,
+        redirect_uri,
+        scopes,
+        ..
+    } = &instance.auth
+    else {
+        return Ok(strategy(credentials, instance));
+    };
+    let Credentials::OAuth {
+        client_id,
+        client_secret,
+        token_url,
+        ..
+    } = credentials
+    else {
+        return Ok(strategy(credentials, instance));
+    };
+
+    let oauth = OAuth::new(
+        client_id,
+        client_secret,
+        None,
+        token_url.unwrap_or_else(|| ATLASSIAN_TOKEN_URL.to_string()),
+        token_cache_path(instance.name.as_deref()),
+    );
+    let redirect_uri = redirect_uri.as_deref().unwrap_or(DEFAULT_REDIRECT_URI);
+    let scopes = match scopes {
+        Some(scopes) => scopes.join(" "),
+        None => DEFAULT_SCOPES.join(" "),
+    };
+    oauth
+        .authorize(
+            authorize_url.as_deref().unwrap_or(ATLASSIAN_AUTHORIZE_URL),
+            redirect_uri,
+            &scopes,
+            show,
+        )
+        .await?;
+    Ok(Box::new(oauth))
+}
+
+/// An account email and API token, for Jira Cloud.
+pub struct BasicAuth {
+    pub email: String,
+    pub api_token: String,
+}
+
+impl AuthStrategy for BasicAuth {
+    fn authorize(
+        &self,
+        request: RequestBuilder,
+    ) -> BoxFuture<'_, Result<RequestBuilder, AuthError>> {
+        let request = request.basic_auth(&self.email, Some(&self.api_token));
+        futures::future::ready(Ok(request)).boxed()
+    }
+}
+
+/// A Personal Access Token, for Jira Server and Data Center.
+pub struct BearerAuth {
+    pub token: String,
+}
+
+impl AuthStrategy for BearerAuth {
+    fn authorize(
+        &self,
+        request: RequestBuilder,
+    ) -> BoxFuture<'_, Result<RequestBuilder, AuthError>> {
+        futures::future::ready(Ok(request.bearer_auth(&self.token))).boxed()
+    }
+}
+
+/// OAuth 2.0 (3LO) access tokens, refreshed when they expire.
+///
+/// Refresh tokens rotate, so the latest tokens are cached on disk to be reused
+/// by the next runs.
+pub struct OAuth {
+    client: Client,
+    client_id: String,
+    client_secret: String,
+    token_url: String,
+    /// The configured refresh token, used when the cached one is rejected.
+    refresh_token: Option<String>,
+    cache_path: Option<PathBuf>,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedToken {
+    access_token: String,
+    refresh_token: Option<String>,
+    /// When the access token expires, in seconds since the Unix epoch.
+    expires_at: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+    refresh_token: Option<String>,
+}
+
+impl OAuth {
+    pub fn new(
+        client_id: String,
+        client_secret: String,
+        refresh_token: Option<String>,
+        token_url: String,
+        cache_path: Option<PathBuf>,
+    ) -> Self {
+        let cached = cache_path.as_deref().and_then(|path| {
+            let content = fs::read_to_string(path).ok()?;
+            serde_json::from_str(&content)
+                .inspect_err(|e| warn!("Ignoring the OAuth token cache {:?}: {}", path, e))
+                .ok()
+        });
+
+        Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("Failed to create HTTP client"),
+            client_id,
+            client_secret,
+            token_url,
+            refresh_token,
+            cache_path,
+            cached: Mutex::new(cached),
+        }
+    }
+
+    /// Returns a valid access token, refreshing it if needed.
+    async fn access_token(&self) -> Result<String, AuthError> {
+        // Held during the refresh, so concurrent requests refresh only once.
+        let mut cached = self.cached.lock().await;
+        if let Some(token) = cached.as_ref()
+            && token.expires_at > now() + EXPIRY_MARGIN.as_secs()
+        {
+            return Ok(token.access_token.clone());
+        }
+
+        let mut refresh_tokens: Vec<String> = cached
+            .as_ref()
+            .and_then(|token| token.refresh_token.clone())
+            .into_iter()
+            .collect();
+        if let Some(configured) = &self.refresh_token
+            && !refresh_tokens.contains(configured)
+        {
+            refresh_tokens.push(configured.clone());
+        }
+
+        let mut error = AuthError::MissingRefreshToken;
+        for refresh_token in refresh_tokens {
+            match self.refresh(&refresh_token).await {
+                Ok(token) => {
+                    self.save(&token);
+                    let access_token = token.access_token.clone();
+                    *cached = Some(token);
+                    return Ok(access_token);
+                }
+                Err(e) => {
+                    debug!("OAuth refresh token rejected: {}", e);
+                    error = e;
+                }
+            }
+        }
+        Err(error)
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<CachedToken, AuthError> {
+        debug!("Refreshing the OAuth access token at {}", self.token_url);
+        let token = self
+            .request_token(json!({
+                "grant_type": "refresh_token",
+                "client_id": self.client_id,
+                "client_secret": self.client_secret,
+                "refresh_token": refresh_token,
+            }))
+            .await
+            .map_err(AuthError::Refresh)?;
+
+        Ok(CachedToken {
+            access_token: token.access_token,
+            refresh_token: token
+                .refresh_token
+                .or_else(|| Some(refresh_token.to_string())),
+            expires_at: now() + token.expires_in,
+        })
+    }
+
+    /// Sends the user to the authorization page, waits for the code on
+    /// `redirect_uri` and exchanges it for tokens, which are cached.
+    async fn authorize(
+        &self,
+        authorize_url: &str,
+        redirect_uri: &str,
+        scopes: &str,
+        show: impl FnOnce(&str),
+    ) -> Result<(), AuthError> {
+        let invalid_redirect = || AuthError::InvalidRedirectUri(redirect_uri.to_string());
+        let redirect = Url::parse(redirect_uri).map_err(|_| invalid_redirect())?;
+        let port = redirect.port().ok_or_else(invalid_redirect)?;
+        if redirect.scheme() != "http"
+            || !matches!(redirect.host_str(), Some("localhost" | "127.0.0.1"))
+        {
+            return Err(invalid_redirect());
+        }
+        // Bound before showing the page, so that the redirect cannot be missed.
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+
+        let state = format!("{:016x}", RandomState::new().hash_one(now()));
+        let mut url = Url::parse(authorize_url).map_err(|_| {
+            AuthError::Authorization(format!("invalid authorize_url '{}'", authorize_url))
+        })?;
+        url.query_pairs_mut()
+            .append_pair("audience", "api.atlassian.com")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("scope", scopes)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("state", &state)
+            .append_pair("response_type", "code")
+            .append_pair("prompt", "consent");
+        show(url.as_str());
+
+        let path = redirect.path().to_string();
+        let code = tokio::task::spawn_blocking(move || receive_code(&listener, &path, &state))
+            .await
+            .map_err(|e| AuthError::Authorization(e.to_string()))??;
+
+        debug!(
+            "Exchanging the OAuth authorization code at {}",
+            self.token_url
+        );
+        let token = self
+            .request_token(json!({
+                "grant_type": "authorization_code",
+                "client_id": self.client_id,
+                "client_secret": self.client_secret,
+                "code": code,
+                "redirect_uri": redirect_uri,
+            }))
+            .await
+            .map_err(AuthError::Authorization)?;
+        if token.refresh_token.is_none() {
+            warn!(
+                "No OAuth refresh token was issued: add the offline_access scope to stay logged in"
+            );
+        }
+
+        let token = CachedToken {
+            access_token: token.access_token,
+            refresh_token: token.refresh_token,
+            expires_at: now() + token.expires_in,
+        };
+        self.save(&token);
+        *self.cached.lock().await = Some(token);
+        Ok(())
+    }
+
+    /// Posts a grant to the token endpoint. Errors are the status and body of
+    /// the response, or those of the request.
+    async fn request_token(&self, grant: serde_json::Value) -> Result<TokenResponse, String> {
+        let response = self
+            .client
+            .post(&self.token_url)
+            .json(&grant)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Status: {}, Body: {}", status, body));
+        }
+        response.json().await.map_err(|e| e.to_string())
+    }
+
+    fn save(&self, token: &CachedToken) {
+        let Some(path) = &self.cache_path else {
+            return;
+        };
+        let content = serde_json::to_string(token).expect("a token serializes");
+        if let Err(e) = write_private(path, &content) {
+            warn!("Failed to cache the OAuth token in {:?}: {}", path, e);
+        }
+    }
+}
+
+impl AuthStrategy for OAuth {
+    fn authorize(
+        &self,
+        request: RequestBuilder,
+    ) -> BoxFuture<'_, Result<RequestBuilder, AuthError>> {
+        async move {
+            let access_token = self.access_token().await?;
+            Ok(request.bearer_auth(access_token))
+        }
+        .boxed()
+    }
+}
+
+/// Waits for the browser to be redirected to `path`, and returns the
+/// authorization code it carries. Other requests, such as for the favicon,
+/// are answered with a 404.
+fn receive_code(listener: &TcpListener, path: &str, state: &str) -> Result<String, AuthError> {
+    loop {
+        let (mut stream, _) = listener.accept()?;
+        let mut request_line = String::new();
+        BufReader::new(&stream).read_line(&mut request_line)?;
+        let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+        let Ok(url) = Url::parse(&format!("http://localhost{}", target)) else {
+            continue;
+        };
+        if url.path() != path {
+            respond(&mut stream, "404 Not Found", "Not found");
+            continue;
+        }
+
+        let query: HashMap<_, _> = url.query_pairs().collect();
+        let result = if query.get("state").map(|s| s.as_ref()) != Some(state) {
+            Err(AuthError::Authorization(
+                "the callback does not match the login request".to_string(),
+            ))
+        } else if let Some(code) = query.get("code") {
+            Ok(code.to_string())
+        } else {
+            let error = query
+                .get("error_description")
+                .or_else(|| query.get("error"));
+            Err(AuthError::Authorization(
+                error.map_or("no authorization code".to_string(), |e| e.to_string()),
+            ))
+        };
+
+        match &result {
+            Ok(_) => respond(
+                &mut stream,
+                "200 OK",
+                "tedlt is authorized, you can close this page.",
+            ),
+            Err(e) => respond(&mut stream, "400 Bad Request", &e.to_string()),
+        }
+        return result;
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, message: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        message.len(),
+        message
+    );
+    stream.write_all(response.as_bytes()).ok();
+}
+
+/// Removes the cached OAuth tokens of an instance, returning the file they
+/// were in, if any.
+pub fn forget_oauth_tokens(instance: Option<&str>) -> io::Result<Option<PathBuf>> {
+    let Some(path) = token_cache_path(instance) else {
+        return Ok(None);
+    };
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(Some(path)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Where the OAuth tokens of an instance are cached.
+fn token_cache_path(instance: Option<&str>) -> Option<PathBuf> {
+    let name = instance.unwrap_or("default");
+    dirs::cache_dir().map(|dir| dir.join("tedlt").join(format!("oauth-{}.json", name)))
+}
+
+/// Writes a file that only the current user can read.
+pub(crate) fn write_private(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(content.as_bytes())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::StandIn;
+
+    async fn send(auth: &dyn AuthStrategy, url: &str) {
+        let request = auth.authorize(Client::new().get(url)).await.unwrap();
+        request.send().await.unwrap();
+    }
+
+    fn oauth(stand_in: &StandIn, cache_path: Option<PathBuf>) -> OAuth {
+        OAuth::new(
+            "client".to_string(),
+            "secret".to_string(),
+            Some("refresh-1".to_string()),
+            format!("{}/oauth/token", stand_in.url),
+            cache_path,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth() {
+        let stand_in = StandIn::start(vec![(200, "{}")]);
+        let auth = BasicAuth {
+            email: "me@example.com".to_string(),
+            api_token: "secret".to_string(),
+        };
+
+        send(&auth, &stand_in.url).await;
+
+        assert_eq!(
+            stand_in.requests()[0].headers["authorization"],
+            "Basic bWVAZXhhbXBsZS5jb206c2VjcmV0"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bearer_auth() {
+        let stand_in = StandIn::start(vec![(200, "{}")]);
+        let auth = BearerAuth {
+            token: "pat".to_string(),
+        };
+
+        send(&auth, &stand_in.url).await;
+
+        assert_eq!(
+            stand_in.requests()[0].headers["authorization"],
+            "Bearer pat"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oauth_caches_access_token() {
+        let stand_in = StandIn::start(vec![
+            (
+                200,
+                r#"{"access_token":"access-1","expires_in":3600,"refresh_token":"refresh-2"}"#,
+            ),
+            (200, "{}"),
+            (200, "{}"),
+        ]);
+        let cache_path =
+            std::env::temp_dir().join(format!("tedlt-oauth-cache-{}.json", std::process::id()));
+        let auth = oauth(&stand_in, Some(cache_path.clone()));
+        let api = format!("{}/rest/api/3/myself", stand_in.url);
+
+        send(&auth, &api).await;
+        send(&auth, &api).await;
+        let cache = fs::read_to_string(&cache_path);
+        fs::remove_file(&cache_path).ok();
+
+        let requests = stand_in.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/oauth/token");
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body["grant_type"], "refresh_token");
+        assert_eq!(body["refresh_token"], "refresh-1");
+        assert_eq!(body["client_secret"], "secret");
+        for request in &requests[1..] {
+            assert_eq!(request.headers["authorization"], "Bearer access-1");
+        }
+
+        let cache: CachedToken = serde_json::from_str(&cache.unwrap()).unwrap();
+        assert_eq!(cache.refresh_token.as_deref(), Some("refresh-2"));
+    }
+
+    #[tokio::test]
+    async fn test_oauth_refreshes_expired_token() {
+        let stand_in = StandIn::start(vec![
+            (
+                200,
+                r#"{"access_token":"access-1","expires_in":0,"refresh_token":"refresh-2"}"#,
+            ),
+            (200, "{}"),
+            (200, r#"{"access_token":"access-2","expires_in":3600}"#),
+            (200, "{}"),
+        ]);
+        let auth = oauth(&stand_in, None);
+        let api = format!("{}/rest/api/3/myself", stand_in.url);
+
+        send(&auth, &api).await;
+        send(&auth, &api).await;
+
+        let requests = stand_in.requests();
+        assert_eq!(requests[1].headers["authorization"], "Bearer access-1");
+        let body: serde_json::Value = serde_json::from_str(&requests[2].body).unwrap();
+        assert_eq!(body["refresh_token"], "refresh-2");
+        assert_eq!(requests[3].headers["authorization"], "Bearer access-2");
+    }
+
+    #[tokio::test]
+    async fn test_oauth_falls_back_to_configured_refresh_token() {
+        let stand_in = StandIn::start(vec![
+            (403, r#"{"error":"invalid_grant"}"#),
+            (200, r#"{"access_token":"access-1","expires_in":3600}"#),
+        ]);
+        let cache_path =
+            std::env::temp_dir().join(format!("tedlt-oauth-stale-{}.json", std::process::id()));
+        fs::write(
+            &cache_path,
+            r#"{"access_token":"old","refresh_token":"stale","expires_at":0}"#,
+        )
+        .unwrap();
+        let auth = oauth(&stand_in, Some(cache_path.clone()));
+
+        let access_token = auth.access_token().await;
+        fs::remove_file(&cache_path).ok();
+
+        assert_eq!(access_token.unwrap(), "access-1");
+        let requests = stand_in.requests();
+        assert!(requests[0].body.contains("stale"));
+        assert!(requests[1].body.contains("refresh-1"));
+    }
+
+    /// Follows the redirect of the authorization page, as the browser would
+    /// once the user agreed, with the state of the page and `code`.
+    fn redirect_back(page: &str, code: &str) -> std::thread::JoinHandle<String> {
+        let page = Url::parse(page).unwrap();
+        let query: HashMap<_, _> = page.query_pairs().into_owned().collect();
+        let mut callback = Url::parse(&query["redirect_uri"]).unwrap();
+        callback
+            .query_pairs_mut()
+            .append_pair("code", code)
+            .append_pair("state", &query["state"]);
+
+        std::thread::spawn(move || {
+            use std::io::Read;
+
+            let address = format!("127.0.0.1:{}", callback.port().unwrap());
+            let mut stream = TcpStream::connect(&address).unwrap();
+            write!(
+                stream,
+                "GET {}?{} HTTP/1.1\r\nHost: {}\r\n\r\n",
+                callback.path(),
+                callback.query().unwrap(),
+                address
+            )
+            .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        })
+    }
+
+    #[tokio::test]
+    async fn test_oauth_authorization_code_flow() {
+        let stand_in = StandIn::start(vec![
+            (
+                200,
+                r#"{"access_token":"access-1","expires_in":3600,"refresh_token":"refresh-1"}"#,
+            ),
+            (200, "{}"),
+        ]);
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let redirect_uri = format!("http://localhost:{}/callback", port);
+        let cache_path =
+            std::env::temp_dir().join(format!("tedlt-oauth-login-{}.json", std::process::id()));
+        let auth = OAuth::new(
+            "client".to_string(),
+            "secret".to_string(),
+            None,
+            format!("{}/oauth/token", stand_in.url),
+            Some(cache_path.clone()),
+        );
+
+        let mut page = String::new();
+        let mut browser = None;
+        auth.authorize(
+            ATLASSIAN_AUTHORIZE_URL,
+            &redirect_uri,
+            "read:jira-work offline_access",
+            |url| {
+                page = url.to_string();
+                browser = Some(redirect_back(url, "code-1"));
+            },
+        )
+        .await
+        .unwrap();
+        send(&auth, &format!("{}/rest/api/3/myself", stand_in.url)).await;
+        let response = browser.unwrap().join().unwrap();
+        let cache = fs::read_to_string(&cache_path);
+        fs::remove_file(&cache_path).ok();
+
+        assert!(page.starts_with("https://auth.atlassian.com/authorize?audience=api.atlassian.com&client_id=client&scope=read%3Ajira-work+offline_access"));
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        let requests = stand_in.requests();
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body["grant_type"], "authorization_code");
+        assert_eq!(body["code"], "code-1");
+        assert_eq!(body["redirect_uri"], redirect_uri);
+        assert_eq!(requests[1].headers["authorization"], "Bearer access-1");
+        let cache: CachedToken = serde_json::from_str(&cache.unwrap()).unwrap();
+        assert_eq!(cache.refresh_token.as_deref(), Some("refresh-1"));
+    }
+
+    #[tokio::test]
+    async fn test_oauth_redirect_uri_must_be_local() {
+        let auth = OAuth::new(
+            "client".to_string(),
+            "secret".to_string(),
+            None,
+            ATLASSIAN_TOKEN_URL.to_string(),
+            None,
+        );
+
+        for redirect_uri in [
+            "https://example.com:8765/callback",
+            "http://localhost/callback",
+        ] {
+            let result = auth
+                .authorize(ATLASSIAN_AUTHORIZE_URL, redirect_uri, "", |_| {
+                    panic!("no page should be shown")
+                })
+                .await;
+            assert!(matches!(result, Err(AuthError::InvalidRedirectUri(_))));
+        }
+    }
+}
@@ -0,0 +1,592 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::config::ProfilesResolver;
+
+#[derive(Error, Debug)]
+pub enum BatchError {
+    #[error("Failed to read tickets file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to read CSV: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("Line {line}: {message}")]
+    InvalidRow { line: usize, message: String },
+}
+
+/// The format of a tickets file, detected from its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum InputFormat {
+    /// One title per line.
+    Lines,
+    /// A header row with a `title` (or `summary`) column.
+    Csv,
+    /// One JSON object per line with a `title` (or `summary`) key.
+    Jsonl,
+}
+
+impl InputFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Self::Csv,
+            Some(ext)
+                if ext.eq_ignore_ascii_case("jsonl") || ext.eq_ignore_ascii_case("ndjson") =>
+            {
+                Self::Jsonl
+            }
+            _ => Self::Lines,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Lines => "txt",
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// One ticket to create, as read from the command line or a tickets file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TicketRow {
+    pub title: String,
+    pub description: Option<String>,
+    pub profiles: Vec<String>,
+    /// Field overrides from the extra columns or keys, merged over the profiles.
+    pub fields: Map<String, Value>,
+    raw: RawRow,
+}
+
+/// The row as it was read, so that failed rows can be written back unchanged.
+#[derive(Debug, Clone, PartialEq)]
+enum RawRow {
+    Line(String),
+    Csv(csv::StringRecord),
+}
+
+/// The tickets of a `create` invocation.
+#[derive(Debug)]
+pub struct TicketBatch {
+    pub format: InputFormat,
+    pub rows: Vec<TicketRow>,
+    csv_headers: Option<csv::StringRecord>,
+}
+
+impl TicketBatch {
+    pub fn from_titles(titles: Vec<String>) -> Self {
+        let rows = titles
+            .into_iter()
+            .map(|title| TicketRow {
+                raw: RawRow::Line(title.clone()),
+                title,
+                description: None,
+                profiles: vec![],
+                fields: Map::new(),
+            })
+            .collect();
+
+        Self {
+            format: InputFormat::Lines,
+            rows,
+            csv_headers: None,
+        }
+    }
+
+    /// Reads a tickets file, or stdin when `path` is `-`.
+    pub fn read(path: &Path, format: Option<InputFormat>) -> Result<Self, BatchError> {
+        let format = format.unwrap_or_else(|| InputFormat::from_path(path));
+
+        let content = if path.as_os_str() == "-" {
+            let mut content = String::new();
+            std::io::stdin().read_to_string(&mut content)?;
+            content
+        } else {
+            std::fs::read_to_string(path)?
+        };
+
+        Self::parse(&content, format)
+    }
+
+    pub fn parse(content: &str, format: InputFormat) -> Result<Self, BatchError> {
+        match format {
+            InputFormat::Lines => Ok(Self::from_titles(
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(String::from)
+                    .collect(),
+            )),
+            InputFormat::Csv => Self::parse_csv(content),
+            InputFormat::Jsonl => Self::parse_jsonl(content),
+        }
+    }
+
+    fn parse_csv(content: &str) -> Result<Self, BatchError> {
+        let mut reader = csv::Reader::from_reader(content.as_bytes());
+        let headers = reader.headers()?.clone();
+
+        let column = |names: &[&str]| {
+            headers
+                .iter()
+                .position(|h| names.iter().any(|n| h.trim().eq_ignore_ascii_case(n)))
+        };
+        let title_column = column(&["title", "summary"]).ok_or(BatchError::InvalidRow {
+            line: 1,
+            message: "CSV input needs a 'title' or 'summary' column".to_string(),
+        })?;
+        let description_column = column(&["description"]);
+        let profile_column = column(&["profile", "profiles"]);
+
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            let line = record.position().map_or(0, |p| p.line() as usize);
+
+            let mut row = TicketRow {
+                title: record
+                    .get(title_column)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+                description: None,
+                profiles: vec![],
+                fields: Map::new(),
+                raw: RawRow::Csv(record.clone()),
+            };
+
+            for (index, (header, cell)) in headers.iter().zip(record.iter()).enumerate() {
+                let cell = cell.trim();
+                if cell.is_empty() || index == title_column {
+                    continue;
+                }
+
+                if Some(index) == description_column {
+                    row.description = Some(cell.to_string());
+                } else if Some(index) == profile_column {
+                    row.profiles = split_profiles(cell);
+                } else {
+                    let (path, value) = parse_cell(header.trim(), cell, line)?;
+                    insert_field(&mut row.fields, path, value);
+                }
+            }
+
+            check_title(&row, line)?;
+            rows.push(row);
+        }
+
+        Ok(Self {
+            format: InputFormat::Csv,
+            rows,
+            csv_headers: Some(headers),
+        })
+    }
+
+    fn parse_jsonl(content: &str) -> Result<Self, BatchError> {
+        let mut rows = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line_number = index + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let invalid = |message: String| BatchError::InvalidRow {
+                line: line_number,
+                message,
+            };
+
+            let object = match serde_json::from_str::<Value>(line) {
+                Ok(Value::Object(object)) => object,
+                Ok(_) => return Err(invalid("expected a JSON object".to_string())),
+                Err(e) => return Err(invalid(e.to_string())),
+            };
+
+            let mut row = TicketRow {
+                title: String::new(),
+                description: None,
+                profiles: vec![],
+                fields: Map::new(),
+                raw: RawRow::Line(line.to_string()),
+            };
+
+            for (key, value) in object {
+                match (key.as_str(), value) {
+                    ("title" | "summary", Value::String(title)) => row.title = title,
+                    ("description", Value::String(description)) => {
+                        row.description = Some(description)
+                    }
+                    ("profile" | "profiles", Value::String(profiles)) => {
+                        row.profiles = split_profiles(&profiles)
+                    }
+                    ("profile" | "profiles", Value::Array(profiles)) => {
+                        row.profiles = profiles
+                            .into_iter()
+                            .map(|p| match p {
+                                Value::String(p) => Ok(p),
+                                other => Err(invalid(format!("invalid profile name: {}", other))),
+                            })
+                            .collect::<Result<_, _>>()?
+                    }
+                    ("fields", Value::Object(fields)) => {
+                        for (field, value) in fields {
+                            insert_field(&mut row.fields, &field, value);
+                        }
+                    }
+                    (
+                        "title" | "summary" | "description" | "profile" | "profiles" | "fields",
+                        _,
+                    ) => {
+                        return Err(invalid(format!("invalid value for '{}'", key)));
+                    }
+                    (_, value) => insert_field(&mut row.fields, &key, value),
+                }
+            }
+
+            check_title(&row, line_number)?;
+            rows.push(row);
+        }
+
+        Ok(Self {
+            format: InputFormat::Jsonl,
+            rows,
+            csv_headers: None,
+        })
+    }
+
+    /// The retry file used when none is given: `tedlt-retry.<ext>` in the current directory.
+    pub fn default_retry_path(&self) -> PathBuf {
+        PathBuf::from(format!("tedlt-retry.{}", self.format.extension()))
+    }
+
+    /// Writes the rows at `failed` (indexes into `rows`) in the input format,
+    /// so the file can be passed back to `--from-file`.
+    pub fn write_retry_file(&self, failed: &[usize], path: &Path) -> Result<(), BatchError> {
+        let failed_rows = failed.iter().filter_map(|&index| self.rows.get(index));
+
+        match &self.csv_headers {
+            Some(headers) => {
+                let mut writer = csv::Writer::from_path(path)?;
+                writer.write_record(headers)?;
+                for row in failed_rows {
+                    if let RawRow::Csv(record) = &row.raw {
+                        writer.write_record(record)?;
+                    }
+                }
+                writer.flush()?;
+            }
+            None => {
+                let mut content = String::new();
+                for row in failed_rows {
+                    if let RawRow::Line(line) = &row.raw {
+                        content.push_str(line);
+                        content.push('\n');
+                    }
+                }
+                std::fs::write(path, content)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn check_title(row: &TicketRow, line: usize) -> Result<(), BatchError> {
+    if row.title.trim().is_empty() {
+        return Err(BatchError::InvalidRow {
+            line,
+            message: "missing title".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn split_profiles(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// The suffix of the columns whose cells are JSON, such as `components:json`.
+const JSON_SUFFIX: &str = ":json";
+
+/// The field path of a column and the value of its cell. Cells are strings,
+/// converted to the type of the field before creating the ticket, unless the
+/// column ends with `:json`.
+fn parse_cell<'a>(
+    header: &'a str,
+    cell: &str,
+    line: usize,
+) -> Result<(&'a str, Value), BatchError> {
+    let json_path = header
+        .len()
+        .checked_sub(JSON_SUFFIX.len())
+        .filter(|&at| header.is_char_boundary(at))
+        .filter(|&at| header[at..].eq_ignore_ascii_case(JSON_SUFFIX))
+        .map(|at| header[..at].trim_end());
+
+    match json_path {
+        Some(path) => {
+            let value = serde_json::from_str(cell).map_err(|e| BatchError::InvalidRow {
+                line,
+                message: format!("column '{}' is not valid JSON: {}", header, e),
+            })?;
+            Ok((path, value))
+        }
+        None => Ok((header, Value::String(cell.to_string()))),
+    }
+}
+
+/// Inserts `value` at a dotted path (`priority.id`), merging with what is there.
+fn insert_field(fields: &mut Map<String, Value>, path: &str, value: Value) {
+    let nested = path.rsplit('.').fold(value, |value, key| {
+        Value::Object(Map::from_iter([(key.to_string(), value)]))
+    });
+
+    if let Value::Object(nested) = nested {
+        for (key, value) in nested {
+            let merged = match fields.remove(&key) {
+                Some(existing) => ProfilesResolver::deep_merge_json(existing, value),
+                None => value,
+            };
+            fields.insert(key, merged);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(InputFormat::from_path(Path::new("a.csv")), InputFormat::Csv);
+        assert_eq!(InputFormat::from_path(Path::new("a.CSV")), InputFormat::Csv);
+        assert_eq!(
+            InputFormat::from_path(Path::new("a.jsonl")),
+            InputFormat::Jsonl
+        );
+        assert_eq!(
+            InputFormat::from_path(Path::new("a.ndjson")),
+            InputFormat::Jsonl
+        );
+        assert_eq!(
+            InputFormat::from_path(Path::new("a.txt")),
+            InputFormat::Lines
+        );
+        assert_eq!(InputFormat::from_path(Path::new("-")), InputFormat::Lines);
+    }
+
+    #[test]
+    fn test_parse_lines_skips_blank_lines() {
+        let batch =
+            TicketBatch::parse("Fix bug\n\n  Add feature #api \n", InputFormat::Lines).unwrap();
+        let titles: Vec<_> = batch.rows.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["Fix bug", "Add feature #api"]);
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let content = "title,profile,priority.id,labels:json,customfield_10016\n\
+                       Fix bug,\"bug, backend\",2,\"[\"\"a\"\"]\",5\n\
+                       Add feature,,,,\n";
+
+        let batch = TicketBatch::parse(content, InputFormat::Csv).unwrap();
+        assert_eq!(batch.rows.len(), 2);
+
+        let first = &batch.rows[0];
+        assert_eq!(first.title, "Fix bug");
+        assert_eq!(first.profiles, vec!["bug", "backend"]);
+        assert_eq!(
+            Value::Object(first.fields.clone()),
+            json!({
+                "priority": { "id": "2" },
+                "labels": ["a"],
+                "customfield_10016": "5"
+            })
+        );
+
+        let second = &batch.rows[1];
+        assert_eq!(second.title, "Add feature");
+        assert!(second.profiles.is_empty());
+        assert!(second.fields.is_empty());
+    }
+
+    #[test]
+    fn test_parse_csv_quoted_strings_and_merged_paths() {
+        let content = "Summary,Description,priority.id,priority.name,env,flag,data\n\
+                       T,Some *markdown*,\"\"\"2\"\"\",High,prod,true,{}\n";
+
+        let batch = TicketBatch::parse(content, InputFormat::Csv).unwrap();
+        let row = &batch.rows[0];
+        assert_eq!(row.title, "T");
+        assert_eq!(row.description.as_deref(), Some("Some *markdown*"));
+        assert_eq!(
+            Value::Object(row.fields.clone()),
+            json!({
+                "priority": { "id": "\"2\"", "name": "High" },
+                "env": "prod",
+                "flag": "true",
+                "data": "{}"
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_json_columns() {
+        let content = "title,components:JSON,priority.id:json\n\
+                       T,\"[{\"\"id\"\": \"\"1\"\"}]\",2\n";
+
+        let batch = TicketBatch::parse(content, InputFormat::Csv).unwrap();
+        assert_eq!(
+            Value::Object(batch.rows[0].fields.clone()),
+            json!({ "components": [{ "id": "1" }], "priority": { "id": 2 } })
+        );
+
+        let result = TicketBatch::parse("title,labels:json\nA,[]\nB,{\n", InputFormat::Csv);
+        assert!(matches!(
+            result,
+            Err(BatchError::InvalidRow { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_csv_requires_title_column() {
+        let result = TicketBatch::parse("name\nfoo\n", InputFormat::Csv);
+        assert!(matches!(
+            result,
+            Err(BatchError::InvalidRow { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_csv_rejects_empty_title() {
+        let result = TicketBatch::parse("title,env\nA,x\n,y\n", InputFormat::Csv);
+        assert!(matches!(
+            result,
+            Err(BatchError::InvalidRow { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_jsonl() {
+        let content = r#"{"title": "Fix bug", "profiles": ["bug"], "fields": {"priority": {"id": "2"}}}
+
+{"summary": "Add feature", "profile": "backend", "description": "Body", "labels": ["x"], "priority.name": "Low"}
+"#;
+
+        let batch = TicketBatch::parse(content, InputFormat::Jsonl).unwrap();
+        assert_eq!(batch.rows.len(), 2);
+
+        assert_eq!(batch.rows[0].title, "Fix bug");
+        assert_eq!(batch.rows[0].profiles, vec!["bug"]);
+        assert_eq!(
+            Value::Object(batch.rows[0].fields.clone()),
+            json!({ "priority": { "id": "2" } })
+        );
+
+        assert_eq!(batch.rows[1].title, "Add feature");
+        assert_eq!(batch.rows[1].profiles, vec!["backend"]);
+        assert_eq!(batch.rows[1].description.as_deref(), Some("Body"));
+        assert_eq!(
+            Value::Object(batch.rows[1].fields.clone()),
+            json!({ "labels": ["x"], "priority": { "name": "Low" } })
+        );
+    }
+
+    #[test]
+    fn test_parse_jsonl_errors_report_line() {
+        let result = TicketBatch::parse("{\"title\": \"ok\"}\n[1]\n", InputFormat::Jsonl);
+        assert!(matches!(
+            result,
+            Err(BatchError::InvalidRow { line: 2, .. })
+        ));
+
+        let result = TicketBatch::parse("{\"title\": 3}\n", InputFormat::Jsonl);
+        assert!(matches!(
+            result,
+            Err(BatchError::InvalidRow { line: 1, .. })
+        ));
+
+        let result = TicketBatch::parse("{\"labels\": []}\n", InputFormat::Jsonl);
+        assert!(matches!(
+            result,
+            Err(BatchError::InvalidRow { line: 1, .. })
+        ));
+
+        let result = TicketBatch::parse("not json\n", InputFormat::Jsonl);
+        assert!(matches!(
+            result,
+            Err(BatchError::InvalidRow { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_default_retry_path() {
+        let batch = TicketBatch::parse("title\nA\n", InputFormat::Csv).unwrap();
+        assert_eq!(batch.default_retry_path(), PathBuf::from("tedlt-retry.csv"));
+
+        let batch = TicketBatch::from_titles(vec!["A".to_string()]);
+        assert_eq!(batch.default_retry_path(), PathBuf::from("tedlt-retry.txt"));
+    }
+
+    #[test]
+    fn test_write_retry_file_csv() {
+        let content = "title,labels:json\nA,\"[\"\"x\"\"]\"\nB,\nC,\"\"\"y\"\"\"\n";
+        let batch = TicketBatch::parse(content, InputFormat::Csv).unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("tedlt-test-retry-{}.csv", std::process::id()));
+        batch.write_retry_file(&[0, 2], &path).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(
+            written,
+            "title,labels:json\nA,\"[\"\"x\"\"]\"\nC,\"\"\"y\"\"\"\n"
+        );
+
+        let reread = TicketBatch::parse(&written, InputFormat::Csv).unwrap();
+        assert_eq!(
+            reread.rows,
+            vec![batch.rows[0].clone(), batch.rows[2].clone()]
+        );
+    }
+
+    #[test]
+    fn test_write_retry_file_lines() {
+        let batch = TicketBatch::from_titles(vec!["A #x".to_string(), "B".to_string()]);
+
+        let path =
+            std::env::temp_dir().join(format!("tedlt-test-retry-{}.txt", std::process::id()));
+        batch.write_retry_file(&[1], &path).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(written, "B\n");
+    }
+
+    #[test]
+    fn test_insert_field_nested_paths() {
+        let mut fields = Map::new();
+        insert_field(&mut fields, "a.b.c", json!(1));
+        insert_field(&mut fields, "a.b.d", json!(2));
+        insert_field(&mut fields, "labels", json!(["x"]));
+        insert_field(&mut fields, "labels", json!(["y"]));
+
+        assert_eq!(
+            Value::Object(fields),
+            json!({ "a": { "b": { "c": 1, "d": 2 } }, "labels": ["x", "y"] })
+        );
+    }
+}
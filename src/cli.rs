@@ -1,5 +1,11 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 
+use crate::batch::InputFormat;
+use crate::config::{CONFIG_ENV_VAR, FieldOverride, FieldOverrides, parse_property};
+use crate::output::OutputFormat;
+
 /// A CLI tool to interact with Jira and create tickets efficiently.
 #[derive(Parser, Debug)]
 #[command(
@@ -9,8 +15,18 @@ use clap::{Parser, Subcommand};
     long_about = None,
     after_help = "EXAMPLES:\n  \
         tedlt create \"Fix login bug\" --profile work\n  \
+        tedlt create \"Fix login bug #auth !high :bug\"\n  \
+        tedlt create \"Fix bug\" \"Add feature\" \"Update docs\" -p work\n  \
+        tedlt create --from-file tickets.csv\n  \
+        tedlt create \"Fix login bug\" --format '{key} {url}'\n  \
+        tedlt create \"Hotfix\" -p bug --field labels+=hotfix --set team=core\n  \
         tedlt info project KAN\n  \
-        tedlt info ticket KAN-123"
+        tedlt info fields -o csv > fields.csv\n  \
+        tedlt info ticket KAN-123\n  \
+        tedlt config paths\n  \
+        tedlt config validate\n  \
+        tedlt config show -p work -p bug\n  \
+        tedlt auth status -p work"
 )]
 pub struct Args {
     /// Enable verbose logging.
@@ -25,6 +41,16 @@ pub struct Args {
     #[arg(long, global = true)]
     pub project_key: Option<String>,
 
+    /// Use only this configuration file instead of discovering and merging
+    /// `tedlt.jsonc` files.
+    #[arg(long, global = true, env = CONFIG_ENV_VAR, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// How to print the results. `info` defaults to a table in a terminal and
+    /// to json otherwise, `create` to the URLs of the tickets.
+    #[arg(short, long, global = true, value_enum, value_name = "FORMAT")]
+    pub output: Option<OutputFormat>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -41,19 +67,120 @@ pub enum Commands {
     /// Initialize the configuration file in the home directory.
     #[command()]
     Init(InitCommand),
+
+    /// Inspect the configuration files.
+    Config(ConfigCommand),
+
+    /// Check, save or remove the credentials of a Jira instance.
+    Auth(AuthCommand),
 }
 
 /// Arguments for the 'create' command.
 #[derive(Parser, Debug)]
 pub struct CreateCommand {
-    /// The title of the ticket to create.
-    #[arg(required = true)]
-    pub title: String,
+    /// The title(s) of the ticket(s) to create. Each title creates one ticket.
+    ///
+    /// Inline tokens set fields and are removed from the summary:
+    /// `#label`, `+component`, `!priority`, `^PARENT-1`, `@me` or `@user`,
+    /// and `:name` (a profile, or else an issue type).
+    #[arg(required_unless_present_any = ["edit", "from_file"])]
+    pub titles: Vec<String>,
+
+    /// Create one ticket per line of a file, or per row of a `.csv` or `.jsonl` file.
+    /// Use `-` to read from stdin.
+    ///
+    /// CSV columns and JSONL keys: `title` (or `summary`), `description`,
+    /// `profile` (comma-separated), and any other name is a field override
+    /// (`priority.id`, `labels`, ...).
+    #[arg(short = 'f', long, value_name = "PATH", conflicts_with_all = ["titles", "edit"])]
+    pub from_file: Option<PathBuf>,
+
+    /// The format of `--from-file`, detected from the extension by default.
+    #[arg(long, value_enum, requires = "from_file")]
+    pub input_format: Option<InputFormat>,
+
+    /// Number of tickets created in parallel.
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    pub concurrency: u16,
+
+    /// Send the tickets with Jira's bulk endpoint, 50 per request, instead of
+    /// one request per ticket. Faster for large imports.
+    #[arg(long)]
+    pub bulk: bool,
+
+    /// Where to write the rows that failed, in the input format [default: tedlt-retry.<ext>].
+    #[arg(long, value_name = "PATH")]
+    pub retry_file: Option<PathBuf>,
 
     /// The name of profile(s) to use for creating the ticket.
     /// Can be specified multiple times. Profiles are merged left-to-right.
     #[arg(short, long, value_name = "PROFILE")]
     pub profile: Vec<String>,
+
+    /// Print the request that would be sent to Jira instead of creating the ticket.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Send the fields as they are, without converting them to the types of the
+    /// create screen of the issue type or checking them against it.
+    #[arg(long)]
+    pub no_validate: bool,
+
+    /// The ticket description, in Markdown.
+    #[arg(short, long, conflicts_with = "description_file")]
+    pub description: Option<String>,
+
+    /// Read the ticket description (Markdown) from a file, or from stdin with `-`.
+    #[arg(long, value_name = "PATH")]
+    pub description_file: Option<PathBuf>,
+
+    /// Write the summary and description in $EDITOR, like `git commit`.
+    /// The first line is the summary, the rest is the description.
+    #[arg(short, long)]
+    pub edit: bool,
+
+    /// Print each created ticket with a template instead of its URL, such as
+    /// '{key} {url}'. Placeholders: {key}, {id}, {self} (the API URL), {url}.
+    /// Cannot be combined with --output.
+    #[arg(long, value_name = "TEMPLATE", conflicts_with = "quiet")]
+    pub format: Option<String>,
+
+    /// Print only the key of each created ticket. Cannot be combined with
+    /// --output.
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    #[command(flatten)]
+    pub overrides: OverrideArgs,
+}
+
+/// The fields and properties set over the configuration.
+#[derive(clap::Args, Debug, Clone)]
+pub struct OverrideArgs {
+    /// Set a field over the profiles: `path=value` replaces the value, such as
+    /// `priority.id=2`, and `path+=value` appends to a list, such as
+    /// `labels+=hotfix`. Repeatable.
+    #[arg(long = "field", value_name = "PATH=VALUE", value_parser = FieldOverride::parse)]
+    pub fields: Vec<FieldOverride>,
+
+    /// Like `--field`, with a JSON value: `components=[{"id":"10100"}]`.
+    /// Applied after the `--field` values.
+    #[arg(long, value_name = "PATH=JSON", value_parser = FieldOverride::parse_json)]
+    pub field_json: Vec<FieldOverride>,
+
+    /// Override a property of the config file, before templates use it.
+    /// Repeatable.
+    #[arg(long = "set", value_name = "NAME=VALUE", value_parser = parse_property)]
+    pub properties: Vec<(String, String)>,
+}
+
+impl OverrideArgs {
+    pub fn field_overrides(self) -> FieldOverrides {
+        FieldOverrides {
+            fields: self.fields.into_iter().chain(self.field_json).collect(),
+            properties: self.properties,
+        }
+    }
 }
 
 /// Arguments for the 'info' command.
@@ -68,6 +195,24 @@ pub struct InfoCommand {
     pub profile: Vec<String>,
 }
 
+/// How many items of a list to fetch.
+#[derive(clap::Args, Debug, Clone, Copy)]
+pub struct PageArgs {
+    /// The maximum number of items to list.
+    #[arg(long, value_name = "N", default_value_t = 50, conflicts_with = "all")]
+    pub limit: usize,
+    /// List every item, however many pages it takes.
+    #[arg(long)]
+    pub all: bool,
+}
+
+impl PageArgs {
+    /// The maximum number of items to list, `None` for all of them.
+    pub fn limit(&self) -> Option<usize> {
+        (!self.all).then_some(self.limit)
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub enum InfoSubCommand {
     /// Fetch metadata for a specific project.
@@ -88,6 +233,8 @@ pub enum InfoSubCommand {
         /// The board ID to find epics for.
         #[arg(long)]
         board_id: Option<u64>,
+        #[command(flatten)]
+        page: PageArgs,
     },
 
     /// Inspect the raw JSON data of an existing ticket.
@@ -97,6 +244,9 @@ pub enum InfoSubCommand {
         /// The ticket key (e.g., "KAN-123").
         #[arg(required = true)]
         key: String,
+        /// Show the fields that have a `field_aliases` entry under their alias.
+        #[arg(long)]
+        aliases: bool,
     },
 
     /// List all available boards.
@@ -106,6 +256,8 @@ pub enum InfoSubCommand {
         /// Optionally filter boards by a project key.
         #[arg(long)]
         project: Option<String>,
+        #[command(flatten)]
+        page: PageArgs,
     },
     /// Fetch metadata about the fields for a given issue type in a project.
     ///
@@ -136,6 +288,80 @@ pub struct InitCommand {
     pub force: bool,
 }
 
+/// Arguments for the 'config' command.
+#[derive(Parser, Debug)]
+pub struct ConfigCommand {
+    #[command(subcommand)]
+    pub subcmd: ConfigSubCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigSubCommand {
+    /// List the configuration files that are loaded, lowest precedence first.
+    Paths,
+
+    /// Check the configuration and report every problem found.
+    ///
+    /// Resolves every profile and its property references, and warns about
+    /// unknown keys, unused properties and profile names that titles and input
+    /// files cannot select.
+    Validate,
+
+    /// Print the JSON Schema of the configuration file.
+    ///
+    /// Reference it from `$schema` in `tedlt.jsonc` for completion and
+    /// validation in editors.
+    Schema,
+
+    /// Print the resolved configuration and where each value comes from.
+    ///
+    /// Every value is annotated with the profile and file that set it, or the
+    /// environment variable, CLI flag or properties it was taken from.
+    Show {
+        /// The name of profile(s) to resolve, like `create --profile`.
+        /// Can be specified multiple times. Profiles are merged left-to-right.
+        #[arg(short, long, value_name = "PROFILE")]
+        profile: Vec<String>,
+
+        #[command(flatten)]
+        overrides: OverrideArgs,
+    },
+}
+
+/// Arguments for the 'auth' command.
+#[derive(Parser, Debug)]
+pub struct AuthCommand {
+    #[command(subcommand)]
+    pub subcmd: AuthSubCommand,
+
+    /// The name of profile(s) selecting the instance, like `create --profile`.
+    /// Can be specified multiple times. Profiles are merged left-to-right.
+    #[arg(short, long, global = true, value_name = "PROFILE")]
+    pub profile: Vec<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuthSubCommand {
+    /// Check the credentials by fetching the current user from Jira.
+    ///
+    /// Prints the account, the site and the auth method.
+    Status,
+
+    /// Prompt for an email and API token, check them, and save them.
+    ///
+    /// They are saved in `credentials.json` in the config directory, readable
+    /// only by you, and used when no environment variable or `env_file` sets
+    /// the token.
+    Login {
+        /// The account email, prompted for if not given.
+        #[arg(long)]
+        email: Option<String>,
+    },
+
+    /// Remove the saved credentials.
+    Logout,
+}
+
 impl Args {
     pub fn parse_args() -> Self {
         Parser::parse()
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::{
+    AppError, auth,
+    cli::{AuthCommand, AuthSubCommand},
+    config::{AuthMethod, CliOverrides, ConfigFile, Instance, ResolvedSite},
+    credential_store::{self, CredentialStore},
+    env::{API_TOKEN_VAR, Credentials, EMAIL_VAR},
+    jira::{JiraClient, JiraError, User},
+    retry::RetryPolicy,
+};
+
+pub async fn handle_command(
+    cmd: AuthCommand,
+    config_file: &ConfigFile,
+    cli_overrides: CliOverrides,
+) -> Result<(), AppError> {
+    let site = config_file.resolve_site(&cmd.profile, cli_overrides)?;
+
+    match cmd.subcmd {
+        AuthSubCommand::Status => status(&site).await,
+        AuthSubCommand::Login { email } => login(&site, email).await,
+        AuthSubCommand::Logout => logout(&site),
+    }
+}
+
+async fn status(config: &ResolvedSite) -> Result<(), AppError> {
+    let credentials = Credentials::load(&config.instance, &config.jira_url)?;
+    let client = client(config, credentials);
+    let user = client.get_myself().await?;
+
+    println!("Site:     {}", config.jira_url);
+    let instance = config.instance.name.as_deref().unwrap_or("(top level)");
+    println!("Instance: {}", instance);
+    println!("Auth:     {}", config.instance.auth.name());
+    println!("API:      v{}", client.api().await);
+    println!("Account:  {}", account(&user));
+
+    Ok(())
+}
+
+async fn login(config: &ResolvedSite, email: Option<String>) -> Result<(), AppError> {
+    let instance = &config.instance;
+    let (credentials, vars) = match &instance.auth {
+        AuthMethod::Basic => {
+            let email = match email {
+                Some(email) => email,
+                None => prompt_email(instance.email.as_deref())?,
+            };
+            let api_token = rpassword::prompt_password("API token: ")?;
+            let vars = HashMap::from([
+                (EMAIL_VAR.to_string(), email.clone()),
+                (API_TOKEN_VAR.to_string(), api_token.clone()),
+            ]);
+            (Credentials::Basic { email, api_token }, vars)
+        }
+        AuthMethod::Bearer => {
+            let token = rpassword::prompt_password("Personal access token: ")?;
+            let vars = HashMap::from([(API_TOKEN_VAR.to_string(), token.clone())]);
+            (Credentials::Bearer { token }, vars)
+        }
+        AuthMethod::OAuth { .. } => return oauth_login(config).await,
+    };
+
+    let user = client(config, credentials)
+        .get_myself()
+        .await
+        .map_err(|e| AppError::InvalidInput(format!("The credentials were not saved: {}", e)))?;
+
+    let mut store = CredentialStore::open(CredentialStore::default_path()?)?;
+    store.set(
+        &credential_store::key(instance.name.as_deref(), &config.jira_url),
+        vars,
+    );
+    store.save()?;
+
+    println!("Logged in to {} as {}", config.jira_url, account(&user));
+    println!("Credentials saved to {}", store.path().display());
+
+    Ok(())
+}
+
+/// Authorizes tedlt in the browser. The client secret is read like for any
+/// request, and the tokens are cached instead of saved with the credentials.
+async fn oauth_login(config: &ResolvedSite) -> Result<(), AppError> {
+    let credentials = Credentials::load(&config.instance, &config.jira_url)?;
+    let strategy = auth::oauth_login(credentials, &config.instance, |url| {
+        println!(
+            "Open this page to authorize tedlt, then come back here:\n\n  {}\n",
+            url
+        );
+    })
+    .await
+    .map_err(JiraError::from)?;
+
+    let client = JiraClient::new(
+        config.jira_url.clone(),
+        String::new(),
+        strategy,
+        config.instance.api_version,
+    );
+    let user = client.get_myself().await?;
+    println!("Logged in to {} as {}", config.jira_url, account(&user));
+
+    Ok(())
+}
+
+fn logout(site: &ResolvedSite) -> Result<(), AppError> {
+    let instance = &site.instance;
+    if let AuthMethod::OAuth { .. } = instance.auth {
+        match auth::forget_oauth_tokens(instance.name.as_deref())? {
+            Some(path) => println!(
+                "Removed the OAuth tokens of {} from {}",
+                instance_name(instance),
+                path.display()
+            ),
+            None => println!("No OAuth tokens cached for {}", instance_name(instance)),
+        }
+        return Ok(());
+    }
+
+    let mut store = CredentialStore::open(CredentialStore::default_path()?)?;
+    if store.remove(&credential_store::key(
+        instance.name.as_deref(),
+        &site.jira_url,
+    )) {
+        store.save()?;
+        println!(
+            "Removed the credentials of {} from {}",
+            instance_name(instance),
+            store.path().display()
+        );
+    } else {
+        println!("No saved credentials for {}", instance_name(instance));
+    }
+
+    Ok(())
+}
+
+/// A client for the site. The `auth` commands only ask for the current user,
+/// so no project is needed.
+fn client(site: &ResolvedSite, credentials: Credentials) -> JiraClient {
+    JiraClient::new(
+        site.jira_url.clone(),
+        String::new(),
+        auth::strategy(credentials, &site.instance),
+        site.instance.api_version,
+    )
+    .with_retry_policy(RetryPolicy::from(&site.retry))
+}
+
+fn prompt_email(configured: Option<&str>) -> Result<String, AppError> {
+    match configured {
+        Some(email) => eprint!("Jira account email [{}]: ", email),
+        None => eprint!("Jira account email: "),
+    }
+    std::io::stderr().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+
+    match (input.trim(), configured) {
+        ("", Some(email)) => Ok(email.to_string()),
+        ("", None) => Err(AppError::InvalidInput("Email cannot be empty".to_string())),
+        (email, _) => Ok(email.to_string()),
+    }
+}
+
+fn instance_name(instance: &Instance) -> String {
+    match &instance.name {
+        Some(name) => format!("instance `{}`", name),
+        None => "the top-level settings".to_string(),
+    }
+}
+
+fn account(user: &User) -> String {
+    match &user.email_address {
+        Some(email) => format!("{} <{}> ({})", user.display_name, email, user.id()),
+        None => format!("{} ({})", user.display_name, user.id()),
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::Path;
+
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::{
+    AppError,
+    cli::{ConfigCommand, ConfigSubCommand},
+    config::{
+        self, CliOverrides, ConfigError, ConfigFile, Origin, Origins, ResolvedConfig,
+        config_file_paths, config_schema,
+    },
+};
+
+pub fn handle_command(
+    cmd: ConfigCommand,
+    explicit: Option<&Path>,
+    cli_overrides: CliOverrides,
+) -> Result<(), AppError> {
+    match cmd.subcmd {
+        ConfigSubCommand::Paths => {
+            let paths = config_file_paths(explicit)?;
+            if paths.is_empty() {
+                warn!("{}", ConfigError::NotFound);
+            }
+
+            // Included files are only known once the files are loaded.
+            let paths = match ConfigFile::load_layers(paths.clone()) {
+                Ok(config) => config.sources,
+                Err(e) => {
+                    warn!("{}", e);
+                    paths
+                }
+            };
+            for path in paths {
+                println!("{}", path.display());
+            }
+        }
+        ConfigSubCommand::Validate => {
+            let paths = config_file_paths(explicit)?;
+            if paths.is_empty() {
+                return Err(ConfigError::NotFound.into());
+            }
+
+            let report = config::validate(&paths);
+            for diagnostic in &report.diagnostics {
+                println!("{}: {}\n", diagnostic.severity, diagnostic);
+            }
+
+            let (errors, warnings) = (report.errors(), report.warnings());
+            if errors > 0 {
+                return Err(AppError::InvalidConfig { errors, warnings });
+            }
+
+            info!(
+                "Configuration is valid: {} file(s), {} profile(s), {} warning(s)",
+                report.files.len(),
+                report.profiles,
+                warnings
+            );
+        }
+        ConfigSubCommand::Schema => {
+            let schema = serde_json::to_string_pretty(&config_schema())
+                .map_err(|e| AppError::Json(e.to_string()))?;
+            println!("{}", schema);
+        }
+        ConfigSubCommand::Show { profile, overrides } => {
+            let config_file = ConfigFile::load(explicit)?;
+            let resolved =
+                config_file.resolve_with(&profile, cli_overrides, &overrides.field_overrides())?;
+            for line in annotated_lines(&resolved) {
+                println!("{}", line);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders every leaf of the resolved config as `path = value  # origin`,
+/// with the origins aligned.
+fn annotated_lines(resolved: &ResolvedConfig) -> Vec<String> {
+    let untracked = Origins::default();
+    let mut leaves = vec![
+        (
+            "jira_url".to_string(),
+            Value::from(resolved.jira_url.as_str()),
+            Some(&resolved.origins.jira_url),
+        ),
+        (
+            "project_key".to_string(),
+            Value::from(resolved.project_key.as_str()),
+            Some(&resolved.origins.project_key),
+        ),
+    ];
+    if let Some(fields) = &resolved.fields {
+        let origins = resolved.origins.fields.as_ref().unwrap_or(&untracked);
+        collect_leaves(fields, "fields", &mut vec![], origins, &mut leaves);
+    }
+
+    let assignments: Vec<String> = leaves
+        .iter()
+        .map(|(path, value, _)| format!("{} = {}", path, value))
+        .collect();
+    let width = assignments
+        .iter()
+        .map(|a| a.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    assignments
+        .into_iter()
+        .zip(leaves)
+        .map(|(assignment, (_, _, origin))| match origin {
+            Some(origin) => format!("{:width$}  # {}", assignment, origin),
+            None => assignment,
+        })
+        .collect()
+}
+
+/// Collects the leaves of `value`, with their display path and origin.
+/// Empty objects and arrays are leaves too.
+fn collect_leaves<'a>(
+    value: &Value,
+    path: &str,
+    keys: &mut Vec<String>,
+    origins: &'a Origins,
+    leaves: &mut Vec<(String, Value, Option<&'a Origin>)>,
+) {
+    let children: Vec<(String, String, &Value)> = match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| (format!("{}.{}", path, key), key.clone(), value))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (format!("{}[{}]", path, i), i.to_string(), value))
+            .collect(),
+        _ => vec![],
+    };
+
+    if children.is_empty() {
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        leaves.push((path.to_string(), value.clone(), origins.at(&keys)));
+        return;
+    }
+
+    for (child_path, key, child) in children {
+        keys.push(key);
+        collect_leaves(child, &child_path, keys, origins, leaves);
+        keys.pop();
+    }
+}
@@ -1,20 +1,850 @@
+use std::io::Read;
+
 use crate::AppError;
+use crate::batch::{TicketBatch, TicketRow};
 use crate::cli::CreateCommand;
-use crate::config::ResolvedConfig;
-use crate::jira::JiraClient;
-use tracing::info;
+use crate::config::{
+    CliOverrides, ConfigFile, FieldOverrides, Origin, Origins, ResolvedConfig, Traced,
+};
+use crate::editor;
+use crate::fields::FieldResolver;
+use crate::jira::{self, JiraApiError, JiraClient, JiraError, NewIssue, RestApi, TicketInfo};
+use crate::output::{self, OutputFormat, Tabular};
+use crate::title::{Assignee, TitleParser};
+use futures::{StreamExt, stream};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use tracing::{debug, error, info, warn};
+
+/// The placeholders of a `--format` template.
+const TEMPLATE_PLACEHOLDERS: [&str; 4] = ["key", "id", "self", "url"];
+
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{([^{}]*)\}").expect("valid regex"));
+
+/// A ticket with its configuration resolved, ready to be sent.
+struct PreparedTicket {
+    summary: String,
+    config: ResolvedConfig,
+    fields: Option<Value>,
+    assignee: Option<Assignee>,
+}
+
+/// A created ticket, as printed by `--output` and `--format`.
+#[derive(Debug, Serialize)]
+struct CreatedTicket {
+    key: String,
+    id: String,
+    /// The REST API URL of the ticket.
+    #[serde(rename = "self")]
+    self_url: String,
+    /// The URL of the ticket in the browser.
+    url: String,
+    /// The fields sent to Jira.
+    fields: Value,
+}
+
+impl CreatedTicket {
+    fn new(ticket: TicketInfo, body: Value, client_url: &str) -> Result<Self, AppError> {
+        Ok(Self {
+            url: jira::browse_url(client_url, &ticket.key)?.to_string(),
+            key: ticket.key,
+            id: ticket.id,
+            self_url: ticket.self_url,
+            fields: body["fields"].clone(),
+        })
+    }
+}
+
+impl Tabular for CreatedTicket {
+    const COLUMNS: &'static [&'static str] = &["KEY", "ID", "URL"];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![vec![self.key.clone(), self.id.clone(), self.url.clone()]]
+    }
+
+    fn keys(&self) -> Vec<String> {
+        vec![self.key.clone()]
+    }
+}
+
+/// The outcome of a ticket submission.
+enum Submitted {
+    /// The request a dry run would send.
+    Request(String),
+    Created(CreatedTicket),
+}
+
+/// How the created tickets are printed.
+enum TicketOutput {
+    /// The URL of each ticket, as soon as it is created.
+    Url,
+    /// `--quiet`: the key of each ticket.
+    Key,
+    /// `--format`: the template filled in for each ticket.
+    Template(String),
+    /// `--output`: all the tickets once they are created.
+    Document(OutputFormat),
+}
+
+impl TicketOutput {
+    fn new(cmd: &CreateCommand, format: Option<OutputFormat>) -> Result<Self, AppError> {
+        // `--output` is global, so clap cannot tell when it comes before `create`.
+        let flag = match (&cmd.format, cmd.quiet) {
+            (Some(_), _) => Some("--format"),
+            (None, true) => Some("--quiet"),
+            (None, false) => None,
+        };
+        if let (Some(flag), Some(_)) = (flag, format) {
+            return Err(AppError::InvalidInput(format!(
+                "{} cannot be used with --output",
+                flag
+            )));
+        }
 
+        if let Some(template) = &cmd.format {
+            for placeholder in PLACEHOLDER.captures_iter(template) {
+                let name = &placeholder[1];
+                if !TEMPLATE_PLACEHOLDERS.contains(&name) {
+                    return Err(AppError::InvalidInput(format!(
+                        "Unknown placeholder `{{{}}}` in --format, expected one of {{{}}}",
+                        name,
+                        TEMPLATE_PLACEHOLDERS.join("}, {")
+                    )));
+                }
+            }
+            return Ok(Self::Template(template.clone()));
+        }
+
+        Ok(match format {
+            _ if cmd.quiet => Self::Key,
+            Some(format) => Self::Document(format),
+            None => Self::Url,
+        })
+    }
+
+    /// The line printed when `ticket` is created, if tickets are printed one by one.
+    fn line(&self, ticket: &CreatedTicket) -> Option<String> {
+        match self {
+            Self::Url => Some(ticket.url.clone()),
+            Self::Key => Some(ticket.key.clone()),
+            Self::Template(template) => Some(
+                PLACEHOLDER
+                    .replace_all(
+                        template,
+                        |placeholder: &regex::Captures| match &placeholder[1] {
+                            "key" => ticket.key.clone(),
+                            "id" => ticket.id.clone(),
+                            "self" => ticket.self_url.clone(),
+                            _ => ticket.url.clone(),
+                        },
+                    )
+                    .into_owned(),
+            ),
+            Self::Document(_) => None,
+        }
+    }
+}
+
+/// Creates one ticket per title or input row, or prints the requests when
+/// `--dry-run` is set. Several tickets are created concurrently, and the rows
+/// that failed are written to a retry file.
 pub async fn handle_command(
     cmd: CreateCommand,
-    client: &JiraClient,
-    config: &ResolvedConfig,
+    config_file: &ConfigFile,
+    cli_overrides: CliOverrides,
+    output_format: Option<OutputFormat>,
 ) -> Result<(), AppError> {
-    let ticket = client
-        .create_ticket(&cmd.title, config.fields.clone())
+    let ticket_output = TicketOutput::new(&cmd, output_format)?;
+    let parser = TitleParser::new(
+        &config_file.title_syntax,
+        config_file.profiles.keys().cloned(),
+    )?;
+    let (batch, description) = read_batch(&cmd)?;
+    let overrides = cmd.overrides.clone().field_overrides();
+
+    let mut tickets: Vec<Result<PreparedTicket, AppError>> = batch
+        .rows
+        .iter()
+        .map(|row| {
+            let description = row.description.as_deref().or(description.as_deref());
+            prepare_ticket(
+                &cmd,
+                row,
+                description,
+                &parser,
+                config_file,
+                &cli_overrides,
+                &overrides,
+            )
+        })
+        .collect();
+
+    // All tickets share the client of the first resolved one.
+    let first_config = tickets
+        .iter()
+        .find_map(|t| t.as_ref().ok())
+        .map(|t| t.config.clone());
+    let client = match &first_config {
+        // A dry run only needs Jira for lookups, so it can validate configs without credentials.
+        Some(config) if cmd.dry_run => crate::build_client(config)
+            .inspect_err(|e| warn!("Jira lookups disabled: {}", e))
+            .ok(),
+        Some(config) => Some(crate::build_client(config)?),
+        None => None,
+    };
+    let client_url = first_config.map(|c| c.jira_url).unwrap_or_default();
+    let resolver = client.as_ref().map(FieldResolver::new);
+
+    let mut created = Vec::new();
+    let mut print = |submitted: Submitted| match submitted {
+        Submitted::Request(request) => println!("{}", request),
+        Submitted::Created(ticket) => {
+            if let Some(line) = ticket_output.line(&ticket) {
+                println!("{}", line);
+            }
+            created.push(ticket);
+        }
+    };
+
+    if tickets.len() == 1 {
+        let ticket = tickets.pop().expect("one ticket")?;
+        let submitted = submit_ticket(
+            ticket,
+            client.as_ref(),
+            resolver.as_ref(),
+            &client_url,
+            cmd.dry_run,
+            !cmd.no_validate,
+        )
         .await?;
+        if matches!(ticket_output, TicketOutput::Url) && !cmd.dry_run {
+            info!("Ticket created successfully:");
+        }
+        print(submitted);
+
+        if let (TicketOutput::Document(format), [ticket]) = (&ticket_output, created.as_slice()) {
+            output::print(ticket, *format)?;
+        }
+        return Ok(());
+    }
+
+    let total = tickets.len();
+    let concurrency = usize::from(cmd.concurrency);
+    let mut failed = Vec::new();
+    let mut report = |index: usize, result: Result<Submitted, AppError>| match result {
+        Ok(submitted) => print(submitted),
+        Err(e) => {
+            error!("'{}': {}", batch.rows[index].title, e);
+            failed.push(index);
+        }
+    };
+
+    match (client.as_ref(), resolver.as_ref()) {
+        (Some(client), Some(resolver)) if cmd.bulk && !cmd.dry_run => {
+            let results = submit_bulk(
+                tickets,
+                client,
+                resolver,
+                &client_url,
+                concurrency,
+                !cmd.no_validate,
+            )
+            .await;
+            for (index, result) in results.into_iter().enumerate() {
+                report(index, result);
+            }
+        }
+        _ => {
+            let mut results = stream::iter(tickets.into_iter().enumerate())
+                .map(|(index, ticket)| {
+                    let client = client.as_ref();
+                    let resolver = resolver.as_ref();
+                    let client_url = client_url.as_str();
+                    async move {
+                        let result = match ticket {
+                            Ok(ticket) => {
+                                submit_ticket(
+                                    ticket,
+                                    client,
+                                    resolver,
+                                    client_url,
+                                    cmd.dry_run,
+                                    !cmd.no_validate,
+                                )
+                                .await
+                            }
+                            Err(e) => Err(e),
+                        };
+                        (index, result)
+                    }
+                })
+                .buffered(concurrency);
+
+            while let Some((index, result)) = results.next().await {
+                report(index, result);
+            }
+        }
+    }
+
+    if let TicketOutput::Document(format) = ticket_output {
+        output::print(&created, format)?;
+    }
+
+    let verb = if cmd.dry_run { "Checked" } else { "Created" };
+    info!(
+        "{} {} of {} tickets, {} failed",
+        verb,
+        total - failed.len(),
+        total,
+        failed.len()
+    );
+
+    if failed.is_empty() {
+        return Ok(());
+    }
+
+    // A dry run has no side effects.
+    if cmd.dry_run {
+        return Err(AppError::BulkCreate {
+            failed: failed.len(),
+            total,
+        });
+    }
+
+    let retry_path = cmd
+        .retry_file
+        .clone()
+        .unwrap_or_else(|| batch.default_retry_path());
+    batch.write_retry_file(&failed, &retry_path)?;
+    info!("Failed rows written to {:?}", retry_path);
+
+    Err(AppError::BulkCreate {
+        failed: failed.len(),
+        total,
+    })
+}
+
+/// Reads the tickets from the titles, the tickets file or the editor, and the
+/// description shared by the tickets that do not set their own.
+fn read_batch(cmd: &CreateCommand) -> Result<(TicketBatch, Option<String>), AppError> {
+    let description = match &cmd.description_file {
+        Some(path) if path.as_os_str() == "-" => {
+            let mut content = String::new();
+            std::io::stdin().read_to_string(&mut content)?;
+            Some(content)
+        }
+        Some(path) => Some(std::fs::read_to_string(path)?),
+        None => cmd.description.clone(),
+    };
+
+    if cmd.edit {
+        if cmd.titles.len() > 1 {
+            return Err(AppError::InvalidInput(
+                "--edit creates a single ticket".to_string(),
+            ));
+        }
+
+        let title = cmd.titles.first().map(String::as_str).unwrap_or_default();
+        let message =
+            editor::edit_ticket_message(title, description.as_deref().unwrap_or_default())?;
+        let (title, description) = editor::parse_ticket_message(&message);
+
+        return Ok((TicketBatch::from_titles(vec![title]), description));
+    }
+
+    let batch = match &cmd.from_file {
+        Some(path) => TicketBatch::read(path, cmd.input_format)?,
+        None => TicketBatch::from_titles(cmd.titles.clone()),
+    };
+
+    if batch.rows.is_empty() {
+        return Err(AppError::InvalidInput("No tickets to create".to_string()));
+    }
+
+    Ok((batch, description.filter(|d| !d.trim().is_empty())))
+}
+
+/// Parses the title, resolves the profiles (from `--profile`, the input row and
+/// the title tokens) and merges the row fields, title fields and command-line
+/// fields over them.
+fn prepare_ticket(
+    cmd: &CreateCommand,
+    row: &TicketRow,
+    description: Option<&str>,
+    parser: &TitleParser,
+    config_file: &ConfigFile,
+    cli_overrides: &CliOverrides,
+    overrides: &FieldOverrides,
+) -> Result<PreparedTicket, AppError> {
+    let title = parser.parse(&row.title);
+    debug!("Parsed title: {:?}", title);
+
+    if title.summary.is_empty() {
+        return Err(AppError::InvalidInput(
+            "The title is empty once its tokens are removed".to_string(),
+        ));
+    }
+
+    let profile_names: Vec<String> = cmd
+        .profile
+        .iter()
+        .chain(&row.profiles)
+        .chain(&title.profiles)
+        .cloned()
+        .collect();
+
+    // The command-line fields are merged once the row and title fields are.
+    let properties = FieldOverrides {
+        fields: Vec::new(),
+        properties: overrides.properties.clone(),
+    };
+    let mut config =
+        config_file.resolve_with(&profile_names, cli_overrides.clone(), &properties)?;
+    debug!("Resolved configuration: {:?}", config);
+
+    let mut fields = config.fields.take().map(|value| Traced {
+        value,
+        origins: config.origins.fields.take().unwrap_or_default(),
+    });
+    if !row.fields.is_empty() {
+        let mut row_fields = row.fields.clone();
+        config.instance.expand_aliases(&mut row_fields, None);
+        let row_fields = Traced::new(Value::Object(row_fields), &Origin::Input);
+        fields = Some(match fields {
+            Some(fields) => fields.deep_merge(row_fields),
+            None => row_fields,
+        });
+    }
+
+    let mut extra_fields = Map::new();
+    if let Some(description) = description {
+        // Converted to ADF once the REST API version is known.
+        extra_fields.insert(
+            "description".to_string(),
+            Value::String(description.to_string()),
+        );
+    }
+    let description_origin = if row.description.is_some() {
+        Origin::Input
+    } else if cmd.description_file.is_some() {
+        Origin::Cli("--description-file")
+    } else if cmd.edit {
+        Origin::Cli("--edit")
+    } else {
+        Origin::Cli("--description")
+    };
+
+    let mut fields = title.merge_into(fields, extra_fields, &description_origin);
+    let mut assignee = title.assignee;
+    if !overrides.fields.is_empty() {
+        let mut merged = overrides.apply_fields(fields.unwrap_or_else(|| Traced {
+            value: json!({}),
+            origins: Origins::default(),
+        }));
+        if let Value::Object(map) = &mut merged.value {
+            config
+                .instance
+                .expand_aliases(map, Some(&mut merged.origins));
+        }
+        fields = Some(merged);
+
+        // The `@` token is only resolved later, but must not win over `--field`.
+        if assignee.is_some() && sets_field(overrides, &config, "assignee") {
+            debug!("Ignoring the assignee of the title, set with --field");
+            assignee = None;
+        }
+    }
+
+    let (fields, origins) = fields.map(|fields| (fields.value, fields.origins)).unzip();
+    config.origins.fields = origins;
+    Ok(PreparedTicket {
+        fields,
+        summary: title.summary,
+        assignee,
+        config,
+    })
+}
+
+/// Whether the `--field` and `--field-json` values set `field`, directly or
+/// through an alias.
+fn sets_field(overrides: &FieldOverrides, config: &ResolvedConfig, field: &str) -> bool {
+    let mut set = overrides.apply_fields(Traced::new(json!({}), &Origin::Cli("--field")));
+    match &mut set.value {
+        Value::Object(map) => {
+            config.instance.expand_aliases(map, None);
+            map.contains_key(field)
+        }
+        _ => false,
+    }
+}
+
+/// Checks the ticket targets the shared client, resolves its assignee, and
+/// replaces field aliases and the names of issue types, priorities, components
+/// and versions with their IDs. If `validate`, the fields are then converted to
+/// the types of the create screen and checked against it. `client` is only
+/// `None` for a dry run without credentials.
+async fn finalize_ticket(
+    ticket: PreparedTicket,
+    client: Option<&JiraClient>,
+    resolver: Option<&FieldResolver<'_>>,
+    client_url: &str,
+    validate: bool,
+) -> Result<NewIssue, AppError> {
+    let PreparedTicket {
+        summary,
+        mut config,
+        mut fields,
+        assignee,
+    } = ticket;
+
+    if config.jira_url != client_url {
+        return Err(AppError::InvalidInput(format!(
+            "All tickets must target the same Jira instance ({}), got {}",
+            client_url, config.jira_url
+        )));
+    }
+
+    if let Some(assignee) = &assignee {
+        let assignee = resolve_assignee(assignee, client).await?;
+        match fields.get_or_insert_with(|| json!({})) {
+            Value::Object(map) => {
+                map.insert("assignee".to_string(), assignee);
+            }
+            _ => unreachable!("fields are an object"),
+        }
+        if let Origins::Object(origins) = config.origins.fields.get_or_insert_with(Origins::default)
+        {
+            origins.insert("assignee".to_string(), Origins::Leaf(Origin::Title));
+        }
+    }
+
+    // Without credentials, a dry run shows the names as they are.
+    if let (Some(resolver), Some(fields)) = (resolver, &mut fields) {
+        resolver
+            .expand_aliases(
+                &config.instance.field_aliases,
+                fields,
+                config.origins.fields.as_mut(),
+            )
+            .await?;
+        resolver.resolve(&config.project_key, fields).await?;
+    }
+
+    if let Some(resolver) = resolver
+        && validate
+    {
+        if let Some(fields) = &mut fields {
+            resolver.coerce(&config.project_key, fields).await?;
+        }
+        let errors = resolver
+            .check(&config.project_key, fields.as_ref().unwrap_or(&Value::Null))
+            .await?;
+        if !errors.is_empty() {
+            let error = JiraApiError {
+                error_messages: vec![
+                    "The fields do not match the create screen (use --no-validate to send them anyway)"
+                        .to_string(),
+                ],
+                errors,
+                ..Default::default()
+            };
+            return Err(rejected(error, config.origins.fields));
+        }
+    }
+
+    Ok(NewIssue {
+        project_key: config.project_key,
+        summary,
+        fields,
+    })
+}
+
+/// Creates the ticket, or returns the request for a dry run.
+async fn submit_ticket(
+    ticket: PreparedTicket,
+    client: Option<&JiraClient>,
+    resolver: Option<&FieldResolver<'_>>,
+    client_url: &str,
+    dry_run: bool,
+    validate: bool,
+) -> Result<Submitted, AppError> {
+    let api_version = ticket.config.instance.api_version;
+    let field_origins = ticket.config.origins.fields.clone();
+    let issue = finalize_ticket(ticket, client, resolver, client_url, validate).await?;
+
+    let api = match client {
+        Some(client) => client.api().await,
+        None => RestApi::assumed(api_version),
+    };
+    let body = jira::create_issue_body(api, &issue.project_key, &issue.summary, issue.fields);
+
+    if dry_run {
+        let request =
+            serde_json::to_string_pretty(&body).map_err(|e| AppError::Json(e.to_string()))?;
+        return Ok(Submitted::Request(format!(
+            "POST {}\n{}",
+            jira::create_issue_url(client_url, api)?,
+            request
+        )));
+    }
+
+    let client = client.expect("a client is always built outside of dry runs");
+    let ticket = client.create_ticket(&body).await.map_err(|e| match e {
+        e @ JiraError::Api { .. } => AppError::CreateTicket {
+            source: Box::new(e),
+            field_origins,
+        },
+        e => e.into(),
+    })?;
+
+    Ok(Submitted::Created(CreatedTicket::new(
+        ticket, body, client_url,
+    )?))
+}
+
+/// Creates the tickets with the bulk endpoint and returns one result per ticket.
+async fn submit_bulk(
+    tickets: Vec<Result<PreparedTicket, AppError>>,
+    client: &JiraClient,
+    resolver: &FieldResolver<'_>,
+    client_url: &str,
+    concurrency: usize,
+    validate: bool,
+) -> Vec<Result<Submitted, AppError>> {
+    let mut field_origins: Vec<Option<Origins>> = tickets
+        .iter()
+        .map(|ticket| {
+            let ticket = ticket.as_ref().ok()?;
+            ticket.config.origins.fields.clone()
+        })
+        .collect();
+
+    // Assignee lookups still need one request per ticket.
+    let finalized: Vec<Result<NewIssue, AppError>> = stream::iter(tickets)
+        .map(|ticket| async move {
+            finalize_ticket(ticket?, Some(client), Some(resolver), client_url, validate).await
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    let mut results = Vec::with_capacity(finalized.len());
+    let mut indexes = Vec::new();
+    let mut issues = Vec::new();
+    for (index, issue) in finalized.into_iter().enumerate() {
+        match issue {
+            Ok(issue) => {
+                indexes.push(index);
+                issues.push(issue);
+                results.push(Err(rejected(
+                    JiraApiError::message("Missing from the bulk results"),
+                    None,
+                )));
+            }
+            Err(e) => results.push(Err(e)),
+        }
+    }
+
+    let api = client.api().await;
+    match client.create_tickets_bulk(&issues).await {
+        Ok(bulk) => {
+            for (index, ticket) in bulk.created {
+                let issue = &issues[index];
+                let body = jira::create_issue_body(
+                    api,
+                    &issue.project_key,
+                    &issue.summary,
+                    issue.fields.clone(),
+                );
+                results[indexes[index]] =
+                    CreatedTicket::new(ticket, body, client_url).map(Submitted::Created);
+            }
+            for failure in bulk.failed {
+                let index = indexes[failure.index];
+                results[index] = Err(rejected(failure.error, field_origins[index].take()));
+            }
+        }
+        Err(e) => {
+            let error = JiraApiError::from_failure(&e);
+            for index in indexes {
+                results[index] = Err(rejected(error.clone(), None));
+            }
+        }
+    }
+
+    results
+}
+
+/// The error of a ticket Jira refused.
+fn rejected(error: JiraApiError, field_origins: Option<Origins>) -> AppError {
+    AppError::CreateTicket {
+        source: Box::new(JiraError::Api {
+            action: "create ticket",
+            error,
+        }),
+        field_origins,
+    }
+}
+
+/// Describes the error of a ticket, with each field error attributed to the
+/// profiles that set the field.
+pub(crate) fn describe_rejection(error: &JiraError, field_origins: Option<&Origins>) -> String {
+    let JiraError::Api { action, error } = error else {
+        return error.to_string();
+    };
+
+    let message = error.describe(|field| {
+        let Some(origins) = field_origins.and_then(|origins| origins.subtree(&[field])) else {
+            return field.to_string();
+        };
+
+        let mut sources = Vec::new();
+        match origins.profiles().as_slice() {
+            [] => {}
+            [profile] => sources.push(format!("profile `{}`", profile)),
+            profiles => {
+                let profiles: Vec<String> = profiles.iter().map(|p| format!("`{}`", p)).collect();
+                sources.push(format!("profiles {}", profiles.join(", ")));
+            }
+        }
+        for origin in origins.leaves() {
+            let source = match origin {
+                Origin::Title => "the title".to_string(),
+                Origin::Input => "the input file".to_string(),
+                Origin::Cli(flag) => flag.to_string(),
+                _ => continue,
+            };
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+
+        match sources.is_empty() {
+            true => field.to_string(),
+            false => format!("{} (from {})", field, sources.join(", ")),
+        }
+    });
+    format!("Failed to {}: {}", action, message)
+}
+
+async fn resolve_assignee(
+    assignee: &Assignee,
+    client: Option<&JiraClient>,
+) -> Result<Value, AppError> {
+    let Some(client) = client else {
+        warn!("Cannot look up assignee {:?} without credentials", assignee);
+        let placeholder = match assignee {
+            Assignee::Me => "<me>".to_string(),
+            Assignee::User(query) => format!("<{}>", query),
+        };
+        return Ok(json!({ "accountId": placeholder }));
+    };
+
+    let user = match assignee {
+        Assignee::Me => client.get_myself().await?,
+        Assignee::User(query) => client.find_user(query).await?,
+    };
+    debug!("Assigning to {} ({})", user.display_name, user.id());
+
+    Ok(user.reference())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::config::FieldOverride;
+
+    fn prepare(config_file: &ConfigFile, args: &[&str], fields: &[&str]) -> PreparedTicket {
+        let parser =
+            TitleParser::new(&config_file.title_syntax, config_file.profiles.keys()).unwrap();
+        let cmd = CreateCommand::parse_from(["create"].iter().chain(args));
+        let batch = TicketBatch::from_titles(cmd.titles.clone());
+        let overrides = FieldOverrides {
+            fields: fields
+                .iter()
+                .map(|field| FieldOverride::parse(field).unwrap())
+                .collect(),
+            ..Default::default()
+        };
+        prepare_ticket(
+            &cmd,
+            &batch.rows[0],
+            cmd.description.as_deref(),
+            &parser,
+            config_file,
+            &CliOverrides::default(),
+            &overrides,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_field_overrides_beat_the_title_assignee() {
+        let config_file = ConfigFile::from_str(
+            r#"{
+                "jira_url": "https://example.atlassian.net",
+                "project_key": "TEST",
+                "field_aliases": { "owner": "assignee" }
+            }"#,
+        )
+        .unwrap();
+
+        let ticket = prepare(
+            &config_file,
+            &["Fix login @jane"],
+            &["owner.accountId=5b10ac8d82e05b22cc7d4ef5"],
+        );
+        assert_eq!(ticket.assignee, None);
+        assert_eq!(
+            ticket.fields.unwrap()["assignee"],
+            json!({ "accountId": "5b10ac8d82e05b22cc7d4ef5" })
+        );
+
+        let ticket = prepare(&config_file, &["Fix login @jane"], &["labels+=hotfix"]);
+        assert_eq!(ticket.assignee, Some(Assignee::User("jane".to_string())));
+    }
 
-    info!("Ticket created successfully:");
-    println!("{}/browse/{}", config.jira_url, ticket.key);
+    #[test]
+    fn test_rejections_name_the_title_and_flags() {
+        let config_file = ConfigFile::from_str(
+            r#"{
+                "jira_url": "https://example.atlassian.net",
+                "project_key": "TEST",
+                "profiles": { "bug": { "fields": { "labels": ["bug"], "priority": { "id": "3" } } } }
+            }"#,
+        )
+        .unwrap();
+        let ticket = prepare(
+            &config_file,
+            &["Fix login !Hihg #auth", "-p", "bug", "-d", "Steps"],
+            &["customfield_10016=5"],
+        );
 
-    Ok(())
+        let error = JiraError::Api {
+            action: "create ticket",
+            error: JiraApiError {
+                errors: BTreeMap::from([
+                    ("customfield_10016".to_string(), "not a number".to_string()),
+                    ("description".to_string(), "too long".to_string()),
+                    ("labels".to_string(), "invalid".to_string()),
+                    ("priority".to_string(), "not found".to_string()),
+                ]),
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            describe_rejection(&error, ticket.config.origins.fields.as_ref()),
+            "Failed to create ticket: \
+             customfield_10016 (from --field): not a number; \
+             description (from --description): too long; \
+             labels (from profile `bug`, the title): invalid; \
+             priority (from the title): not found"
+        );
+    }
 }
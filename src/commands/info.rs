@@ -1,63 +1,160 @@
+use std::collections::BTreeMap;
+use std::pin::pin;
+
 use crate::AppError;
-use crate::cli::InfoCommand;
-use crate::jira::JiraClient;
-use serde::Serialize;
-use tracing::warn;
+use crate::cli::{InfoCommand, PageArgs};
+use crate::config::{NAME_PREFIX, rename_field};
+use crate::fields::FieldResolver;
+use crate::jira::{JiraClient, JiraError};
+use crate::output::{self, OutputFormat};
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use tracing::{info, warn};
 
-pub async fn handle_command(cmd: InfoCommand, client: &JiraClient) -> Result<(), AppError> {
+pub async fn handle_command(
+    cmd: InfoCommand,
+    client: &JiraClient,
+    field_aliases: &BTreeMap<String, String>,
+    format: OutputFormat,
+) -> Result<(), AppError> {
     use crate::cli::InfoSubCommand::*;
 
     match cmd.subcmd {
         Project { key } => {
             let project = client.get_project(key).await?;
-            print_output(&project)?;
+            output::print(&project, format)?;
         }
-        Ticket { key } => {
-            let ticket = client.get_ticket(&key).await?;
-            print_output(&ticket)?;
+        Ticket { key, aliases } => {
+            let mut ticket = client.get_ticket(&key).await?;
+            if aliases {
+                use_aliases(&mut ticket, field_aliases, client).await?;
+            }
+            output::print(&ticket, format)?;
         }
         Epics {
             project_key,
             board_id,
+            page,
         } => {
             if let Some(board_id) = board_id {
-                let epics = client.get_epics_by_board(board_id).await?;
-                print_output(&epics)?;
+                let epics = collect(client.epics_by_board(board_id)?, page, "epics").await?;
+                output::print(&epics, format)?;
             } else {
-                let boards = client.get_boards(project_key.as_deref()).await?;
+                let all = PageArgs { all: true, ..page };
+                let boards = collect(client.boards(project_key.as_deref())?, all, "boards").await?;
                 if boards.is_empty() {
                     warn!("No boards found");
                     return Ok(());
                 }
 
+                // A table per board, or a single list that scripts can parse.
+                let mut all_epics = Vec::new();
                 for board in boards {
-                    println!("Epics for board: {} ({})", board.name, board.id);
-                    let epics = client.get_epics_by_board(board.id).await?;
-                    print_output(&epics)?;
+                    let epics = collect(client.epics_by_board(board.id)?, page, "epics").await?;
+                    if format == OutputFormat::Table {
+                        println!("Epics for board: {} ({})", board.name, board.id);
+                        output::print(&epics, format)?;
+                    } else {
+                        all_epics.extend(epics);
+                    }
+                }
+                if format != OutputFormat::Table {
+                    output::print(&all_epics, format)?;
                 }
             }
         }
-        Boards { project } => {
-            let boards = client.get_boards(project.as_deref()).await?;
-            print_output(&boards)?;
+        Boards { project, page } => {
+            let boards = collect(client.boards(project.as_deref())?, page, "boards").await?;
+            output::print(&boards, format)?;
         }
         Fields {
             project_key,
             issue_type,
         } => {
             let fields = client.get_fields(project_key, issue_type).await?;
-            print_output(&fields)?;
+            output::print(&fields, format)?;
         }
     }
 
     Ok(())
 }
 
-fn print_output<T>(data: &T) -> Result<(), AppError>
-where
-    T: Serialize + ?Sized,
-{
-    let json = serde_json::to_string_pretty(data).map_err(|e| AppError::Json(e.to_string()))?;
-    println!("{}", json);
+/// Renames the fields of a ticket to their aliases.
+async fn use_aliases(
+    ticket: &mut Value,
+    field_aliases: &BTreeMap<String, String>,
+    client: &JiraClient,
+) -> Result<(), AppError> {
+    let resolver = FieldResolver::new(client);
+    let Some(fields) = ticket.get_mut("fields").and_then(Value::as_object_mut) else {
+        return Ok(());
+    };
+
+    for (alias, target) in field_aliases {
+        let id = match target.strip_prefix(NAME_PREFIX) {
+            Some(name) => resolver.field_id(name).await?,
+            None => target.clone(),
+        };
+        rename_field(fields, &id, alias);
+    }
+
     Ok(())
 }
+
+/// Collects the items of a list, up to the limit of `page`.
+async fn collect<T>(
+    items: impl Stream<Item = Result<T, JiraError>>,
+    page: PageArgs,
+    what: &str,
+) -> Result<Vec<T>, AppError> {
+    let mut items = pin!(items);
+    let limit = page.limit();
+    let mut collected = Vec::new();
+    while limit.is_none_or(|limit| collected.len() < limit) {
+        match items.next().await {
+            Some(item) => collected.push(item?),
+            None => return Ok(collected),
+        }
+    }
+
+    // A single item past the limit tells whether there are more.
+    if items.next().await.is_some() {
+        info!(
+            "Showing the first {} {}, use --all to list them all",
+            collected.len(),
+            what
+        );
+    }
+    Ok(collected)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use futures::stream;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_collect_reads_one_item_past_the_limit() {
+        let read = Cell::new(0);
+        let items = || {
+            stream::iter(1..=5)
+                .inspect(|_| read.set(read.get() + 1))
+                .map(Ok::<_, JiraError>)
+        };
+        let page = |limit, all| PageArgs { limit, all };
+
+        let collected = collect(items(), page(2, false), "items").await.unwrap();
+        assert_eq!(collected, vec![1, 2]);
+        assert_eq!(read.replace(0), 3);
+
+        let collected = collect(items(), page(5, false), "items").await.unwrap();
+        assert_eq!(collected, vec![1, 2, 3, 4, 5]);
+        assert_eq!(read.replace(0), 5);
+
+        let collected = collect(items(), page(2, true), "items").await.unwrap();
+        assert_eq!(collected.len(), 5);
+    }
+}
@@ -3,7 +3,7 @@ use tracing::info;
 use crate::{
     AppError,
     cli::InitCommand,
-    config::{ConfigFile, ProfileDef, get_home_config_file_path},
+    config::{ConfigFile, ProfileDef, SCHEMA_URL, get_home_config_file_path},
 };
 
 pub async fn handle_command(cmd: InitCommand) -> Result<(), AppError> {
@@ -22,6 +22,7 @@ pub async fn handle_command(cmd: InitCommand) -> Result<(), AppError> {
     let project_key = get_project_key(cmd.project_key)?;
 
     let config = ConfigFile {
+        schema: Some(SCHEMA_URL.to_string()),
         jira_url: Some(jira_url),
         project_key,
         properties: std::collections::HashMap::new(),
@@ -34,14 +35,16 @@ pub async fn handle_command(cmd: InitCommand) -> Result<(), AppError> {
                     project_key: None,
                     fields: Some(serde_json::json!({})),
                     inherits: vec![], // TODO: should not appear in generated config
+                    instance: None,
                 },
             );
             profiles
         },
+        ..Default::default()
     };
 
     let config_content = serde_json::to_string_pretty(&config)
-        .map_err(|e| AppError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+        .map_err(|e| AppError::Io(std::io::Error::other(e)))?;
 
     std::fs::write(&cwd_path, config_content)?;
 
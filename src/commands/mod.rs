@@ -1,3 +1,5 @@
+pub mod auth;
+pub mod config;
 pub mod create;
 pub mod info;
 pub mod init;
@@ -0,0 +1,266 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// A position in a config file, one-based like editors and compilers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path.display(), self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in the configuration, with the source line it points to.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub location: Option<SourceLocation>,
+    /// The source line at `location`.
+    pub source_line: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            location: None,
+            source_line: None,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(message)
+        }
+    }
+
+    /// Points the diagnostic at `line` and `column` of a file.
+    pub fn at(mut self, path: &Path, content: &str, line: usize, column: usize) -> Self {
+        self.source_line = content
+            .lines()
+            .nth(line.saturating_sub(1))
+            .map(str::to_string);
+        self.location = Some(SourceLocation {
+            path: path.to_path_buf(),
+            line,
+            column,
+        });
+        self
+    }
+
+    /// Builds a diagnostic from a json5 error, keeping its location.
+    pub fn from_json5(path: &Path, content: &str, error: &json5::Error) -> Self {
+        let json5::Error::Message { msg, location } = error;
+
+        // Syntax errors come with their own rendering of the source line, only
+        // keep the explanation (the `= expected ...` line).
+        let message = msg
+            .lines()
+            .rev()
+            .find_map(|line| line.trim().strip_prefix("= "))
+            .unwrap_or(msg)
+            .to_string();
+
+        let diagnostic = Self::error(message);
+        match location {
+            Some(location) => diagnostic.at(path, content, location.line, location.column),
+            None => Self {
+                location: Some(SourceLocation {
+                    path: path.to_path_buf(),
+                    line: 1,
+                    column: 1,
+                }),
+                ..diagnostic
+            },
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Renders the message followed by the location and source line, without the
+/// severity.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        let Some(location) = &self.location else {
+            return Ok(());
+        };
+        write!(f, "\n  --> {}", location)?;
+
+        if let Some(source_line) = &self.source_line {
+            let gutter = " ".repeat(location.line.to_string().len());
+            // Tabs keep their width so the caret stays aligned.
+            let padding: String = source_line
+                .chars()
+                .take(location.column.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            write!(
+                f,
+                "\n{gutter} |\n{} | {}\n{gutter} | {}^",
+                location.line, source_line, padding
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the line and column of the key at `keys` (a path of nested object
+/// keys) in JSONC source, by looking for each key after the previous one.
+///
+/// This is a textual search, good enough to point at a key in a hand-written
+/// config file, but it can be fooled by the same key appearing in a comment.
+pub fn locate_key(content: &str, keys: &[&str]) -> Option<(usize, usize)> {
+    key_offset(content, keys).map(|offset| line_column(content, offset))
+}
+
+/// Finds the first occurrence of `text` after the key at `keys`.
+pub fn locate_text(content: &str, keys: &[&str], text: &str) -> Option<(usize, usize)> {
+    let from = key_offset(content, keys)?;
+    let index = content[from..].find(text)?;
+    Some(line_column(content, from + index))
+}
+
+fn key_offset(content: &str, keys: &[&str]) -> Option<usize> {
+    let mut offset = 0;
+    for key in keys {
+        offset = find_key(content, key, offset)?;
+    }
+    Some(offset)
+}
+
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = content[..offset].matches('\n').count() + 1;
+    let column = content[line_start..offset].chars().count() + 1;
+    (line, column)
+}
+
+/// Returns the offset of the first `key` (quoted or bare) followed by a colon.
+fn find_key(content: &str, key: &str, from: usize) -> Option<usize> {
+    let candidates = [
+        format!("\"{}\"", key),
+        format!("'{}'", key),
+        key.to_string(),
+    ];
+
+    candidates
+        .iter()
+        .filter_map(|candidate| {
+            content[from..]
+                .match_indices(candidate.as_str())
+                .map(|(index, _)| from + index)
+                .find(|&start| {
+                    let end = start + candidate.len();
+                    let before = content[..start].chars().next_back();
+                    let is_word_start = !before.is_some_and(is_identifier_char);
+                    let followed_by_colon = content[end..].trim_start().starts_with(':');
+                    is_word_start && followed_by_colon
+                })
+        })
+        .min()
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$' || c == '"' || c == '\''
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = r#"{
+  // Profiles
+  "profiles": {
+    "bug": {
+      "fields": {},
+      unknown: 1
+    }
+  }
+}"#;
+
+    #[test]
+    fn test_locate_nested_key() {
+        assert_eq!(locate_key(CONTENT, &["profiles", "bug"]), Some((4, 5)));
+        assert_eq!(
+            locate_key(CONTENT, &["profiles", "bug", "unknown"]),
+            Some((6, 7))
+        );
+    }
+
+    #[test]
+    fn test_locate_text_after_key() {
+        let content = r#"{ "a": { "x": "${v}" }, "b": { "x": "${v}" } }"#;
+        assert_eq!(locate_text(content, &["b"], "${v}"), Some((1, 38)));
+        assert_eq!(locate_text(content, &["b"], "${w}"), None);
+    }
+
+    #[test]
+    fn test_locate_ignores_values_and_comments_without_colon() {
+        let content = r#"{ "a": "profiles", "profiles": {} }"#;
+        assert_eq!(locate_key(content, &["profiles"]), Some((1, 20)));
+        assert_eq!(locate_key(CONTENT, &["missing"]), None);
+    }
+
+    #[test]
+    fn test_render_with_source_line() {
+        let diagnostic = Diagnostic::warning("unknown key `unknown` in profile `bug`").at(
+            Path::new("tedlt.jsonc"),
+            CONTENT,
+            6,
+            7,
+        );
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "unknown key `unknown` in profile `bug`\n  \
+             --> tedlt.jsonc:6:7\n  \
+             |\n\
+             6 |       unknown: 1\n  \
+             |       ^"
+        );
+    }
+
+    #[test]
+    fn test_from_json5_syntax_error() {
+        let content = "{\n  \"jira_url\": \"x\",\n  \"project_key\":\n}";
+        let error = json5::from_str::<serde_json::Value>(content).unwrap_err();
+
+        let diagnostic = Diagnostic::from_json5(Path::new("tedlt.jsonc"), content, &error);
+
+        let location = diagnostic.location.unwrap();
+        assert_eq!((location.line, location.column), (4, 1));
+        assert!(
+            diagnostic.message.starts_with("expected"),
+            "{}",
+            diagnostic.message
+        );
+        assert_eq!(diagnostic.source_line.as_deref(), Some("}"));
+    }
+}
@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+
+use super::ConfigError;
+
+pub const CONFIG_FILE_NAME: &str = "tedlt.jsonc";
+
+/// Environment variable pinning a single configuration file, like `--config`.
+pub const CONFIG_ENV_VAR: &str = "TEDLT_CONFIG";
+
+/// Returns the configuration files to load, from lowest to highest precedence.
+///
+/// An explicit path (from `--config` or `TEDLT_CONFIG`) is the only file loaded.
+/// Otherwise the existing files are, in order: the global file in
+/// `$XDG_CONFIG_HOME/tedlt/`, the user file in the home directory, then one file
+/// per directory from the repository root down to the current directory.
+pub fn config_file_paths(explicit: Option<&Path>) -> Result<Vec<PathBuf>, ConfigError> {
+    if let Some(path) = explicit {
+        if !path.is_file() {
+            return Err(ConfigError::ExplicitNotFound(path.to_path_buf()));
+        }
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let cwd = std::env::current_dir()?;
+    let home = dirs::home_dir();
+    let xdg_config_home = xdg_config_home(home.as_deref());
+
+    Ok(discover(&cwd, home.as_deref(), xdg_config_home.as_deref()))
+}
+
+/// The files and directories whose settings may run commands, such as
+/// `credential_command`: the explicit file, or else the user's own files, the
+/// `tedlt` directory of `$XDG_CONFIG_HOME` and the file in the home directory.
+/// Repository files are left out, since any cloned repository can have one.
+pub fn user_config_paths(explicit: Option<&Path>) -> Vec<PathBuf> {
+    let paths = match explicit {
+        Some(path) => vec![path.to_path_buf()],
+        None => {
+            let home = dirs::home_dir();
+            xdg_config_home(home.as_deref())
+                .map(|dir| dir.join("tedlt"))
+                .into_iter()
+                .chain(home.map(|home| home.join(CONFIG_FILE_NAME)))
+                .collect()
+        }
+    };
+    paths
+        .into_iter()
+        .map(|path| path.canonicalize().unwrap_or(path))
+        .collect()
+}
+
+fn xdg_config_home(home: Option<&Path>) -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.map(|home| home.join(".config")))
+}
+
+fn discover(cwd: &Path, home: Option<&Path>, xdg_config_home: Option<&Path>) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Some(dir) = xdg_config_home {
+        candidates.push(dir.join("tedlt").join(CONFIG_FILE_NAME));
+    }
+    if let Some(home) = home {
+        candidates.push(home.join(CONFIG_FILE_NAME));
+    }
+    candidates.extend(
+        project_dirs(cwd)
+            .into_iter()
+            .map(|dir| dir.join(CONFIG_FILE_NAME)),
+    );
+
+    // The home directory can also be the repository root or the current directory.
+    let mut seen = Vec::new();
+    candidates
+        .into_iter()
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if seen.contains(&canonical) {
+                return false;
+            }
+            seen.push(canonical);
+            true
+        })
+        .collect()
+}
+
+/// The directories from the repository root down to `cwd`, or only `cwd` when
+/// it is not inside a repository.
+fn project_dirs(cwd: &Path) -> Vec<PathBuf> {
+    let Some(root) = cwd.ancestors().find(|dir| dir.join(".git").exists()) else {
+        return vec![cwd.to_path_buf()];
+    };
+
+    let mut dirs: Vec<PathBuf> = cwd
+        .ancestors()
+        .take_while(|dir| *dir != root)
+        .map(Path::to_path_buf)
+        .collect();
+    dirs.push(root.to_path_buf());
+    dirs.reverse();
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_tree(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("tedlt-discovery-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn touch(dir: &Path) -> PathBuf {
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(CONFIG_FILE_NAME);
+        std::fs::write(&path, "{}").unwrap();
+        path
+    }
+
+    #[test]
+    fn test_discover_layers_in_order() {
+        let root = temp_tree("layers");
+        let home = root.join("home");
+        let repo = home.join("repo");
+        let cwd = repo.join("service").join("api");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(&cwd).unwrap();
+
+        let global = touch(&home.join(".config").join("tedlt"));
+        let user = touch(&home);
+        let repo_file = touch(&repo);
+        let cwd_file = touch(&cwd);
+
+        let paths = discover(&cwd, Some(&home), Some(&home.join(".config")));
+        assert_eq!(paths, vec![global, user, repo_file, cwd_file]);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_discover_stops_at_repository_root() {
+        let root = temp_tree("stop");
+        let repo = root.join("repo");
+        let cwd = repo.join("sub");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(&cwd).unwrap();
+
+        touch(&root);
+        let sub_file = touch(&cwd);
+
+        assert_eq!(discover(&cwd, None, None), vec![sub_file]);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_discover_only_cwd_outside_repository() {
+        let root = temp_tree("norepo");
+        let cwd = root.join("a").join("b");
+        std::fs::create_dir_all(&cwd).unwrap();
+
+        touch(&root.join("a"));
+
+        assert!(discover(&cwd, None, None).is_empty());
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_discover_deduplicates_home_as_cwd() {
+        let root = temp_tree("dedup");
+        let home_file = touch(&root);
+
+        assert_eq!(discover(&root, Some(&root), None), vec![home_file]);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_explicit_path_must_exist() {
+        let result = config_file_paths(Some(Path::new("/nonexistent/tedlt.jsonc")));
+        assert!(matches!(result, Err(ConfigError::ExplicitNotFound(_))));
+    }
+}
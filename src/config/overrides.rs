@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use super::origin::{Origin, Traced};
+
+/// A field set from the command line: `--field path=value` replaces the value
+/// at `path`, `--field path+=value` appends to the list at `path`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldOverride {
+    /// The keys leading to the value, such as `["priority", "id"]`.
+    pub path: Vec<String>,
+    pub value: Value,
+    pub append: bool,
+    /// The flag the override was given with.
+    flag: &'static str,
+}
+
+impl FieldOverride {
+    /// Parses a `--field` argument, whose value is a string.
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        Self::parse_with(arg, "--field", |value| Ok(Value::from(value)))
+    }
+
+    /// Parses a `--field-json` argument, whose value is JSON.
+    pub fn parse_json(arg: &str) -> Result<Self, String> {
+        Self::parse_with(arg, "--field-json", |value| {
+            serde_json::from_str(value).map_err(|e| format!("invalid JSON `{}`: {}", value, e))
+        })
+    }
+
+    fn parse_with(
+        arg: &str,
+        flag: &'static str,
+        value: impl Fn(&str) -> Result<Value, String>,
+    ) -> Result<Self, String> {
+        let (target, raw) = arg
+            .split_once('=')
+            .ok_or_else(|| format!("expected PATH=VALUE or PATH+=VALUE, got `{}`", arg))?;
+        let (target, append) = match target.strip_suffix('+') {
+            Some(target) => (target, true),
+            None => (target, false),
+        };
+
+        let path: Vec<String> = target
+            .split('.')
+            .map(|key| key.trim().to_string())
+            .collect();
+        if path.iter().any(String::is_empty) {
+            return Err(format!("invalid field path `{}`", target));
+        }
+
+        Ok(Self {
+            path,
+            value: value(raw)?,
+            append,
+            flag,
+        })
+    }
+
+    /// Merges the value over `fields`. A replaced value is removed first, so
+    /// that lists and objects are not merged with it.
+    fn apply(&self, mut fields: Traced) -> Traced {
+        if !self.append {
+            let path: Vec<&str> = self.path.iter().map(String::as_str).collect();
+            fields.remove(&path);
+        }
+
+        let mut value = match &self.value {
+            Value::Array(_) => self.value.clone(),
+            value if self.append => Value::Array(vec![value.clone()]),
+            value => value.clone(),
+        };
+        for key in self.path.iter().rev() {
+            value = Value::Object(Map::from_iter([(key.clone(), value)]));
+        }
+
+        fields.deep_merge(Traced::new(value, &Origin::Cli(self.flag)))
+    }
+}
+
+/// Parses a `--set name=value` argument.
+pub fn parse_property(arg: &str) -> Result<(String, String), String> {
+    let (name, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got `{}`", arg))?;
+    let name = name.trim();
+    if name.is_empty() || name.split('.').any(str::is_empty) {
+        return Err(format!("invalid property name `{}`", name));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// The fields and properties set from the command line of `create` and
+/// `config show`.
+#[derive(Debug, Default, Clone)]
+pub struct FieldOverrides {
+    /// The `--field` then `--field-json` values, merged over the profiles.
+    pub fields: Vec<FieldOverride>,
+    /// The `--set` values, by property name.
+    pub properties: Vec<(String, String)>,
+}
+
+impl FieldOverrides {
+    /// Merges the `--field` and `--field-json` values over `fields`, in order.
+    pub fn apply_fields(&self, fields: Traced) -> Traced {
+        self.fields
+            .iter()
+            .fold(fields, |fields, field| field.apply(fields))
+    }
+
+    /// Sets the `--set` values in the properties of the config file. Dotted
+    /// names set nested properties, like they are referenced.
+    pub fn apply_properties(
+        &self,
+        mut properties: HashMap<String, Value>,
+    ) -> HashMap<String, Value> {
+        for (name, value) in &self.properties {
+            let mut keys = name.split('.');
+            let first = keys.next().expect("split yields at least one key");
+            let mut target = properties.entry(first.to_string()).or_insert(Value::Null);
+            for key in keys {
+                if !target.is_object() {
+                    *target = Value::Object(Map::new());
+                }
+                target = target
+                    .as_object_mut()
+                    .expect("made an object")
+                    .entry(key)
+                    .or_insert(Value::Null);
+            }
+            *target = Value::from(value.as_str());
+        }
+        properties
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_field() {
+        let field = FieldOverride::parse("priority.id=2").unwrap();
+        assert_eq!(field.path, vec!["priority", "id"]);
+        assert_eq!(field.value, json!("2"));
+        assert!(!field.append);
+
+        let field = FieldOverride::parse("labels+=a=b").unwrap();
+        assert_eq!(field.path, vec!["labels"]);
+        assert_eq!(field.value, json!("a=b"));
+        assert!(field.append);
+
+        let field = FieldOverride::parse_json(r#"components=[{"id": "1"}]"#).unwrap();
+        assert_eq!(field.value, json!([{ "id": "1" }]));
+
+        assert!(FieldOverride::parse("labels").is_err());
+        assert!(FieldOverride::parse("priority..id=2").is_err());
+        assert!(FieldOverride::parse_json("labels={").is_err());
+    }
+
+    #[test]
+    fn test_replace_and_append() {
+        let profile = Origin::Profile {
+            name: "bug".to_string(),
+            file: None,
+        };
+        let fields = Traced::new(
+            json!({ "labels": ["bug"], "priority": { "id": "3" }, "components": [{ "id": "1" }] }),
+            &profile,
+        );
+
+        let overrides = FieldOverrides {
+            fields: vec![
+                FieldOverride::parse("labels+=hotfix").unwrap(),
+                FieldOverride::parse("priority.id=2").unwrap(),
+                FieldOverride::parse_json(r#"components=[{"id": "2"}]"#).unwrap(),
+                FieldOverride::parse("customfield_10016=5").unwrap(),
+            ],
+            ..Default::default()
+        };
+        let fields = overrides.apply_fields(fields);
+
+        assert_eq!(
+            fields.value,
+            json!({
+                "labels": ["bug", "hotfix"],
+                "priority": { "id": "2" },
+                "components": [{ "id": "2" }],
+                "customfield_10016": "5",
+            })
+        );
+        assert_eq!(fields.origins.at(&["labels", "0"]), Some(&profile));
+        assert_eq!(
+            fields.origins.at(&["labels", "1"]),
+            Some(&Origin::Cli("--field"))
+        );
+        assert_eq!(
+            fields.origins.at(&["components", "0", "id"]),
+            Some(&Origin::Cli("--field-json"))
+        );
+        assert_eq!(
+            fields.origins.subtree(&["components"]).unwrap().profiles(),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn test_apply_properties() {
+        let overrides = FieldOverrides {
+            properties: vec![
+                ("team".to_string(), "payments".to_string()),
+                ("issueTypes.epic".to_string(), "10002".to_string()),
+            ],
+            ..Default::default()
+        };
+        let properties = HashMap::from([
+            ("team".to_string(), json!("core")),
+            (
+                "issueTypes".to_string(),
+                json!({ "epic": "10001", "bug": "10004" }),
+            ),
+        ]);
+
+        let properties = overrides.apply_properties(properties);
+
+        assert_eq!(properties["team"], json!("payments"));
+        assert_eq!(
+            properties["issueTypes"],
+            json!({ "epic": "10002", "bug": "10004" })
+        );
+        assert!(parse_property("=x").is_err());
+        assert_eq!(
+            parse_property("a.b=c=d").unwrap(),
+            ("a.b".to_string(), "c=d".to_string())
+        );
+    }
+}
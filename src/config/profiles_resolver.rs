@@ -1,12 +1,79 @@
 use std::collections::{HashMap, HashSet};
 
 use serde_json::Value;
-use tracing::info;
 
-use super::{ConfigError, file::ProfileDef};
+use super::{
+    ConfigError,
+    file::ProfileDef,
+    origin::{Origin, Origins, Traced},
+};
 
 pub const DEFAULT_PROFILE: &str = "default";
 
+/// Where the values of a resolved profile come from. `None` when the value is
+/// not set, or not tracked.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileOrigins {
+    pub jira_url: Option<Origin>,
+    pub project_key: Option<Origin>,
+    pub fields: Option<Origins>,
+}
+
+/// A profile along with the origin of each of its values.
+#[derive(Debug, Clone)]
+pub struct TracedProfile {
+    pub profile: ProfileDef,
+    pub origins: ProfileOrigins,
+}
+
+impl TracedProfile {
+    /// The profile `name` as defined in the config, with the files its values
+    /// come from taken from `file_origins` (the origins of the config file).
+    fn defined(name: &str, profile: &ProfileDef, file_origins: &Origins) -> Self {
+        let origin = |key: &str| {
+            let file = match file_origins.at(&["profiles", name, key]) {
+                Some(Origin::File(file)) => file.clone(),
+                _ => None,
+            };
+            Origin::Profile {
+                name: name.to_string(),
+                file,
+            }
+        };
+
+        let fields = profile.fields.as_ref().map(|fields| {
+            match file_origins.subtree(&["profiles", name, "fields"]) {
+                Some(origins) => origins.map(&|leaf| match leaf {
+                    Origin::File(file) => Origin::Profile {
+                        name: name.to_string(),
+                        file: file.clone(),
+                    },
+                    other => other.clone(),
+                }),
+                _ => Origins::uniform(fields, &origin("fields")),
+            }
+        });
+
+        Self {
+            origins: ProfileOrigins {
+                jira_url: profile.jira_url.as_ref().map(|_| origin("jira_url")),
+                project_key: profile.project_key.as_ref().map(|_| origin("project_key")),
+                fields,
+            },
+            profile: profile.clone(),
+        }
+    }
+}
+
+impl From<ProfileDef> for TracedProfile {
+    fn from(profile: ProfileDef) -> Self {
+        Self {
+            profile,
+            origins: ProfileOrigins::default(),
+        }
+    }
+}
+
 /// Resolves and merges multiple profiles into a single ProfileDef.
 /// Profiles are merged from left to right, with later profiles overriding earlier ones.
 /// Merging is deep for objects, meaning nested fields are merged recursively.
@@ -15,13 +82,17 @@ pub struct ProfilesResolver;
 impl ProfilesResolver {
     /// Merges multiple ProfileDef instances from left to right.
     /// Returns None if the input is empty.
+    #[cfg(test)]
     pub fn merge_profiles(profiles: Vec<ProfileDef>) -> Option<ProfileDef> {
-        if profiles.is_empty() {
-            return None;
-        }
+        Self::merge_traced_profiles(profiles.into_iter().map(TracedProfile::from).collect())
+            .map(|merged| merged.profile)
+    }
 
+    /// Merges profiles like `merge_profiles`, keeping track of the origin of
+    /// each value.
+    pub fn merge_traced_profiles(profiles: Vec<TracedProfile>) -> Option<TracedProfile> {
         let mut iter = profiles.into_iter();
-        let mut merged = iter.next().unwrap();
+        let mut merged = iter.next()?;
 
         for profile in iter {
             merged = Self::merge_two_profiles(merged, profile);
@@ -30,20 +101,50 @@ impl ProfilesResolver {
         Some(merged)
     }
 
-    /// Merges two ProfileDef instances, with `right` overriding `left`.
-    fn merge_two_profiles(left: ProfileDef, right: ProfileDef) -> ProfileDef {
-        ProfileDef {
-            jira_url: right.jira_url.or(left.jira_url),
-            project_key: right.project_key.or(left.project_key),
-            fields: match (left.fields, right.fields) {
-                (Some(left_fields), Some(right_fields)) => {
-                    Some(Self::deep_merge_json(left_fields, right_fields))
-                }
-                (Some(left_fields), None) => Some(left_fields),
-                (None, Some(right_fields)) => Some(right_fields),
-                (None, None) => None,
+    /// Merges two profiles, with `right` overriding `left`.
+    fn merge_two_profiles(left: TracedProfile, right: TracedProfile) -> TracedProfile {
+        // Selecting an instance also selects its URL over the ones of the
+        // profiles merged before, so its credentials go to its own host.
+        let (jira_url, jira_url_origin) = match right.profile.jira_url {
+            Some(url) => (Some(url), right.origins.jira_url),
+            None if right.profile.instance.is_some() => (None, None),
+            None => (left.profile.jira_url, left.origins.jira_url),
+        };
+        let (project_key, project_key_origin) = match right.profile.project_key {
+            Some(key) => (Some(key), right.origins.project_key),
+            None => (left.profile.project_key, left.origins.project_key),
+        };
+        let traced = |value: Option<Value>, origins: Option<Origins>| {
+            value.map(|value| Traced {
+                value,
+                origins: origins.unwrap_or_default(),
+            })
+        };
+        let fields = match (
+            traced(left.profile.fields, left.origins.fields),
+            traced(right.profile.fields, right.origins.fields),
+        ) {
+            (Some(left_fields), Some(right_fields)) => Some(left_fields.deep_merge(right_fields)),
+            (left_fields, right_fields) => right_fields.or(left_fields),
+        };
+        let (fields, fields_origins) = match fields {
+            Some(Traced { value, origins }) => (Some(value), Some(origins)),
+            None => (None, None),
+        };
+
+        TracedProfile {
+            profile: ProfileDef {
+                jira_url,
+                project_key,
+                fields,
+                inherits: vec![], // Merged profiles don't need inherits anymore
+                instance: right.profile.instance.or(left.profile.instance),
+            },
+            origins: ProfileOrigins {
+                jira_url: jira_url_origin,
+                project_key: project_key_origin,
+                fields: fields_origins,
             },
-            inherits: vec![], // Merged profiles don't need inherits anymore
         }
     }
 
@@ -84,6 +185,18 @@ pub fn resolve_profile(
     profiles: &HashMap<String, ProfileDef>,
     profile_names: &[String],
 ) -> Result<Option<ProfileDef>, ConfigError> {
+    resolve_traced_profile(profiles, profile_names, &Origins::default())
+        .map(|resolved| resolved.map(|resolved| resolved.profile))
+}
+
+/// Resolves profiles like `resolve_profile`, keeping track of the profile and
+/// file each value comes from. `file_origins` are the origins of the config
+/// file the profiles were read from.
+pub fn resolve_traced_profile(
+    profiles: &HashMap<String, ProfileDef>,
+    profile_names: &[String],
+    file_origins: &Origins,
+) -> Result<Option<TracedProfile>, ConfigError> {
     // If no profiles requested and no profiles defined, return None
     if profiles.is_empty() && profile_names.is_empty() {
         return Ok(None);
@@ -98,16 +211,15 @@ pub fn resolve_profile(
     if profile_names.is_empty() {
         return match profiles.get(DEFAULT_PROFILE) {
             Some(_) => {
-                info!("Using profile '{}'", DEFAULT_PROFILE);
                 // Resolve default profile with inheritance
-                resolve_profile_with_inheritance(profiles, DEFAULT_PROFILE).map(Some)
+                resolve_profile_with_inheritance(profiles, DEFAULT_PROFILE, file_origins).map(Some)
             }
             None => Ok(None),
         };
     }
 
     // Resolve and merge the requested profiles
-    resolve_profile_names_with_inheritance(profiles, profile_names)
+    resolve_profile_names_with_inheritance(profiles, profile_names, file_origins)
 }
 
 /// Resolves a single profile with all its inheritance chain.
@@ -115,14 +227,15 @@ pub fn resolve_profile(
 fn resolve_profile_with_inheritance(
     profiles_map: &HashMap<String, ProfileDef>,
     profile_name: &str,
-) -> Result<ProfileDef, ConfigError> {
+    file_origins: &Origins,
+) -> Result<TracedProfile, ConfigError> {
     // Validate that default profile doesn't have inherits
-    if let Some(default_profile) = profiles_map.get(DEFAULT_PROFILE) {
-        if !default_profile.inherits.is_empty() {
-            return Err(ConfigError::InvalidConfig(
-                "The 'default' profile cannot have an 'inherits' field".to_string(),
-            ));
-        }
+    if let Some(default_profile) = profiles_map.get(DEFAULT_PROFILE)
+        && !default_profile.inherits.is_empty()
+    {
+        return Err(ConfigError::InvalidConfig(
+            "The 'default' profile cannot have an 'inherits' field".to_string(),
+        ));
     }
 
     let mut visited = HashSet::new();
@@ -137,12 +250,13 @@ fn resolve_profile_with_inheritance(
     )?;
 
     // Now merge all profiles in the resolution order
-    let profiles_to_merge: Vec<ProfileDef> = resolution_order
+    let profiles_to_merge: Vec<TracedProfile> = resolution_order
         .iter()
-        .map(|name| profiles_map.get(name).unwrap().clone())
+        .map(|name| TracedProfile::defined(name, &profiles_map[name], file_origins))
         .collect();
 
-    Ok(ProfilesResolver::merge_profiles(profiles_to_merge).expect("Chain should not be empty"))
+    Ok(ProfilesResolver::merge_traced_profiles(profiles_to_merge)
+        .expect("Chain should not be empty"))
 }
 
 /// Collects the complete inheritance chain for a profile in the correct order (lowest to highest priority).
@@ -210,7 +324,8 @@ fn collect_profile_chain(
 fn resolve_profile_names_with_inheritance(
     profiles_map: &HashMap<String, ProfileDef>,
     profile_names: &[String],
-) -> Result<Option<ProfileDef>, ConfigError> {
+    file_origins: &Origins,
+) -> Result<Option<TracedProfile>, ConfigError> {
     if profile_names.is_empty() {
         return Ok(None);
     }
@@ -218,18 +333,11 @@ fn resolve_profile_names_with_inheritance(
     let mut resolved_profiles = Vec::new();
 
     for name in profile_names {
-        info!("Loading profile '{}'", name);
-        let resolved = resolve_profile_with_inheritance(profiles_map, name)?;
+        let resolved = resolve_profile_with_inheritance(profiles_map, name, file_origins)?;
         resolved_profiles.push(resolved);
     }
 
-    if resolved_profiles.len() == 1 {
-        info!("Using profile '{}'", profile_names[0]);
-    } else {
-        info!("Merging profiles: {:?}", profile_names);
-    }
-
-    Ok(ProfilesResolver::merge_profiles(resolved_profiles))
+    Ok(ProfilesResolver::merge_traced_profiles(resolved_profiles))
 }
 
 #[cfg(test)]
@@ -250,6 +358,7 @@ mod tests {
             project_key: Some("TEST".to_string()),
             fields: Some(json!({"key": "value"})),
             inherits: vec![],
+            instance: None,
         };
 
         let result = ProfilesResolver::merge_profiles(vec![profile.clone()]);
@@ -267,6 +376,7 @@ mod tests {
             project_key: Some("FIRST".to_string()),
             fields: None,
             inherits: vec![],
+            instance: None,
         };
 
         let profile2 = ProfileDef {
@@ -274,6 +384,7 @@ mod tests {
             project_key: None,
             fields: None,
             inherits: vec![],
+            instance: None,
         };
 
         let result = ProfilesResolver::merge_profiles(vec![profile1, profile2]);
@@ -295,6 +406,7 @@ mod tests {
                 "field2": "value2"
             })),
             inherits: vec![],
+            instance: None,
         };
 
         let profile2 = ProfileDef {
@@ -305,6 +417,7 @@ mod tests {
                 "field3": "value3"
             })),
             inherits: vec![],
+            instance: None,
         };
 
         let profile3 = ProfileDef {
@@ -314,6 +427,7 @@ mod tests {
                 "field1": "final1"
             })),
             inherits: vec![],
+            instance: None,
         };
 
         let result = ProfilesResolver::merge_profiles(vec![profile1, profile2, profile3]);
@@ -455,6 +569,7 @@ mod tests {
                 "labels": ["work-item"]
             })),
             inherits: vec![],
+            instance: None,
         };
 
         let profile2 = ProfileDef {
@@ -465,6 +580,7 @@ mod tests {
                 "labels": ["bug"]
             })),
             inherits: vec![],
+            instance: None,
         };
 
         let result = ProfilesResolver::merge_profiles(vec![profile1, profile2]);
@@ -481,7 +597,7 @@ mod tests {
     #[test]
     fn test_resolve_profile_names_empty() {
         let profiles = std::collections::HashMap::new();
-        let result = resolve_profile_names_with_inheritance(&profiles, &[]);
+        let result = resolve_profile_names_with_inheritance(&profiles, &[], &Origins::default());
         assert!(result.is_ok());
         assert!(result.unwrap().is_none());
     }
@@ -489,7 +605,11 @@ mod tests {
     #[test]
     fn test_resolve_profile_names_not_found() {
         let profiles = std::collections::HashMap::new();
-        let result = resolve_profile_names_with_inheritance(&profiles, &["missing".to_string()]);
+        let result = resolve_profile_names_with_inheritance(
+            &profiles,
+            &["missing".to_string()],
+            &Origins::default(),
+        );
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
@@ -507,12 +627,17 @@ mod tests {
                 project_key: Some("WORK".to_string()),
                 fields: None,
                 inherits: vec![],
+                instance: None,
             },
         );
 
-        let result = resolve_profile_names_with_inheritance(&profiles, &["work".to_string()]);
+        let result = resolve_profile_names_with_inheritance(
+            &profiles,
+            &["work".to_string()],
+            &Origins::default(),
+        );
         assert!(result.is_ok());
-        let merged = result.unwrap().unwrap();
+        let merged = result.unwrap().unwrap().profile;
         assert_eq!(merged.jira_url, Some("https://work.com".to_string()));
     }
 
@@ -526,6 +651,7 @@ mod tests {
                 project_key: Some("BASE".to_string()),
                 fields: Some(json!({"field1": "value1"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -535,15 +661,17 @@ mod tests {
                 project_key: Some("OVERRIDE".to_string()),
                 fields: Some(json!({"field2": "value2"})),
                 inherits: vec![],
+                instance: None,
             },
         );
 
         let result = resolve_profile_names_with_inheritance(
             &profiles,
             &["base".to_string(), "override".to_string()],
+            &Origins::default(),
         );
         assert!(result.is_ok());
-        let merged = result.unwrap().unwrap();
+        let merged = result.unwrap().unwrap().profile;
         assert_eq!(merged.jira_url, Some("https://base.com".to_string()));
         assert_eq!(merged.project_key, Some("OVERRIDE".to_string()));
 
@@ -565,6 +693,7 @@ mod tests {
                 project_key: Some("PARENT".to_string()),
                 fields: Some(json!({"field1": "parent_value"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -574,6 +703,7 @@ mod tests {
                 project_key: Some("CHILD".to_string()),
                 fields: Some(json!({"field2": "child_value"})),
                 inherits: vec!["parent".to_string()],
+                instance: None,
             },
         );
 
@@ -603,6 +733,7 @@ mod tests {
                 project_key: Some("BASE1".to_string()),
                 fields: Some(json!({"field1": "from_base1", "field2": "from_base1"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -612,6 +743,7 @@ mod tests {
                 project_key: None,
                 fields: Some(json!({"field2": "from_base2", "field3": "from_base2"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -621,6 +753,7 @@ mod tests {
                 project_key: Some("CHILD".to_string()),
                 fields: Some(json!({"field3": "from_child"})),
                 inherits: vec!["base1".to_string(), "base2".to_string()],
+                instance: None,
             },
         );
 
@@ -650,6 +783,7 @@ mod tests {
                 project_key: Some("GRAND".to_string()),
                 fields: Some(json!({"field1": "grandparent"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -659,6 +793,7 @@ mod tests {
                 project_key: Some("PARENT".to_string()),
                 fields: Some(json!({"field2": "parent"})),
                 inherits: vec!["grandparent".to_string()],
+                instance: None,
             },
         );
         profiles.insert(
@@ -668,6 +803,7 @@ mod tests {
                 project_key: None,
                 fields: Some(json!({"field3": "child"})),
                 inherits: vec!["parent".to_string()],
+                instance: None,
             },
         );
 
@@ -697,6 +833,7 @@ mod tests {
                 project_key: Some("DEFAULT".to_string()),
                 fields: Some(json!({"field1": "default", "field2": "default"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -706,6 +843,7 @@ mod tests {
                 project_key: Some("MINE".to_string()),
                 fields: Some(json!({"field2": "mine"})),
                 inherits: vec![],
+                instance: None,
             },
         );
 
@@ -737,6 +875,7 @@ mod tests {
                     json!({"field1": "default", "field2": "default", "field3": "default"}),
                 ),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -746,6 +885,7 @@ mod tests {
                 project_key: None,
                 fields: Some(json!({"field2": "base"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -755,6 +895,7 @@ mod tests {
                 project_key: Some("MINE".to_string()),
                 fields: Some(json!({"field3": "mine"})),
                 inherits: vec!["base".to_string()],
+                instance: None,
             },
         );
 
@@ -784,6 +925,7 @@ mod tests {
                 project_key: None,
                 fields: None,
                 inherits: vec!["b".to_string()],
+                instance: None,
             },
         );
         profiles.insert(
@@ -793,6 +935,7 @@ mod tests {
                 project_key: Some("B".to_string()),
                 fields: None,
                 inherits: vec!["a".to_string()],
+                instance: None,
             },
         );
 
@@ -815,6 +958,7 @@ mod tests {
                 project_key: None,
                 fields: None,
                 inherits: vec!["b".to_string()],
+                instance: None,
             },
         );
         profiles.insert(
@@ -824,6 +968,7 @@ mod tests {
                 project_key: Some("B".to_string()),
                 fields: None,
                 inherits: vec!["c".to_string()],
+                instance: None,
             },
         );
         profiles.insert(
@@ -833,6 +978,7 @@ mod tests {
                 project_key: None,
                 fields: Some(json!({"field": "c"})),
                 inherits: vec!["a".to_string()],
+                instance: None,
             },
         );
 
@@ -855,6 +1001,7 @@ mod tests {
                 project_key: Some("SELF".to_string()),
                 fields: None,
                 inherits: vec!["self".to_string()],
+                instance: None,
             },
         );
 
@@ -877,6 +1024,7 @@ mod tests {
                 project_key: Some("CHILD".to_string()),
                 fields: None,
                 inherits: vec!["nonexistent".to_string()],
+                instance: None,
             },
         );
 
@@ -904,6 +1052,7 @@ mod tests {
                 project_key: Some("DEFAULT".to_string()),
                 fields: Some(json!({"f1": "default", "f2": "default", "f3": "default", "f4": "default", "f5": "default"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -913,6 +1062,7 @@ mod tests {
                 project_key: None,
                 fields: Some(json!({"f2": "base1", "f3": "base1"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -922,6 +1072,7 @@ mod tests {
                 project_key: None,
                 fields: Some(json!({"f3": "base2", "f4": "base2"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -931,6 +1082,7 @@ mod tests {
                 project_key: Some("MIDDLE".to_string()),
                 fields: Some(json!({"f4": "middle"})),
                 inherits: vec!["base1".to_string(), "base2".to_string()],
+                instance: None,
             },
         );
         profiles.insert(
@@ -940,6 +1092,7 @@ mod tests {
                 project_key: None,
                 fields: Some(json!({"f5": "final"})),
                 inherits: vec!["middle".to_string()],
+                instance: None,
             },
         );
 
@@ -977,6 +1130,7 @@ mod tests {
                 project_key: Some("BASE".to_string()),
                 fields: Some(json!({"f1": "base", "f2": "base"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -986,6 +1140,7 @@ mod tests {
                 project_key: Some("LEFT".to_string()),
                 fields: Some(json!({"f2": "left"})),
                 inherits: vec!["base".to_string()],
+                instance: None,
             },
         );
         profiles.insert(
@@ -995,6 +1150,7 @@ mod tests {
                 project_key: None,
                 fields: Some(json!({"f2": "right", "f3": "right"})),
                 inherits: vec!["base".to_string()],
+                instance: None,
             },
         );
         profiles.insert(
@@ -1004,6 +1160,7 @@ mod tests {
                 project_key: None,
                 fields: Some(json!({"f3": "child"})),
                 inherits: vec!["left".to_string(), "right".to_string()],
+                instance: None,
             },
         );
 
@@ -1033,6 +1190,7 @@ mod tests {
                 project_key: Some("DEFAULT".to_string()),
                 fields: Some(json!({"field": "default"})),
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -1042,6 +1200,7 @@ mod tests {
                 project_key: None,
                 fields: None,
                 inherits: vec![],
+                instance: None,
             },
         );
 
@@ -1065,6 +1224,7 @@ mod tests {
                 project_key: Some("DEFAULT".to_string()),
                 fields: Some(json!({"field": "default"})),
                 inherits: vec![],
+                instance: None,
             },
         );
 
@@ -1087,6 +1247,7 @@ mod tests {
                 project_key: Some("DEFAULT".to_string()),
                 fields: Some(json!({"field": "default"})),
                 inherits: vec!["base".to_string()], // This should cause an error
+                instance: None,
             },
         );
         profiles.insert(
@@ -1096,6 +1257,7 @@ mod tests {
                 project_key: None,
                 fields: None,
                 inherits: vec![],
+                instance: None,
             },
         );
         profiles.insert(
@@ -1105,6 +1267,7 @@ mod tests {
                 project_key: Some("MINE".to_string()),
                 fields: None,
                 inherits: vec![],
+                instance: None,
             },
         );
 
@@ -1124,6 +1287,7 @@ mod tests {
                 project_key: Some("DEFAULT".to_string()),
                 fields: Some(json!({"field": "default"})),
                 inherits: vec!["something".to_string()],
+                instance: None,
             },
         );
 
@@ -41,6 +41,11 @@ impl PropertiesResolver {
         }
     }
 
+    /// The flattened keys of all properties
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.properties.keys()
+    }
+
     /// Gets a property value by its flattened key
     pub fn get(&self, key: &str) -> Option<&Value> {
         self.properties.get(key)
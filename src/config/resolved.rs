@@ -1,8 +1,19 @@
-use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value};
+use tracing::{info, warn};
 
 use super::{
-    CliOverrides, ConfigError, ConfigFile, profiles_resolver::resolve_profile,
-    properties_resolver::PropertiesResolver, value_resolver::ValueResolver,
+    CliOverrides, ConfigError, ConfigFile, FieldOverrides,
+    file::{ApiVersion, AuthMethod, RetryConfig},
+    layers::resolve_path,
+    origin::{Origin, Origins, Traced},
+    profiles_resolver::{DEFAULT_PROFILE, ProfilesResolver, TracedProfile, resolve_traced_profile},
+    properties_resolver::PropertiesResolver,
+    value_resolver::ValueResolver,
 };
 
 #[derive(Debug, Clone)]
@@ -10,44 +21,432 @@ pub struct ResolvedConfig {
     pub jira_url: String,
     pub project_key: String,
     pub fields: Option<Value>,
+    /// The instance selected by the profiles, or the top-level settings.
+    pub instance: Instance,
+    pub retry: RetryConfig,
+    pub origins: ResolvedOrigins,
+}
+
+/// How to authenticate with the Jira instance: an entry of `instances`, or
+/// the top-level settings when no profile selects one.
+#[derive(Debug, Clone)]
+pub struct Instance {
+    /// The name in `instances`, `None` for the top-level settings.
+    pub name: Option<String>,
+    pub auth: AuthMethod,
+    pub email: Option<String>,
+    /// The `env_file`, resolved relative to the file that defines it.
+    pub env_file: Option<PathBuf>,
+    pub credential_command: Option<String>,
+    pub api_version: ApiVersion,
+    /// The top-level `field_aliases`, and those of the instance over them.
+    /// Sorted, so that aliases of the same field are always merged in the
+    /// same order.
+    pub field_aliases: BTreeMap<String, String>,
+}
+
+impl Instance {
+    fn top_level(file: &ConfigFile) -> Self {
+        Self {
+            name: None,
+            auth: file.auth.clone(),
+            email: None,
+            env_file: None,
+            credential_command: trusted_command(
+                file,
+                file.credential_command.as_ref(),
+                &["credential_command"],
+            ),
+            api_version: file.api_version,
+            field_aliases: file
+                .field_aliases
+                .iter()
+                .map(|(alias, target)| (alias.clone(), target.clone()))
+                .collect(),
+        }
+    }
+
+    fn resolve(file: &ConfigFile, name: &str) -> Result<Self, ConfigError> {
+        let def = file
+            .instances
+            .get(name)
+            .ok_or_else(|| ConfigError::InstanceNotFound(name.to_string()))?;
+
+        let env_file = def
+            .env_file
+            .as_deref()
+            .map(
+                |path| match file.origins.at(&["instances", name, "env_file"]) {
+                    Some(Origin::File(Some(written_in))) => resolve_path(path, written_in),
+                    _ => resolve_path(path, Path::new("")),
+                },
+            )
+            .transpose()?;
+
+        Ok(Self {
+            name: Some(name.to_string()),
+            auth: def.auth.clone(),
+            email: def.email.clone(),
+            env_file,
+            credential_command: trusted_command(
+                file,
+                def.credential_command.as_ref(),
+                &["instances", name, "credential_command"],
+            ),
+            api_version: def.api_version,
+            field_aliases: file
+                .field_aliases
+                .iter()
+                .chain(&def.field_aliases)
+                .map(|(alias, target)| (alias.clone(), target.clone()))
+                .collect(),
+        })
+    }
+
+    /// Renames the fields keyed by an alias to the field ID it stands for.
+    /// Aliases of a display name (`@name:`) need Jira, and are left as is.
+    pub fn expand_aliases(
+        &self,
+        fields: &mut Map<String, Value>,
+        mut origins: Option<&mut Origins>,
+    ) {
+        for (alias, target) in &self.field_aliases {
+            if target.starts_with(NAME_PREFIX) {
+                continue;
+            }
+            rename_traced_field(fields, origins.as_deref_mut(), alias, target);
+        }
+    }
+}
+
+/// The `credential_command` at `path`, unless the file that sets it may not
+/// run commands.
+fn trusted_command(file: &ConfigFile, command: Option<&String>, path: &[&str]) -> Option<String> {
+    let command = command?;
+    let origin = file.origins.at(path);
+    if file.may_run_commands(origin) {
+        return Some(command.clone());
+    }
+
+    if let Some(origin) = origin {
+        warn!(
+            "Ignoring the credential_command of {}: only the user config files and --config can run commands",
+            origin
+        );
+    }
+    None
+}
+
+/// The prefix of a string naming something instead of giving its ID: a field
+/// value such as `"@name:Bug"`, or an alias target such as `"@name:Story Points"`.
+pub const NAME_PREFIX: &str = "@name:";
+
+/// Moves the value of field `from` to `to`, merged over any value `to` has.
+pub fn rename_field(fields: &mut Map<String, Value>, from: &str, to: &str) {
+    let Some(value) = fields.remove(from) else {
+        return;
+    };
+    let value = match fields.remove(to) {
+        Some(existing) => ProfilesResolver::deep_merge_json(existing, value),
+        None => value,
+    };
+    fields.insert(to.to_string(), value);
+}
+
+/// Like `rename_field`, moving the origins of the value along with it.
+pub fn rename_traced_field(
+    fields: &mut Map<String, Value>,
+    origins: Option<&mut Origins>,
+    from: &str,
+    to: &str,
+) {
+    let Some(Origins::Object(children)) = origins else {
+        rename_field(fields, from, to);
+        return;
+    };
+    let Some(value) = fields.remove(from) else {
+        return;
+    };
+
+    let moved = Traced {
+        value,
+        origins: children.remove(from).unwrap_or_default(),
+    };
+    let merged = match fields.remove(to) {
+        Some(existing) => Traced {
+            value: existing,
+            origins: children.remove(to).unwrap_or_default(),
+        }
+        .deep_merge(moved),
+        None => moved,
+    };
+    fields.insert(to.to_string(), merged.value);
+    children.insert(to.to_string(), merged.origins);
+}
+
+/// Where the values of a `ResolvedConfig` come from.
+#[derive(Debug, Clone)]
+pub struct ResolvedOrigins {
+    pub jira_url: Origin,
+    pub project_key: Origin,
+    /// The origin of each leaf of `fields`.
+    pub fields: Option<Origins>,
 }
 
 impl ResolvedConfig {
     pub fn build(
         file: &ConfigFile,
-        cli: CliOverrides,
+        cli: &CliOverrides,
+        overrides: &FieldOverrides,
         profile_names: &[String],
     ) -> Result<Self, ConfigError> {
-        let profile = resolve_profile(&file.profiles, profile_names)?;
+        let profile = select_profile(file, profile_names)?;
+        let (instance, jira_url, jira_url_origin) = site(file, cli, profile.as_ref())?;
+        let file_origin = |key: &str| {
+            file.origins
+                .at(&[key])
+                .cloned()
+                .unwrap_or(Origin::File(None))
+        };
+        let profile_origin = |origin: &Option<Origin>| origin.clone().unwrap_or(Origin::File(None));
 
-        let jira_url = std::env::var("JIRA_URL")
-            .ok()
-            .or(cli.jira_url)
-            .or_else(|| profile.as_ref().and_then(|p| p.jira_url.clone()))
-            .or_else(|| file.jira_url.clone())
-            .ok_or_else(|| ConfigError::MissingField("jira_url".into()))?;
-
-        let project_key = cli
+        let (project_key, project_key_origin) = cli
             .project_key
-            .or_else(|| profile.as_ref().and_then(|p| p.project_key.clone()))
-            .or_else(|| file.project_key.clone())
+            .clone()
+            .map(|key| (key, Origin::Cli("--project-key")))
+            .or_else(|| {
+                let profile = profile.as_ref()?;
+                let key = profile.profile.project_key.clone()?;
+                Some((key, profile_origin(&profile.origins.project_key)))
+            })
+            .or_else(|| Some((file.project_key.clone()?, file_origin("project_key"))))
             .ok_or_else(|| ConfigError::MissingField("project_key".into()))?;
 
-        let properties_resolver = PropertiesResolver::new(file.properties.clone());
+        let properties_resolver =
+            PropertiesResolver::new(overrides.apply_properties(file.properties.clone()));
         let value_resolver = ValueResolver::new(&properties_resolver);
 
-        let fields = profile
-            .as_ref()
-            .and_then(|p| p.fields.as_ref())
-            .map(|f| value_resolver.resolve(f))
-            .transpose()?;
-
-        reqwest::Url::parse(&jira_url).map_err(|_| ConfigError::InvalidUrl(jira_url.clone()))?;
+        let profile_fields = profile.and_then(|p| Some((p.profile.fields?, p.origins.fields)));
+        let mut fields = match profile_fields {
+            Some((fields, origins)) => Some(Traced {
+                origins: with_properties(&fields, origins.unwrap_or_default(), &file.origins),
+                value: value_resolver.resolve(&fields)?,
+            }),
+            None => None,
+        };
+        if !overrides.fields.is_empty() {
+            let profile_fields = fields.unwrap_or_else(|| Traced {
+                value: Value::Object(Map::new()),
+                origins: Origins::default(),
+            });
+            fields = Some(overrides.apply_fields(profile_fields));
+        }
+        if let Some(Traced {
+            value: Value::Object(map),
+            origins,
+        }) = &mut fields
+        {
+            instance.expand_aliases(map, Some(origins));
+        }
+        let (fields, fields_origins) = fields.map(|fields| (fields.value, fields.origins)).unzip();
 
         Ok(Self {
             jira_url,
             project_key,
             fields,
+            instance,
+            retry: file.retry.clone(),
+            origins: ResolvedOrigins {
+                jira_url: jira_url_origin,
+                project_key: project_key_origin,
+                fields: fields_origins,
+            },
+        })
+    }
+}
+
+/// The Jira site selected by the profiles, without the settings that only
+/// creating tickets needs, such as the project.
+#[derive(Debug, Clone)]
+pub struct ResolvedSite {
+    pub jira_url: String,
+    pub instance: Instance,
+    pub retry: RetryConfig,
+}
+
+impl ResolvedSite {
+    pub fn build(
+        file: &ConfigFile,
+        cli: &CliOverrides,
+        profile_names: &[String],
+    ) -> Result<Self, ConfigError> {
+        let profile = select_profile(file, profile_names)?;
+        let (instance, jira_url, _) = site(file, cli, profile.as_ref())?;
+        Ok(Self {
+            jira_url,
+            instance,
+            retry: file.retry.clone(),
         })
     }
 }
+
+/// Merges the profiles named on the command line, or the default profile.
+fn select_profile(
+    file: &ConfigFile,
+    profile_names: &[String],
+) -> Result<Option<TracedProfile>, ConfigError> {
+    let profile = resolve_traced_profile(&file.profiles, profile_names, &file.origins)?;
+    match profile_names {
+        [] if profile.is_some() => info!("Using profile '{}'", DEFAULT_PROFILE),
+        [] => {}
+        [name] => info!("Using profile '{}'", name),
+        names => info!("Merging profiles: {:?}", names),
+    }
+    Ok(profile)
+}
+
+/// The instance and URL selected by `profile`, with the origin of the URL.
+fn site(
+    file: &ConfigFile,
+    cli: &CliOverrides,
+    profile: Option<&TracedProfile>,
+) -> Result<(Instance, String, Origin), ConfigError> {
+    let instance = profile
+        .and_then(|p| p.profile.instance.as_deref())
+        .map(|name| Instance::resolve(file, name))
+        .transpose()?
+        .unwrap_or_else(|| Instance::top_level(file));
+
+    let file_origin = |key: &str| {
+        file.origins
+            .at(&[key])
+            .cloned()
+            .unwrap_or(Origin::File(None))
+    };
+    let profile_origin = |origin: &Option<Origin>| origin.clone().unwrap_or(Origin::File(None));
+
+    let (jira_url, jira_url_origin) = std::env::var("JIRA_URL")
+        .ok()
+        .map(|url| (url, Origin::Env("JIRA_URL")))
+        .or_else(|| {
+            cli.jira_url
+                .clone()
+                .map(|url| (url, Origin::Cli("--jira-url")))
+        })
+        .or_else(|| {
+            let profile = profile?;
+            let url = profile.profile.jira_url.clone()?;
+            Some((url, profile_origin(&profile.origins.jira_url)))
+        })
+        .or_else(|| {
+            let name = instance.name.as_ref()?;
+            let origin = Origin::Instance {
+                name: name.clone(),
+                file: match file.origins.at(&["instances", name, "url"]) {
+                    Some(Origin::File(file)) => file.clone(),
+                    _ => None,
+                },
+            };
+            Some((file.instances[name].url.clone(), origin))
+        })
+        .or_else(|| Some((file.jira_url.clone()?, file_origin("jira_url"))))
+        .ok_or_else(|| ConfigError::MissingField("jira_url".into()))?;
+
+    reqwest::Url::parse(&jira_url).map_err(|_| ConfigError::InvalidUrl(jira_url.clone()))?;
+    Ok((instance, jira_url, jira_url_origin))
+}
+
+/// Annotates the origins of the strings of `template` that reference
+/// properties with the properties they reference, and the files defining them.
+fn with_properties(template: &Value, origins: Origins, file_origins: &Origins) -> Origins {
+    match (template, origins) {
+        (Value::Object(_) | Value::Array(_), Origins::Leaf(origin)) => {
+            match Origins::uniform(template, &origin) {
+                leaf @ Origins::Leaf(_) => leaf,
+                origins => with_properties(template, origins, file_origins),
+            }
+        }
+        (Value::Object(map), Origins::Object(mut children)) => Origins::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let origins = children.remove(key).unwrap_or_default();
+                    (key.clone(), with_properties(value, origins, file_origins))
+                })
+                .collect(),
+        ),
+        (Value::Array(items), Origins::Array(children)) => Origins::Array(
+            items
+                .iter()
+                .zip(children)
+                .map(|(value, origins)| with_properties(value, origins, file_origins))
+                .collect(),
+        ),
+        (Value::String(_), Origins::Leaf(origin)) => {
+            let references = ValueResolver::references(template);
+            if references.is_empty() {
+                return Origins::Leaf(origin);
+            }
+
+            let properties = references
+                .into_iter()
+                .map(|name| {
+                    let mut path = vec!["properties"];
+                    path.extend(name.split('.'));
+                    let file = match file_origins.at(&path) {
+                        Some(Origin::File(file)) => file.clone(),
+                        _ => None,
+                    };
+                    (name, file)
+                })
+                .collect();
+            Origins::Leaf(Origin::Property {
+                properties,
+                template: Box::new(origin),
+            })
+        }
+        (_, origins) => origins,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_url_beats_the_default_profile_url() {
+        let input = r#"{
+            "project_key": "TEST",
+            "instances": { "dc": { "url": "https://jira.internal" } },
+            "profiles": {
+                "default": { "jira_url": "https://cloud.atlassian.net" },
+                "ops": { "instance": "dc" },
+                "cloud": { "jira_url": "https://other.atlassian.net" }
+            }
+        }"#;
+        let file = ConfigFile::from_str(input).unwrap();
+        let build = |names: &[&str]| {
+            let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+            ResolvedConfig::build(
+                &file,
+                &CliOverrides::default(),
+                &FieldOverrides::default(),
+                &names,
+            )
+            .unwrap()
+        };
+
+        let resolved = build(&["ops"]);
+        assert_eq!(resolved.jira_url, "https://jira.internal");
+        assert!(matches!(
+            resolved.origins.jira_url,
+            Origin::Instance { ref name, .. } if name == "dc"
+        ));
+
+        // A URL from a profile merged after the instance still wins.
+        let resolved = build(&["ops", "cloud"]);
+        assert_eq!(resolved.jira_url, "https://other.atlassian.net");
+        assert_eq!(resolved.instance.name.as_deref(), Some("dc"));
+
+        assert_eq!(build(&[]).jira_url, "https://cloud.atlassian.net");
+    }
+}
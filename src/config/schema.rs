@@ -0,0 +1,196 @@
+use std::borrow::Cow;
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, generate::SchemaSettings, json_schema};
+use serde_json::Value;
+
+use super::ConfigFile;
+
+/// Where the schema is published, written as `$schema` by `tedlt init`.
+pub const SCHEMA_URL: &str = "https://brequet.github.io/tedlt/tedlt.schema.json";
+
+/// Strings whose `${...}` references are all closed and non-empty. Editors
+/// use JavaScript regexes, so this avoids lookarounds to stay portable.
+const TEMPLATE_PATTERN: &str = r"^([^$]|\$[^{]|\$$|\$\{[^{}]+\})*$";
+
+/// The JSON Schema of `tedlt.jsonc`, generated from `ConfigFile`.
+///
+/// Draft 7 is the version best supported by editors.
+pub fn config_schema() -> Value {
+    let mut schema = SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<ConfigFile>();
+    schema.insert("$id".to_string(), SCHEMA_URL.into());
+    schema.insert("title".to_string(), "tedlt configuration".into());
+    schema.to_value()
+}
+
+/// The keys of the object defined at `pointer` in the schema (`""` for the root).
+pub fn schema_keys(schema: &Value, pointer: &str) -> Vec<String> {
+    schema
+        .pointer(pointer)
+        .and_then(|definition| definition["properties"].as_object())
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Schema of profile `fields`: an object of template values.
+pub fn template_fields(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "object",
+        "additionalProperties": generator.subschema_for::<TemplateValue>(),
+    })
+}
+
+/// Any JSON value, where strings can reference properties.
+struct TemplateValue;
+
+impl JsonSchema for TemplateValue {
+    fn schema_name() -> Cow<'static, str> {
+        "TemplateValue".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let value = generator.subschema_for::<Self>();
+        json_schema!({
+            "description": "Any JSON value. Strings can reference properties with `${name}`, and a string that is a single reference takes the type of the property.",
+            "anyOf": [
+                {
+                    "type": "string",
+                    "pattern": TEMPLATE_PATTERN,
+                },
+                { "type": ["number", "boolean", "null"] },
+                { "type": "array", "items": value },
+                { "type": "object", "additionalProperties": value },
+            ],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::config::{
+        ApiVersion, AuthMethod, ProfileDef, RetryConfig, TitleSyntax, file::InstanceDef,
+    };
+
+    /// The schema published with the documentation.
+    const PUBLISHED_SCHEMA: &str = include_str!("../../docs/public/tedlt.schema.json");
+
+    #[test]
+    fn test_published_schema_is_up_to_date() {
+        let published: Value = serde_json::from_str(PUBLISHED_SCHEMA).unwrap();
+        assert!(
+            published == config_schema(),
+            "docs/public/tedlt.schema.json is out of date with the config types, \
+             regenerate it with `cargo run -- config schema > docs/public/tedlt.schema.json`"
+        );
+    }
+
+    #[test]
+    fn test_schema_covers_every_key() {
+        let schema = config_schema();
+
+        // Every key serialized from the structs must be known to the schema.
+        let config = ConfigFile {
+            schema: Some(SCHEMA_URL.to_string()),
+            include: vec!["team.jsonc".to_string()],
+            jira_url: Some("https://example.atlassian.net".to_string()),
+            credential_command: Some("pass show jira/token".to_string()),
+            api_version: ApiVersion::V3,
+            field_aliases: HashMap::from([(
+                "storyPoints".to_string(),
+                "customfield_10016".to_string(),
+            )]),
+            instances: HashMap::from([(
+                "cloud".to_string(),
+                InstanceDef {
+                    url: "https://example.atlassian.net".to_string(),
+                    auth: AuthMethod::Basic,
+                    email: Some("me@example.com".to_string()),
+                    env_file: Some("cloud.env".to_string()),
+                    credential_command: Some("pass show jira/token".to_string()),
+                    api_version: ApiVersion::V2,
+                    field_aliases: HashMap::from([(
+                        "storyPoints".to_string(),
+                        "@name:Story Points".to_string(),
+                    )]),
+                },
+            )]),
+            title_syntax: TitleSyntax {
+                enabled: false,
+                ..Default::default()
+            },
+            retry: RetryConfig {
+                max_retries: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let Value::Object(serialized) = serde_json::to_value(&config).unwrap() else {
+            panic!("a config serializes to an object");
+        };
+        let root_keys = schema_keys(&schema, "");
+        for key in serialized.keys() {
+            assert!(
+                root_keys.contains(key),
+                "`{}` is missing from the schema",
+                key
+            );
+        }
+
+        let profile: Value = serde_json::to_value(ProfileDef {
+            jira_url: None,
+            project_key: None,
+            fields: None,
+            inherits: vec![],
+            instance: Some("cloud".to_string()),
+        })
+        .unwrap();
+        let profile_keys = schema_keys(&schema, "/definitions/ProfileDef");
+        for key in profile.as_object().unwrap().keys() {
+            assert!(
+                profile_keys.contains(key),
+                "`{}` is missing from ProfileDef",
+                key
+            );
+        }
+
+        let instance = serde_json::to_value(&config.instances["cloud"]).unwrap();
+        let instance_keys = schema_keys(&schema, "/definitions/InstanceDef");
+        for key in instance.as_object().unwrap().keys() {
+            assert!(
+                instance_keys.contains(key),
+                "`{}` is missing from InstanceDef",
+                key
+            );
+        }
+        assert!(!root_keys.contains(&"sources".to_string()));
+    }
+
+    #[test]
+    fn test_template_pattern() {
+        let pattern = Regex::new(TEMPLATE_PATTERN).unwrap();
+
+        for valid in [
+            "",
+            "plain",
+            "${id}",
+            "${a.b}-${c}",
+            "cost: $5",
+            "trailing $",
+        ] {
+            assert!(pattern.is_match(valid), "{:?} should be valid", valid);
+        }
+        for invalid in ["${}", "${unclosed", "text ${a"] {
+            assert!(
+                !pattern.is_match(invalid),
+                "{:?} should be invalid",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,550 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::PathBuf,
+};
+
+use serde_json::Value;
+
+use super::{
+    ConfigError, ConfigFile,
+    diagnostic::{Diagnostic, Severity, locate_key, locate_text},
+    layers::{LayerLoader, SourceFile},
+    profiles_resolver::resolve_profile,
+    properties_resolver::PropertiesResolver,
+    schema::{config_schema, schema_keys},
+    value_resolver::ValueResolver,
+};
+
+/// Everything found wrong with a configuration.
+pub struct ValidationReport {
+    pub files: Vec<PathBuf>,
+    pub profiles: usize,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    pub fn errors(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .count()
+    }
+}
+
+/// Loads the files like `ConfigFile::load_layers`, but goes on after errors to
+/// report every problem: syntax and type errors, profiles that do not resolve,
+/// undefined properties, and warnings for unknown keys, unused properties and
+/// profile names that titles and input files cannot select.
+pub fn validate(paths: &[PathBuf]) -> ValidationReport {
+    let loaded = LayerLoader::load_lenient(paths);
+    let mut validator = Validator {
+        files: &loaded.files,
+        diagnostics: loaded.errors.into_iter().map(error_diagnostic).collect(),
+    };
+
+    for file in &loaded.files {
+        validator.check_file(file);
+    }
+
+    let mut profiles = 0;
+    match serde_json::from_value::<ConfigFile>(loaded.merged.value) {
+        Ok(config) => {
+            profiles = config.profiles.len();
+            validator.check_config(&config);
+        }
+        // Type errors are already reported with their location in each file.
+        Err(e) if !validator.has_errors() => {
+            validator.push(Diagnostic::error(e.to_string()));
+        }
+        Err(_) => {}
+    }
+
+    ValidationReport {
+        files: loaded.files.iter().map(|file| file.path.clone()).collect(),
+        profiles,
+        diagnostics: validator.diagnostics,
+    }
+}
+
+fn error_diagnostic(error: ConfigError) -> Diagnostic {
+    match error {
+        ConfigError::ParseFile(diagnostic) => *diagnostic,
+        error => Diagnostic::error(error.to_string()),
+    }
+}
+
+struct Validator<'a> {
+    files: &'a [SourceFile],
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    /// Finds the file with the highest precedence where `locate` succeeds.
+    fn find(
+        &self,
+        locate: impl Fn(&str) -> Option<(usize, usize)>,
+    ) -> Option<(&SourceFile, usize, usize)> {
+        self.files
+            .iter()
+            .rev()
+            .find_map(|file| locate(&file.content).map(|(line, column)| (file, line, column)))
+    }
+
+    /// Points the diagnostic at the key in the file with the highest precedence
+    /// that defines it.
+    fn at_key(&self, diagnostic: Diagnostic, keys: &[&str]) -> Diagnostic {
+        match self.find(|content| locate_key(content, keys)) {
+            Some((file, line, column)) => diagnostic.at(&file.path, &file.content, line, column),
+            None => diagnostic,
+        }
+    }
+
+    /// Like `at_key`, pointing at `text` inside the value of the key.
+    fn at_text(&self, diagnostic: Diagnostic, keys: &[&str], text: &str) -> Diagnostic {
+        match self.find(|content| locate_text(content, keys, text)) {
+            Some((file, line, column)) => diagnostic.at(&file.path, &file.content, line, column),
+            None => self.at_key(diagnostic, keys),
+        }
+    }
+
+    /// Checks a single file: value types, and keys that tedlt does not know.
+    fn check_file(&mut self, file: &SourceFile) {
+        if let Err(e) = json5::from_str::<ConfigFile>(&file.content) {
+            self.push(Diagnostic::from_json5(&file.path, &file.content, &e));
+        }
+
+        let Ok(Value::Object(root)) = json5::from_str::<Value>(&file.content) else {
+            return;
+        };
+        let locate = |message: String, keys: &[&str]| {
+            let diagnostic = Diagnostic::warning(message);
+            match locate_key(&file.content, keys) {
+                Some((line, column)) => diagnostic.at(&file.path, &file.content, line, column),
+                None => diagnostic,
+            }
+        };
+
+        // The schema is generated from the config types, so it knows every key.
+        let schema = config_schema();
+        let config_keys = schema_keys(&schema, "");
+        let profile_keys = schema_keys(&schema, "/definitions/ProfileDef");
+        let instance_keys = schema_keys(&schema, "/definitions/InstanceDef");
+
+        let mut warnings = Vec::new();
+        for key in root.keys().filter(|k| !config_keys.contains(k)) {
+            warnings.push(locate(format!("unknown key `{}`", key), &[key]));
+        }
+
+        if let Some(Value::Object(profiles)) = root.get("profiles") {
+            for (name, profile) in profiles {
+                let Value::Object(profile) = profile else {
+                    continue;
+                };
+                for key in profile.keys().filter(|k| !profile_keys.contains(k)) {
+                    warnings.push(locate(
+                        format!("unknown key `{}` in profile `{}`", key, name),
+                        &["profiles", name, key],
+                    ));
+                }
+            }
+        }
+
+        if let Some(Value::Object(instances)) = root.get("instances") {
+            for (name, instance) in instances {
+                let Value::Object(instance) = instance else {
+                    continue;
+                };
+                for key in instance.keys().filter(|k| !instance_keys.contains(k)) {
+                    warnings.push(locate(
+                        format!("unknown key `{}` in instance `{}`", key, name),
+                        &["instances", name, key],
+                    ));
+                }
+            }
+        }
+
+        for (section, definition) in [("title_syntax", "TitleSyntax"), ("retry", "RetryConfig")] {
+            let Some(Value::Object(section_value)) = root.get(section) else {
+                continue;
+            };
+            let section_keys = schema_keys(&schema, &format!("/definitions/{}", definition));
+            for key in section_value.keys().filter(|k| !section_keys.contains(k)) {
+                warnings.push(locate(
+                    format!("unknown key `{}` in {}", key, section),
+                    &[section, key],
+                ));
+            }
+        }
+
+        self.diagnostics.extend(warnings);
+    }
+
+    /// Checks the merged configuration: every profile resolves, and every
+    /// property is defined and used.
+    fn check_config(&mut self, config: &ConfigFile) {
+        if let Err(e) = config.title_syntax.validate() {
+            let diagnostic = Diagnostic::error(e.to_string());
+            self.push(self.at_key(diagnostic, &["title_syntax"]));
+        }
+
+        if let Some(url) = &config.jira_url {
+            self.check_url(url, &["jira_url"]);
+        }
+
+        self.check_aliases(&HashMap::new(), &config.field_aliases, &["field_aliases"]);
+
+        let mut instances: Vec<&String> = config.instances.keys().collect();
+        instances.sort();
+        for name in instances {
+            let instance = &config.instances[name];
+            self.check_url(&instance.url, &["instances", name, "url"]);
+            self.check_aliases(
+                &config.field_aliases,
+                &instance.field_aliases,
+                &["instances", name, "field_aliases"],
+            );
+        }
+
+        let properties = PropertiesResolver::new(config.properties.clone());
+        let value_resolver = ValueResolver::new(&properties);
+
+        let mut names: Vec<&String> = config.profiles.keys().collect();
+        names.sort();
+
+        let mut used_properties = HashSet::new();
+        // Errors from an inherited profile show up for every profile inheriting it.
+        let mut seen_errors = HashSet::new();
+
+        for name in names {
+            let profile = &config.profiles[name];
+
+            // Titles (`:name`) and input files split profile lists on these.
+            if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == ',' || c == ';')
+            {
+                let diagnostic = Diagnostic::warning(format!(
+                    "profile `{}` cannot be selected from titles or input files: \
+                     its name is empty or contains whitespace, `,` or `;`",
+                    name
+                ));
+                self.push(self.at_key(diagnostic, &["profiles", name]));
+            }
+
+            if let Some(url) = &profile.jira_url {
+                self.check_url(url, &["profiles", name, "jira_url"]);
+            }
+
+            if let Some(instance) = &profile.instance
+                && !config.instances.contains_key(instance)
+            {
+                let diagnostic = Diagnostic::error(format!(
+                    "profile `{}` uses undefined instance `{}`",
+                    name, instance
+                ));
+                self.push(self.at_key(diagnostic, &["profiles", name, "instance"]));
+            }
+
+            let references = profile
+                .fields
+                .as_ref()
+                .map(ValueResolver::references)
+                .unwrap_or_default();
+            for reference in references {
+                if properties.get(&reference).is_none() {
+                    let diagnostic = Diagnostic::error(format!(
+                        "profile `{}` references undefined property `{}`",
+                        name, reference
+                    ));
+                    let text = format!("${{{}}}", reference);
+                    self.push(self.at_text(diagnostic, &["profiles", name], &text));
+                }
+                used_properties.insert(reference);
+            }
+
+            let resolved =
+                resolve_profile(&config.profiles, std::slice::from_ref(name)).and_then(|profile| {
+                    profile
+                        .and_then(|p| p.fields)
+                        .map(|fields| value_resolver.resolve(&fields))
+                        .transpose()
+                });
+            match resolved {
+                // Undefined properties are reported above, where they are referenced.
+                Ok(_) | Err(ConfigError::VariableNotFound(_)) => {}
+                Err(e) => {
+                    if seen_errors.insert(e.to_string()) {
+                        let diagnostic = Diagnostic::error(format!("profile `{}`: {}", name, e));
+                        self.push(self.at_key(diagnostic, &["profiles", name]));
+                    }
+                }
+            }
+        }
+
+        let mut unused: Vec<&String> = properties
+            .keys()
+            .filter(|key| !used_properties.contains(*key))
+            .collect();
+        unused.sort();
+        for key in unused {
+            let mut keys = vec!["properties"];
+            keys.extend(key.split('.'));
+            let diagnostic = Diagnostic::warning(format!("property `{}` is never used", key));
+            self.push(self.at_key(diagnostic, &keys));
+        }
+    }
+
+    /// Warns about aliases of the same field, which are merged in the order of
+    /// their names when a ticket sets several of them. `own` are the aliases
+    /// defined at `keys`, over the `inherited` ones; only collisions involving
+    /// them are reported.
+    fn check_aliases(
+        &mut self,
+        inherited: &HashMap<String, String>,
+        own: &HashMap<String, String>,
+        keys: &[&str],
+    ) {
+        let mut by_target: BTreeMap<&String, Vec<&String>> = BTreeMap::new();
+        let aliases: BTreeMap<&String, &String> = inherited.iter().chain(own).collect();
+        for (alias, target) in aliases {
+            by_target.entry(target).or_default().push(alias);
+        }
+
+        for (target, aliases) in by_target {
+            let Some(last) = aliases.iter().rev().find(|alias| own.contains_key(**alias)) else {
+                continue;
+            };
+            if aliases.len() < 2 {
+                continue;
+            }
+            let names: Vec<String> = aliases.iter().map(|a| format!("`{}`", a)).collect();
+            let diagnostic = Diagnostic::warning(format!(
+                "aliases {} all stand for `{}`: when a ticket sets several, they are merged in this order",
+                names.join(", "),
+                target
+            ));
+            let mut keys = keys.to_vec();
+            keys.push(last);
+            self.push(self.at_key(diagnostic, &keys));
+        }
+    }
+
+    fn check_url(&mut self, url: &str, keys: &[&str]) {
+        if reqwest::Url::parse(url).is_err() {
+            let diagnostic =
+                Diagnostic::error(ConfigError::InvalidUrl(url.to_string()).to_string());
+            self.push(self.at_key(diagnostic, keys));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn validate_content(name: &str, content: &str) -> ValidationReport {
+        let path = std::env::temp_dir().join(format!(
+            "tedlt-validate-{}-{}.jsonc",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, content).unwrap();
+        let report = validate(std::slice::from_ref(&path));
+        std::fs::remove_file(&path).ok();
+        report
+    }
+
+    fn messages(report: &ValidationReport, severity: Severity) -> Vec<&str> {
+        report
+            .diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .map(|d| d.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_config() {
+        let report = validate_content(
+            "valid",
+            r#"{
+                "jira_url": "https://example.atlassian.net",
+                "properties": { "types": { "bug": "10004" } },
+                "profiles": {
+                    "default": { "fields": { "labels": ["tedlt"] } },
+                    "bug": { "fields": { "issuetype": { "id": "${types.bug}" } } }
+                }
+            }"#,
+        );
+
+        assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+        assert_eq!(report.profiles, 2);
+    }
+
+    #[test]
+    fn test_syntax_error_has_location() {
+        let report = validate_content("syntax", "{\n  \"jira_url\":\n}");
+
+        assert_eq!(report.errors(), 1);
+        let location = report.diagnostics[0].location.as_ref().unwrap();
+        assert_eq!((location.line, location.column), (3, 1));
+    }
+
+    #[test]
+    fn test_type_error_has_location() {
+        let report = validate_content(
+            "type",
+            "{\n  \"profiles\": {\n    \"bug\": { \"inherits\": \"base\" }\n  }\n}",
+        );
+
+        assert_eq!(report.errors(), 1);
+        let location = report.diagnostics[0].location.as_ref().unwrap();
+        assert_eq!(location.line, 3);
+    }
+
+    #[test]
+    fn test_reports_all_problems() {
+        let report = validate_content(
+            "all",
+            r#"{
+                "jira_url": "not a url",
+                "properties": { "used": "1", "unused": "2" },
+                "profiles": {
+                    "bug": { "inherits": ["missing"], "fields": { "a": "${used}", "b": "${nope}" } },
+                    "task": { "fields": { "c": "${other}" }, "fieldz": {} },
+                    "two words": {}
+                },
+                "retry": { "max_retries": 5, "retries": 1 },
+                "colour": "blue"
+            }"#,
+        );
+
+        let errors = messages(&report, Severity::Error);
+        assert!(errors.contains(&"Invalid URL: not a url"), "{:?}", errors);
+        assert!(errors.contains(&"profile `bug` references undefined property `nope`"));
+        assert!(errors.contains(&"profile `task` references undefined property `other`"));
+        assert!(errors.contains(&"profile `bug`: Profile not found: missing"));
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+
+        let warnings = messages(&report, Severity::Warning);
+        assert!(warnings.contains(&"unknown key `colour`"));
+        assert!(warnings.contains(&"unknown key `fieldz` in profile `task`"));
+        assert!(warnings.contains(&"unknown key `retries` in retry"));
+        assert!(warnings.contains(&"property `unused` is never used"));
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.starts_with("profile `two words` cannot be selected"))
+        );
+        assert_eq!(warnings.len(), 5, "{:?}", warnings);
+    }
+
+    #[test]
+    fn test_aliases_of_the_same_field() {
+        let report = validate_content(
+            "aliases",
+            r#"{
+                "field_aliases": { "sp": "customfield_10016", "points": "customfield_10016", "team": "@name:Team" },
+                "instances": {
+                    "dc": {
+                        "url": "https://jira.example.com",
+                        "field_aliases": { "squad": "@name:Team" }
+                    },
+                    "cloud": { "url": "https://example.atlassian.net" }
+                }
+            }"#,
+        );
+
+        assert_eq!(
+            messages(&report, Severity::Warning),
+            vec![
+                "aliases `points`, `sp` all stand for `customfield_10016`: when a ticket sets several, they are merged in this order",
+                "aliases `squad`, `team` all stand for `@name:Team`: when a ticket sets several, they are merged in this order",
+            ]
+        );
+        let location = report.diagnostics[1].location.as_ref().unwrap();
+        assert_eq!(location.line, 6);
+    }
+
+    #[test]
+    fn test_instances() {
+        let report = validate_content(
+            "instances",
+            r#"{
+                "instances": {
+                    "cloud": { "url": "https://example.atlassian.net", "token": "secret" },
+                    "dc": { "url": "jira.internal" }
+                },
+                "profiles": {
+                    "work": { "instance": "cloud" },
+                    "ops": { "instance": "missing" }
+                }
+            }"#,
+        );
+
+        let errors = messages(&report, Severity::Error);
+        assert_eq!(
+            errors,
+            vec![
+                "Invalid URL: jira.internal",
+                "profile `ops` uses undefined instance `missing`"
+            ]
+        );
+        let warnings = messages(&report, Severity::Warning);
+        assert_eq!(warnings, vec!["unknown key `token` in instance `cloud`"]);
+    }
+
+    #[test]
+    fn test_undefined_property_points_at_reference() {
+        let report = validate_content(
+            "reference",
+            "{\n  \"profiles\": {\n    \"bug\": {\n      \"fields\": { \"x\": \"${missing}\" }\n    }\n  }\n}",
+        );
+
+        let diagnostic = &report.diagnostics[0];
+        let location = diagnostic.location.as_ref().unwrap();
+        assert_eq!((location.line, location.column), (4, 25));
+    }
+
+    #[test]
+    fn test_shared_inheritance_error_is_reported_once() {
+        let report = validate_content(
+            "shared",
+            r#"{
+                "profiles": {
+                    "default": { "inherits": ["base"] },
+                    "base": {},
+                    "bug": {}
+                }
+            }"#,
+        );
+
+        assert_eq!(report.errors(), 1, "{:?}", report.diagnostics);
+    }
+
+    #[test]
+    fn test_missing_include_is_reported() {
+        let report = validate_content("include", r#"{ "include": ["nope.jsonc"] }"#);
+
+        assert_eq!(report.errors(), 1);
+        assert!(report.files.iter().all(|f| f != Path::new("nope.jsonc")));
+    }
+}
@@ -38,6 +38,19 @@ impl<'a> ValueResolver<'a> {
         }
     }
 
+    /// Returns the variables referenced in a JSON value, in order of appearance
+    pub fn references(value: &Value) -> Vec<String> {
+        match value {
+            Value::String(s) => VAR_PATTERN
+                .captures_iter(s)
+                .map(|cap| cap[1].to_string())
+                .collect(),
+            Value::Array(arr) => arr.iter().flat_map(Self::references).collect(),
+            Value::Object(obj) => obj.values().flat_map(Self::references).collect(),
+            _ => vec![],
+        }
+    }
+
     /// Resolves template variables in a string (e.g., "${variable}")
     /// Supports multiple variables in one string: "${var1}-${var2}"
     fn resolve_string(&self, s: &str) -> Result<Value, ConfigError> {
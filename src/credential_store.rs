@@ -0,0 +1,192 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::auth::write_private;
+
+/// The file `tedlt auth login` saves credentials to, in the config dir.
+const STORE_FILE_NAME: &str = "credentials.json";
+
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error("Could not determine the config directory")]
+    NoConfigDir,
+
+    #[error("Failed to access the credentials file {path:?}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+
+    #[error("Failed to parse the credentials file {path:?}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+/// Credentials saved by `tedlt auth login`: for each instance (see `key`), the
+/// value of each variable (`JIRA_EMAIL`, `JIRA_API_TOKEN`).
+pub struct CredentialStore {
+    path: PathBuf,
+    instances: BTreeMap<String, HashMap<String, String>>,
+}
+
+impl CredentialStore {
+    /// The path of the store: `~/.config/tedlt/credentials.json` on Linux.
+    pub fn default_path() -> Result<PathBuf, StoreError> {
+        let dir = dirs::config_dir().ok_or(StoreError::NoConfigDir)?;
+        Ok(dir.join("tedlt").join(STORE_FILE_NAME))
+    }
+
+    /// Opens the store at `path`, empty if the file does not exist yet.
+    pub fn open(path: PathBuf) -> Result<Self, StoreError> {
+        let instances = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|source| StoreError::Parse {
+                path: path.clone(),
+                source,
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(source) => return Err(StoreError::Io { path, source }),
+        };
+        Ok(Self { path, instances })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The variables saved under `key`.
+    pub fn get(&self, key: &str) -> Option<&HashMap<String, String>> {
+        self.instances.get(key)
+    }
+
+    pub fn set(&mut self, key: &str, vars: HashMap<String, String>) {
+        self.instances.insert(key.to_string(), vars);
+    }
+
+    /// Removes the credentials saved under `key`, returning whether there were
+    /// any.
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.instances.remove(key).is_some()
+    }
+
+    /// Writes the store, readable only by the current user. An empty store
+    /// deletes the file.
+    pub fn save(&self) -> Result<(), StoreError> {
+        let error = |source| StoreError::Io {
+            path: self.path.clone(),
+            source,
+        };
+
+        if self.instances.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(error(e)),
+                _ => Ok(()),
+            };
+        }
+
+        let content =
+            serde_json::to_string_pretty(&self.instances).map_err(|source| StoreError::Parse {
+                path: self.path.clone(),
+                source,
+            })?;
+        write_private(&self.path, &content).map_err(error)
+    }
+}
+
+/// The key credentials are saved under: the name of the instance, or for the
+/// top-level settings the URL they are sent to, so that the credentials of a
+/// site are not sent to another one given with `--jira-url` or `JIRA_URL`.
+pub fn key(instance: Option<&str>, jira_url: &str) -> String {
+    if let Some(name) = instance {
+        return name.to_string();
+    }
+
+    // Lowercases the host and drops the default port.
+    match reqwest::Url::parse(jira_url) {
+        Ok(url) => url.as_str().trim_end_matches('/').to_string(),
+        Err(_) => jira_url.trim_end_matches('/').to_string(),
+    }
+}
+
+/// The variables saved under `key` in the default store.
+pub fn stored_vars(key: &str) -> Result<HashMap<String, String>, StoreError> {
+    let Ok(path) = CredentialStore::default_path() else {
+        return Ok(HashMap::new());
+    };
+    let store = CredentialStore::open(path)?;
+    Ok(store.get(key).cloned().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("tedlt-store-{}-{}", name, std::process::id()))
+            .join(STORE_FILE_NAME)
+    }
+
+    #[test]
+    fn test_save_and_open() {
+        let path = temp_store("roundtrip");
+        let mut store = CredentialStore::open(path.clone()).unwrap();
+        assert!(store.get("work").is_none());
+
+        store.set(
+            "work",
+            HashMap::from([("JIRA_API_TOKEN".to_string(), "secret".to_string())]),
+        );
+        store.save().unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let store = CredentialStore::open(path.clone()).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+
+        assert_eq!(store.get("work").unwrap()["JIRA_API_TOKEN"], "secret");
+        assert!(store.get("https://example.atlassian.net").is_none());
+    }
+
+    #[test]
+    fn test_key() {
+        assert_eq!(key(Some("dc"), "https://jira.internal"), "dc");
+        assert_eq!(
+            key(None, "https://Example.Atlassian.net:443/"),
+            "https://example.atlassian.net"
+        );
+        assert_eq!(
+            key(None, "https://jira.internal/jira"),
+            "https://jira.internal/jira"
+        );
+        assert_ne!(
+            key(None, "https://example.atlassian.net"),
+            key(None, "https://other.atlassian.net")
+        );
+    }
+
+    #[test]
+    fn test_removing_the_last_instance_deletes_the_file() {
+        let path = temp_store("remove");
+        let mut store = CredentialStore::open(path.clone()).unwrap();
+        store.set("work", HashMap::new());
+        store.save().unwrap();
+        assert!(path.exists());
+
+        assert!(store.remove("work"));
+        assert!(!store.remove("work"));
+        store.save().unwrap();
+        let exists = path.exists();
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+
+        assert!(!exists);
+    }
+}
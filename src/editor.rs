@@ -0,0 +1,200 @@
+use std::{
+    collections::hash_map::RandomState,
+    fs,
+    hash::BuildHasher,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+const HELP: &str = "\
+# Do not modify or remove the line above.
+# Everything below it will be ignored.
+# The first line is the ticket summary, the rest is the description (Markdown).
+# An empty summary aborts the ticket creation.";
+
+/// Opens `$VISUAL` or `$EDITOR` on a ticket message prefilled with `title` and
+/// `description`, the same way `git commit` does, and returns the edited text.
+pub fn edit_ticket_message(title: &str, description: &str) -> io::Result<String> {
+    let path = create_message_file(&build_message(title, description))?;
+
+    let result = open_editor(&path).and_then(|_| fs::read_to_string(&path));
+    fs::remove_file(&path).ok();
+
+    result
+}
+
+/// Writes `content` to a new file of the temporary directory, readable only by
+/// the current user. The name is random and the file must not exist, so that
+/// another user cannot read the message or make us write elsewhere.
+fn create_message_file(content: &str) -> io::Result<PathBuf> {
+    let mut attempts = 0;
+    loop {
+        let suffix = RandomState::new().hash_one(attempts);
+        let path = std::env::temp_dir().join(format!("tedlt-{:016x}.md", suffix));
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        match options.open(&path) {
+            Ok(mut file) => {
+                file.write_all(content.as_bytes())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < 10 => attempts += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Splits an edited message into its summary (first non-empty line) and
+/// description (everything after it, up to the scissors line).
+pub fn parse_ticket_message(message: &str) -> (String, Option<String>) {
+    let message = match message.find(SCISSORS) {
+        Some(index) => &message[..index],
+        None => message,
+    };
+
+    let mut lines = message.lines().skip_while(|line| line.trim().is_empty());
+    let summary = lines.next().unwrap_or_default().trim().to_string();
+    let description = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+
+    (summary, (!description.is_empty()).then_some(description))
+}
+
+fn build_message(title: &str, description: &str) -> String {
+    format!("{}\n\n{}\n{}\n{}\n", title, description, SCISSORS, HELP)
+}
+
+fn open_editor(path: &Path) -> io::Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| default_editor().to_string());
+    if editor.trim().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "$EDITOR is empty",
+        ));
+    }
+
+    let status = editor_command(&editor, path).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "Editor '{}' exited with {}",
+            editor, status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Runs the editor through the shell like git does, so that it may have
+/// arguments (`code --wait`) or be a quoted path with spaces.
+#[cfg(not(windows))]
+fn editor_command(editor: &str, path: &Path) -> Command {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(editor)
+        .arg(path);
+    command
+}
+
+#[cfg(windows)]
+fn editor_command(editor: &str, path: &Path) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    let mut command = Command::new("cmd");
+    command
+        .arg("/C")
+        .raw_arg(format!("{} \"{}\"", editor, path.display()));
+    command
+}
+
+fn default_editor() -> &'static str {
+    if cfg!(windows) { "notepad" } else { "vi" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_summary_and_description() {
+        let message = "Fix login #auth\n\nSteps:\n\n- open the page\n";
+        let (summary, description) = parse_ticket_message(message);
+        assert_eq!(summary, "Fix login #auth");
+        assert_eq!(description.as_deref(), Some("Steps:\n\n- open the page"));
+    }
+
+    #[test]
+    fn test_parse_ignores_text_after_scissors() {
+        let message = build_message("Title", "# Heading\n\nBody");
+        let (summary, description) = parse_ticket_message(&message);
+        assert_eq!(summary, "Title");
+        assert_eq!(description.as_deref(), Some("# Heading\n\nBody"));
+    }
+
+    #[test]
+    fn test_parse_summary_only() {
+        let (summary, description) = parse_ticket_message(&build_message("Title", ""));
+        assert_eq!(summary, "Title");
+        assert_eq!(description, None);
+    }
+
+    #[test]
+    fn test_parse_skips_leading_blank_lines() {
+        let (summary, description) = parse_ticket_message("\n\n  Title  \nBody");
+        assert_eq!(summary, "Title");
+        assert_eq!(description.as_deref(), Some("Body"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_message_file_is_private_and_new() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let first = create_message_file("first").unwrap();
+        let second = create_message_file("second").unwrap();
+        let mode = fs::metadata(&first).unwrap().permissions().mode();
+        let content = fs::read_to_string(&first).unwrap();
+        fs::remove_file(&first).ok();
+        fs::remove_file(&second).ok();
+
+        assert_ne!(first, second);
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(content, "first");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_editor_with_arguments_and_spaces() {
+        let dir = std::env::temp_dir().join(format!("tedlt editor {}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("message file.md");
+        fs::write(&path, "Title\n").unwrap();
+
+        let status = editor_command("sed -i.bak 's/Title/Edited/'", &path)
+            .status()
+            .unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert!(status.success());
+        assert_eq!(content, "Edited\n");
+    }
+
+    #[test]
+    fn test_parse_empty_message() {
+        let (summary, description) = parse_ticket_message(&build_message("", ""));
+        assert_eq!(summary, "");
+        assert_eq!(description, None);
+    }
+}
@@ -1,29 +1,614 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::Mutex,
+};
+
+use once_cell::sync::Lazy;
 use thiserror::Error;
 
+use crate::{
+    config::{AuthMethod, Instance},
+    credential_store::{self, StoreError},
+};
+
+pub const API_TOKEN_VAR: &str = "JIRA_API_TOKEN";
+pub const EMAIL_VAR: &str = "JIRA_EMAIL";
+const OAUTH_CLIENT_SECRET_VAR: &str = "JIRA_OAUTH_CLIENT_SECRET";
+const OAUTH_REFRESH_TOKEN_VAR: &str = "JIRA_OAUTH_REFRESH_TOKEN";
+
+/// A credential command, the instance it runs for and the variable it is
+/// asked for.
+type HelperKey = (String, String, String);
+
+/// Secrets printed by credential commands, so each one runs at most once per
+/// process.
+static HELPER_CACHE: Lazy<Mutex<HashMap<HelperKey, String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 #[derive(Error, Debug)]
 pub enum EnvError {
     #[error("Failed to load .env file: {0}")]
     DotEnv(#[from] dotenvy::Error),
 
+    #[error("Failed to load env_file {path:?} of instance '{instance}': {source}")]
+    InstanceEnvFile {
+        instance: String,
+        path: PathBuf,
+        source: dotenvy::Error,
+    },
+
+    #[error(transparent)]
+    Store(#[from] StoreError),
+
     #[error("Environment variable '{0}' is required but not set")]
     MissingVar(String),
+
+    #[error(
+        "No {var} for instance '{instance}': set '{instance_var}', add {var} to the instance env_file, or run `tedlt auth login`"
+    )]
+    MissingInstanceVar {
+        instance: String,
+        var: &'static str,
+        instance_var: String,
+    },
+
+    #[error("Failed to run credential_command '{command}': {source}")]
+    CredentialCommand {
+        command: String,
+        source: std::io::Error,
+    },
+
+    #[error("credential_command '{command}' failed ({status}): {stderr}")]
+    CredentialCommandFailed {
+        command: String,
+        status: String,
+        stderr: String,
+    },
+
+    #[error("credential_command '{command}' printed a token that is not valid UTF-8")]
+    CredentialCommandOutput { command: String },
 }
 
-pub struct Credentials {
-    pub api_token: String,
-    pub email: String,
+/// The secrets of an auth method.
+pub enum Credentials {
+    Basic {
+        email: String,
+        api_token: String,
+    },
+    Bearer {
+        token: String,
+    },
+    OAuth {
+        client_id: String,
+        client_secret: String,
+        /// Only needed until a refreshed token is cached.
+        refresh_token: Option<String>,
+        token_url: Option<String>,
+    },
 }
 
 impl Credentials {
-    pub fn load() -> Result<Self, EnvError> {
+    /// Loads the credentials that the auth method of an instance needs.
+    ///
+    /// For an instance named `work`, each credential is read from
+    /// `JIRA_API_TOKEN_WORK` (`JIRA_EMAIL_WORK`, ...), then from the instance
+    /// `env_file`, then from the unsuffixed variables, which are only read for
+    /// the top-level settings, and last from the credentials saved by
+    /// `tedlt auth login`. The email is read where the API token is found, or
+    /// else taken from the instance `email`, so that both belong to the same
+    /// account. The bearer token is read like the API token.
+    ///
+    /// Secrets (API token, OAuth client secret and refresh token) are first
+    /// asked to the `credential_command`, if any; when it prints nothing, the
+    /// variables are used.
+    pub fn load(instance: &Instance, jira_url: &str) -> Result<Self, EnvError> {
         dotenvy::dotenv().ok();
 
-        let api_token = std::env::var("JIRA_API_TOKEN")
-            .map_err(|_| EnvError::MissingVar("JIRA_API_TOKEN".to_string()))?;
+        let vars = std::env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+            .collect();
+        let stored = credential_store::stored_vars(&credential_store::key(
+            instance.name.as_deref(),
+            jira_url,
+        ))?;
+        Self::from_lookup(&Lookup::new(instance, vars, stored)?)
+    }
+
+    fn from_lookup(lookup: &Lookup) -> Result<Self, EnvError> {
+        Ok(match &lookup.instance.auth {
+            AuthMethod::Basic => {
+                let (email, api_token) = lookup.basic()?;
+                Self::Basic { email, api_token }
+            }
+            AuthMethod::Bearer => Self::Bearer {
+                token: lookup.required_secret(API_TOKEN_VAR)?,
+            },
+            AuthMethod::OAuth {
+                client_id,
+                token_url,
+                ..
+            } => Self::OAuth {
+                client_id: client_id.clone(),
+                client_secret: lookup.required_secret(OAUTH_CLIENT_SECRET_VAR)?,
+                refresh_token: lookup.secret(OAUTH_REFRESH_TOKEN_VAR)?,
+                token_url: token_url.clone(),
+            },
+        })
+    }
+}
+
+/// Where credentials are read from, in lookup order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    /// The variables suffixed with the instance name.
+    InstanceVars,
+    EnvFile,
+    /// The unsuffixed variables.
+    Vars,
+    /// The credentials saved by `tedlt auth login`.
+    Stored,
+}
+
+const SOURCES: [Source; 4] = [
+    Source::InstanceVars,
+    Source::EnvFile,
+    Source::Vars,
+    Source::Stored,
+];
+
+/// Reads the credentials of an instance from the environment, its `env_file`
+/// and the saved credentials.
+struct Lookup<'a> {
+    instance: &'a Instance,
+    /// The process environment, once the `.env` file is loaded.
+    vars: HashMap<String, String>,
+    env_file: HashMap<String, String>,
+    stored: HashMap<String, String>,
+}
+
+impl<'a> Lookup<'a> {
+    fn new(
+        instance: &'a Instance,
+        vars: HashMap<String, String>,
+        stored: HashMap<String, String>,
+    ) -> Result<Self, EnvError> {
+        Ok(Self {
+            instance,
+            vars,
+            env_file: read_env_file(instance)?,
+            stored,
+        })
+    }
+
+    fn get(&self, source: Source, var: &str) -> Option<String> {
+        match source {
+            Source::InstanceVars => {
+                let name = self.instance.name.as_ref()?;
+                self.vars.get(&instance_var(var, name)).cloned()
+            }
+            Source::EnvFile => self.env_file.get(var).cloned(),
+            // The unsuffixed variables may hold the credentials of another site,
+            // such as those of a `.env` in the current directory.
+            Source::Vars => match self.instance.name {
+                Some(_) => None,
+                None => self.vars.get(var).cloned(),
+            },
+            Source::Stored => self.stored.get(var).cloned(),
+        }
+    }
+
+    /// The first source setting `var`, with its value.
+    fn find(&self, var: &str) -> Option<(Source, String)> {
+        SOURCES
+            .iter()
+            .find_map(|&source| Some((source, self.get(source, var)?)))
+    }
+
+    fn optional(&self, var: &str) -> Option<String> {
+        self.find(var).map(|(_, value)| value)
+    }
+
+    /// The email and API token, from the same source.
+    fn basic(&self) -> Result<(String, String), EnvError> {
+        let configured = self.instance.email.clone();
+        if let Some(api_token) = self.command_secret(API_TOKEN_VAR)? {
+            let email = self.optional(EMAIL_VAR).or(configured);
+            return Ok((email.ok_or_else(|| self.missing(EMAIL_VAR))?, api_token));
+        }
+
+        let (source, api_token) = self
+            .find(API_TOKEN_VAR)
+            .ok_or_else(|| self.missing(API_TOKEN_VAR))?;
+        let email = self
+            .get(source, EMAIL_VAR)
+            .or(configured)
+            .ok_or_else(|| self.missing(EMAIL_VAR))?;
+        Ok((email, api_token))
+    }
+
+    /// A secret: printed by the `credential_command`, or read like any variable.
+    fn secret(&self, var: &str) -> Result<Option<String>, EnvError> {
+        Ok(self.command_secret(var)?.or_else(|| self.optional(var)))
+    }
+
+    fn command_secret(&self, var: &str) -> Result<Option<String>, EnvError> {
+        match &self.instance.credential_command {
+            Some(command) => run_credential_command(command, var, self.instance.name.as_deref()),
+            None => Ok(None),
+        }
+    }
+
+    fn required_secret(&self, var: &'static str) -> Result<String, EnvError> {
+        self.secret(var)?.ok_or_else(|| self.missing(var))
+    }
+
+    fn missing(&self, var: &'static str) -> EnvError {
+        match &self.instance.name {
+            Some(name) => EnvError::MissingInstanceVar {
+                instance: name.clone(),
+                var,
+                instance_var: instance_var(var, name),
+            },
+            None => EnvError::MissingVar(var.to_string()),
+        }
+    }
+}
+
+/// Runs `command` through the shell and returns the first line it prints, or
+/// `None` when it prints nothing. The variable asked for is passed in
+/// `TEDLT_CREDENTIAL`, and the instance name in `TEDLT_INSTANCE`.
+fn run_credential_command(
+    command: &str,
+    var: &str,
+    instance: Option<&str>,
+) -> Result<Option<String>, EnvError> {
+    let key = (
+        command.to_string(),
+        instance.unwrap_or_default().to_string(),
+        var.to_string(),
+    );
+    if let Some(secret) = HELPER_CACHE.lock().unwrap().get(&key) {
+        return Ok(Some(secret.clone()));
+    }
+
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let output = shell
+        .arg(command)
+        .env("TEDLT_CREDENTIAL", var)
+        .env("TEDLT_INSTANCE", instance.unwrap_or_default())
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|source| EnvError::CredentialCommand {
+            command: command.to_string(),
+            source,
+        })?;
+
+    if !output.status.success() {
+        return Err(EnvError::CredentialCommandFailed {
+            command: command.to_string(),
+            status: output.status.to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    let stdout =
+        String::from_utf8(output.stdout).map_err(|_| EnvError::CredentialCommandOutput {
+            command: command.to_string(),
+        })?;
+    let secret = stdout.lines().next().unwrap_or_default().trim().to_string();
+    if secret.is_empty() {
+        return Ok(None);
+    }
+
+    HELPER_CACHE.lock().unwrap().insert(key, secret.clone());
+    Ok(Some(secret))
+}
+
+/// The name of `var` for an instance: `JIRA_API_TOKEN_MY_SITE` for `my-site`.
+fn instance_var(var: &str, instance: &str) -> String {
+    let suffix: String = instance
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}_{}", var, suffix)
+}
+
+/// Reads the variables of the instance `env_file`, without adding them to the
+/// process environment.
+fn read_env_file(instance: &Instance) -> Result<HashMap<String, String>, EnvError> {
+    let Some(path) = &instance.env_file else {
+        return Ok(HashMap::new());
+    };
+    let error = |source| EnvError::InstanceEnvFile {
+        instance: instance.name.clone().unwrap_or_default(),
+        path: path.clone(),
+        source,
+    };
+
+    dotenvy::from_path_iter(path)
+        .map_err(error)?
+        .collect::<Result<_, _>>()
+        .map_err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(name: &str, auth: AuthMethod, env_file: Option<PathBuf>) -> Instance {
+        Instance {
+            name: Some(name.to_string()),
+            auth,
+            email: Some("config@example.com".to_string()),
+            env_file,
+            credential_command: None,
+            api_version: Default::default(),
+            field_aliases: Default::default(),
+        }
+    }
+
+    /// Loads the credentials without the process environment or the saved
+    /// credentials of the user running the tests.
+    fn load(instance: &Instance) -> Result<Credentials, EnvError> {
+        Credentials::from_lookup(&Lookup::new(instance, HashMap::new(), HashMap::new())?)
+    }
+
+    fn with_command(name: &str, command: &str) -> Instance {
+        Instance {
+            credential_command: Some(command.to_string()),
+            ..instance(name, AuthMethod::Bearer, None)
+        }
+    }
+
+    #[test]
+    fn test_instance_var() {
+        assert_eq!(
+            instance_var(API_TOKEN_VAR, "my-site"),
+            "JIRA_API_TOKEN_MY_SITE"
+        );
+        assert_eq!(instance_var(EMAIL_VAR, "dc2"), "JIRA_EMAIL_DC2");
+    }
+
+    #[test]
+    fn test_instance_credentials_from_env_file() {
+        let path = std::env::temp_dir().join(format!("tedlt-instance-{}.env", std::process::id()));
+        std::fs::write(
+            &path,
+            "JIRA_API_TOKEN=file-token\nJIRA_EMAIL=file@example.com\n",
+        )
+        .unwrap();
+        let instance = instance("tedlt-test-env-file", AuthMethod::Basic, Some(path.clone()));
+
+        let credentials = load(&instance);
+        std::fs::remove_file(&path).ok();
+
+        match credentials.unwrap() {
+            Credentials::Basic { email, api_token } => {
+                assert_eq!(api_token, "file-token");
+                assert_eq!(email, "file@example.com");
+            }
+            _ => panic!("expected basic credentials"),
+        }
+    }
+
+    #[test]
+    fn test_oauth_credentials_from_env_file() {
+        let path = std::env::temp_dir().join(format!("tedlt-oauth-{}.env", std::process::id()));
+        std::fs::write(&path, "JIRA_OAUTH_CLIENT_SECRET=shh\n").unwrap();
+        let auth = AuthMethod::OAuth {
+            client_id: "app".to_string(),
+            token_url: None,
+            authorize_url: None,
+            redirect_uri: None,
+            scopes: None,
+        };
+        let instance = instance("tedlt-test-oauth", auth, Some(path.clone()));
+
+        let credentials = load(&instance);
+        std::fs::remove_file(&path).ok();
+
+        match credentials.unwrap() {
+            Credentials::OAuth {
+                client_id,
+                client_secret,
+                ..
+            } => {
+                assert_eq!(client_id, "app");
+                assert_eq!(client_secret, "shh");
+            }
+            _ => panic!("expected OAuth credentials"),
+        }
+    }
+
+    #[test]
+    fn test_missing_instance_env_file() {
+        let instance = instance(
+            "tedlt-test-missing",
+            AuthMethod::Basic,
+            Some(PathBuf::from("/nonexistent/tedlt.env")),
+        );
+
+        let result = load(&instance);
+        assert!(matches!(result, Err(EnvError::InstanceEnvFile { .. })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_credential_command() {
+        let instance = with_command(
+            "tedlt-test-command",
+            "printf '%s-secret\\nsecond line\\n' \"$TEDLT_INSTANCE\"",
+        );
+
+        match load(&instance).unwrap() {
+            Credentials::Bearer { token } => assert_eq!(token, "tedlt-test-command-secret"),
+            _ => panic!("expected bearer credentials"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_credential_command_runs_once() {
+        let path = std::env::temp_dir().join(format!("tedlt-runs-{}", std::process::id()));
+        let command = format!("echo run >> '{}'; echo token", path.display());
+        let instance = with_command("tedlt-test-runs-once", &command);
+
+        load(&instance).unwrap();
+        load(&instance).unwrap();
+        let runs = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(runs.lines().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_credential_command_failure() {
+        let instance = with_command("tedlt-test-failing", "echo 'not logged in' >&2; exit 3");
+
+        match load(&instance) {
+            Err(EnvError::CredentialCommandFailed { stderr, .. }) => {
+                assert_eq!(stderr, "not logged in");
+            }
+            _ => panic!("expected the command to fail"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_empty_credential_command_falls_back() {
+        let path = std::env::temp_dir().join(format!("tedlt-fallback-{}.env", std::process::id()));
+        std::fs::write(&path, "JIRA_API_TOKEN=file-token\n").unwrap();
+        let instance = Instance {
+            env_file: Some(path.clone()),
+            ..with_command("tedlt-test-fallback", "true")
+        };
+
+        let credentials = load(&instance);
+        std::fs::remove_file(&path).ok();
+
+        match credentials.unwrap() {
+            Credentials::Bearer { token } => assert_eq!(token, "file-token"),
+            _ => panic!("expected bearer credentials"),
+        }
+    }
+
+    #[test]
+    fn test_saved_credentials_come_after_the_variables() {
+        let instance = Instance {
+            name: None,
+            ..instance("unused", AuthMethod::Basic, None)
+        };
+        let stored = HashMap::from([
+            (API_TOKEN_VAR.to_string(), "stored-token".to_string()),
+            (EMAIL_VAR.to_string(), "stored@example.com".to_string()),
+        ]);
+        let lookup = Lookup {
+            instance: &instance,
+            vars: HashMap::from([
+                (API_TOKEN_VAR.to_string(), "env-token".to_string()),
+                (EMAIL_VAR.to_string(), "env@example.com".to_string()),
+            ]),
+            env_file: HashMap::new(),
+            stored: stored.clone(),
+        };
+        assert_eq!(
+            lookup.basic().unwrap(),
+            ("env@example.com".to_string(), "env-token".to_string())
+        );
+
+        // The email of another account is not paired with the saved token.
+        let lookup = Lookup {
+            vars: HashMap::from([(EMAIL_VAR.to_string(), "env@example.com".to_string())]),
+            ..lookup
+        };
+        assert_eq!(
+            lookup.basic().unwrap(),
+            ("stored@example.com".to_string(), "stored-token".to_string())
+        );
+
+        // Without an email next to the token, the configured one is used.
+        let lookup = Lookup {
+            stored: HashMap::from([(API_TOKEN_VAR.to_string(), "stored-token".to_string())]),
+            ..lookup
+        };
+        assert_eq!(
+            lookup.basic().unwrap(),
+            ("config@example.com".to_string(), "stored-token".to_string())
+        );
+    }
+
+    #[test]
+    fn test_saved_credentials_come_after_the_instance_variables() {
+        let instance = instance("tedlt-test-stored", AuthMethod::Basic, None);
+        let lookup = Lookup {
+            instance: &instance,
+            vars: HashMap::from([(
+                "JIRA_API_TOKEN_TEDLT_TEST_STORED".to_string(),
+                "env-token".to_string(),
+            )]),
+            env_file: HashMap::from([(
+                OAUTH_CLIENT_SECRET_VAR.to_string(),
+                "file-secret".to_string(),
+            )]),
+            stored: HashMap::from([
+                (API_TOKEN_VAR.to_string(), "stored-token".to_string()),
+                (
+                    OAUTH_CLIENT_SECRET_VAR.to_string(),
+                    "stored-secret".to_string(),
+                ),
+                (
+                    OAUTH_REFRESH_TOKEN_VAR.to_string(),
+                    "stored-refresh".to_string(),
+                ),
+            ]),
+        };
+
+        assert_eq!(
+            lookup.secret(API_TOKEN_VAR).unwrap().as_deref(),
+            Some("env-token")
+        );
+        assert_eq!(
+            lookup.secret(OAUTH_CLIENT_SECRET_VAR).unwrap().as_deref(),
+            Some("file-secret")
+        );
+        assert_eq!(
+            lookup.secret(OAUTH_REFRESH_TOKEN_VAR).unwrap().as_deref(),
+            Some("stored-refresh")
+        );
+    }
 
-        let email = std::env::var("JIRA_EMAIL")
-            .map_err(|_| EnvError::MissingVar("JIRA_EMAIL".to_string()))?;
+    #[test]
+    fn test_named_instance_ignores_unsuffixed_variables() {
+        let instance = instance("tedlt-test-unsuffixed", AuthMethod::Basic, None);
+        let lookup = Lookup {
+            instance: &instance,
+            vars: HashMap::from([(API_TOKEN_VAR.to_string(), "env-token".to_string())]),
+            env_file: HashMap::new(),
+            stored: HashMap::new(),
+        };
 
-        Ok(Self { api_token, email })
+        match lookup.required_secret(API_TOKEN_VAR) {
+            Err(EnvError::MissingInstanceVar { instance_var, .. }) => {
+                assert_eq!(instance_var, "JIRA_API_TOKEN_TEDLT_TEST_UNSUFFIXED");
+            }
+            _ => panic!("expected the instance variable to be missing"),
+        }
     }
 }
@@ -0,0 +1,1019 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use serde_json::{Map, Value, json};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::config::{NAME_PREFIX, Origins, rename_traced_field};
+use crate::jira::{
+    FieldMeta, FieldType, IssueTypeInfo, JiraClient, JiraError, JiraProject, RestApi,
+};
+
+/// The fields whose values can reference an item by name, in resolution
+/// order: priorities depend on the issue type.
+const NAMED_FIELDS: [&str; 5] = [
+    "issuetype",
+    "priority",
+    "components",
+    "fixVersions",
+    "versions",
+];
+
+/// The most suggestions listed for an unknown name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The most names listed when none is close to an unknown name.
+const MAX_LISTED: usize = 10;
+
+/// The fields every ticket sets, whatever its profiles.
+const ALWAYS_SET: [&str; 2] = ["project", "summary"];
+
+#[derive(Error, Debug)]
+pub enum FieldError {
+    #[error("Unknown {field} `{name}`{hint}")]
+    UnknownName {
+        field: String,
+        name: String,
+        hint: String,
+    },
+
+    #[error("Cannot look up {field} names: {reason}")]
+    NoChoices { field: String, reason: String },
+
+    #[error(transparent)]
+    Jira(#[from] JiraError),
+}
+
+/// A project key, and the issue type ID if known.
+type IssueTypeKey = (String, Option<String>);
+
+/// An item a name can refer to.
+#[derive(Debug, Clone)]
+struct Choice {
+    id: String,
+    name: String,
+}
+
+/// Prepares the fields of tickets against Jira: rewrites the names of issue
+/// types, priorities, components and versions to their IDs, and checks the
+/// fields against the create screen of the issue type. Issue types, components
+/// and versions are looked up in the project, priorities and the create screen
+/// in the create metadata of the issue type, and field display names in the
+/// fields of the instance, and users in the user search. Lookups are cached,
+/// so a batch of tickets fetches each list once.
+pub struct FieldResolver<'a> {
+    client: &'a JiraClient,
+    projects: Mutex<HashMap<String, Arc<JiraProject>>>,
+    create_meta: Mutex<HashMap<IssueTypeKey, Arc<Vec<IssueTypeInfo>>>>,
+    fields: Mutex<Option<Arc<Vec<Choice>>>>,
+    users: Mutex<HashMap<String, Value>>,
+}
+
+impl<'a> FieldResolver<'a> {
+    pub fn new(client: &'a JiraClient) -> Self {
+        Self {
+            client,
+            projects: Mutex::new(HashMap::new()),
+            create_meta: Mutex::new(HashMap::new()),
+            fields: Mutex::new(None),
+            users: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Renames the fields keyed by an alias of a display name
+    /// (`"storyPoints": "@name:Story Points"`) to the ID of that field, along
+    /// with their `origins`.
+    pub async fn expand_aliases(
+        &self,
+        aliases: &BTreeMap<String, String>,
+        fields: &mut Value,
+        mut origins: Option<&mut Origins>,
+    ) -> Result<(), FieldError> {
+        let Some(fields) = fields.as_object_mut() else {
+            return Ok(());
+        };
+
+        for (alias, target) in aliases {
+            let Some(name) = target.strip_prefix(NAME_PREFIX) else {
+                continue;
+            };
+            if fields.contains_key(alias) {
+                let id = self.field_id(name).await?;
+                rename_traced_field(fields, origins.as_deref_mut(), alias, &id);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The ID of the field with this display name, ignoring case.
+    pub async fn field_id(&self, name: &str) -> Result<String, FieldError> {
+        let fields = self.all_fields().await?;
+
+        let mut matches = fields
+            .iter()
+            .filter(|field| field.name.eq_ignore_ascii_case(name.trim()));
+        let field = matches
+            .next()
+            .ok_or_else(|| unknown("field", name, &fields))?;
+        if matches.next().is_some() {
+            warn!("Several fields are named `{}`, using {}", name, field.id);
+        }
+
+        Ok(field.id.clone())
+    }
+
+    /// Replaces each `{"name": ...}` or `"@name:..."` value of the named
+    /// fields with `{"id": ...}`.
+    pub async fn resolve(&self, project_key: &str, fields: &mut Value) -> Result<(), FieldError> {
+        let Some(fields) = fields.as_object_mut() else {
+            return Ok(());
+        };
+
+        for field in NAMED_FIELDS {
+            let issue_type_id = issue_type_id(fields);
+            let Some(value) = fields.get_mut(field) else {
+                continue;
+            };
+
+            let values = match value {
+                Value::Array(items) => items.iter_mut().collect(),
+                value => vec![value],
+            };
+            for value in values {
+                let Some(name) = referenced_name(value) else {
+                    continue;
+                };
+                let choices = self
+                    .choices(field, project_key, issue_type_id.as_deref())
+                    .await?;
+                let choice = find(&choices, name).ok_or_else(|| unknown(field, name, &choices))?;
+                *value = json!({ "id": choice.id });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Converts the values to the shape their field takes on the create screen
+    /// of the issue type, such as `"5"` to `5` for a number field, so that
+    /// templated values reach Jira with the right type. Users that are not
+    /// given by account ID are looked up like the assignee. Fields that are not
+    /// on the screen are kept as they are.
+    pub async fn coerce(&self, project_key: &str, fields: &mut Value) -> Result<(), FieldError> {
+        let Some(fields) = fields.as_object_mut() else {
+            return Ok(());
+        };
+        let Some(issue_type_id) = issue_type_id(fields) else {
+            return Ok(());
+        };
+        let issue_types = self.create_meta(project_key, Some(&issue_type_id)).await?;
+        let Some(issue_type) = issue_types.iter().find(|t| t.id == issue_type_id) else {
+            return Ok(());
+        };
+
+        let api = self.client.api().await;
+        for (key, value) in fields.iter_mut() {
+            let Some(meta) = issue_type.fields.get(key) else {
+                continue;
+            };
+            *value = coerce(&meta.field_type, value.take(), api);
+
+            let schema = &meta.field_type;
+            if schema.type_name == "user" || schema.items.as_deref() == Some("user") {
+                let users = match value {
+                    Value::Array(items) => items.iter_mut().collect(),
+                    value => vec![value],
+                };
+                for user in users {
+                    if let Value::String(query) = user {
+                        *user = self.user(query).await?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks the fields against the create screen of their issue type: that
+    /// the required fields are set, that each field is on the screen, and that
+    /// each value has the type and one of the allowed values of its field.
+    /// Returns the problems by field ID, empty if there are none.
+    pub async fn check(
+        &self,
+        project_key: &str,
+        fields: &Value,
+    ) -> Result<BTreeMap<String, String>, FieldError> {
+        let mut problems = BTreeMap::new();
+        let empty = Map::new();
+        let fields = fields.as_object().unwrap_or(&empty);
+
+        let Some(issue_type_id) = issue_type_id(fields) else {
+            problems.insert("issuetype".to_string(), "is required".to_string());
+            return Ok(problems);
+        };
+        let issue_types = self.create_meta(project_key, Some(&issue_type_id)).await?;
+        let Some(issue_type) = issue_types.iter().find(|t| t.id == issue_type_id) else {
+            problems.insert(
+                "issuetype".to_string(),
+                format!(
+                    "`{}` is not an issue type of {}",
+                    issue_type_id, project_key
+                ),
+            );
+            return Ok(problems);
+        };
+
+        for (key, meta) in &issue_type.fields {
+            if meta.required
+                && !meta.has_default_value
+                && !ALWAYS_SET.contains(&key.as_str())
+                && !fields.contains_key(key)
+            {
+                problems.insert(key.clone(), format!("is required for {}", issue_type.name));
+            }
+        }
+
+        for (key, value) in fields {
+            let problem = match issue_type.fields.get(key) {
+                None => Some(format!(
+                    "is not on the create screen of {}",
+                    issue_type.name
+                )),
+                Some(meta) => check_value(meta, value),
+            };
+            if let Some(problem) = problem {
+                problems.insert(key.clone(), problem);
+            }
+        }
+
+        Ok(problems)
+    }
+
+    /// The reference to the first user matching `query`.
+    async fn user(&self, query: &str) -> Result<Value, FieldError> {
+        let mut users = self.users.lock().await;
+        if let Some(user) = users.get(query) {
+            return Ok(user.clone());
+        }
+
+        let user = self.client.find_user(query).await?.reference();
+        users.insert(query.to_string(), user.clone());
+        Ok(user)
+    }
+
+    async fn choices(
+        &self,
+        field: &str,
+        project_key: &str,
+        issue_type_id: Option<&str>,
+    ) -> Result<Vec<Choice>, FieldError> {
+        if field == "priority" {
+            return self.priorities(project_key, issue_type_id).await;
+        }
+
+        let project = self.project(project_key).await?;
+        let choices = match field {
+            "issuetype" => project
+                .issue_types
+                .iter()
+                .map(|t| choice(&t.id, &t.name))
+                .collect(),
+            "components" => project
+                .components
+                .iter()
+                .map(|c| choice(&c.id, &c.name))
+                .collect(),
+            _ => project
+                .versions
+                .iter()
+                .map(|v| choice(&v.id, &v.name))
+                .collect(),
+        };
+        Ok(choices)
+    }
+
+    async fn project(&self, project_key: &str) -> Result<Arc<JiraProject>, FieldError> {
+        // Held across the request, so that concurrent tickets fetch it once.
+        let mut projects = self.projects.lock().await;
+        if let Some(project) = projects.get(project_key) {
+            return Ok(Arc::clone(project));
+        }
+
+        let project = Arc::new(
+            self.client
+                .get_project(Some(project_key.to_string()))
+                .await?,
+        );
+        projects.insert(project_key.to_string(), Arc::clone(&project));
+        Ok(project)
+    }
+
+    async fn all_fields(&self) -> Result<Arc<Vec<Choice>>, FieldError> {
+        let mut fields = self.fields.lock().await;
+        if let Some(fields) = fields.as_ref() {
+            return Ok(Arc::clone(fields));
+        }
+
+        let all_fields = self.client.get_all_fields().await?;
+        let choices = Arc::new(
+            all_fields
+                .iter()
+                .map(|field| choice(&field.id, &field.name))
+                .collect(),
+        );
+        *fields = Some(Arc::clone(&choices));
+        Ok(choices)
+    }
+
+    async fn priorities(
+        &self,
+        project_key: &str,
+        issue_type_id: Option<&str>,
+    ) -> Result<Vec<Choice>, FieldError> {
+        let issue_types = self.create_meta(project_key, issue_type_id).await?;
+        let field = issue_types
+            .iter()
+            .find_map(|issue_type| issue_type.fields.get("priority"))
+            .ok_or_else(|| FieldError::NoChoices {
+                field: "priority".to_string(),
+                reason: format!("the field is not on the create screen of {}", project_key),
+            })?;
+
+        Ok(field
+            .allowed_values
+            .iter()
+            .filter_map(|value| Some(choice(value["id"].as_str()?, value["name"].as_str()?)))
+            .collect())
+    }
+
+    async fn create_meta(
+        &self,
+        project_key: &str,
+        issue_type_id: Option<&str>,
+    ) -> Result<Arc<Vec<IssueTypeInfo>>, FieldError> {
+        let key = (project_key.to_string(), issue_type_id.map(str::to_string));
+        let mut create_meta = self.create_meta.lock().await;
+        if let Some(issue_types) = create_meta.get(&key) {
+            return Ok(Arc::clone(issue_types));
+        }
+
+        let issue_types = Arc::new(
+            self.client
+                .get_fields(Some(key.0.clone()), key.1.clone())
+                .await?,
+        );
+        create_meta.insert(key, Arc::clone(&issue_types));
+        Ok(issue_types)
+    }
+}
+
+/// The problem with the value of a field, if any.
+fn check_value(meta: &FieldMeta, value: &Value) -> Option<String> {
+    let schema = &meta.field_type;
+    if !has_type(&schema.type_name, value) {
+        return Some(format!(
+            "expects {}, got {}",
+            expected(&schema.type_name),
+            value
+        ));
+    }
+
+    let items = match value {
+        Value::Array(items) => items.iter().collect(),
+        value => vec![value],
+    };
+    for item in items {
+        if let Some(items_type) = &schema.items
+            && schema.type_name == "array"
+            && !has_type(items_type, item)
+        {
+            return Some(format!(
+                "expects a list of {}, got {}",
+                expected(items_type),
+                item
+            ));
+        }
+        if let Some(problem) = disallowed(&meta.allowed_values, item) {
+            return Some(problem);
+        }
+    }
+
+    None
+}
+
+/// Converts a value to the shape of its schema type, when there is an obvious
+/// way: `"a, b"` to `["a", "b"]` for a list, `"5"` to `5` for a number, `"Major"`
+/// to `{"value": "Major"}` for an option. Other values are kept as they are.
+fn coerce(schema: &FieldType, value: Value, api: RestApi) -> Value {
+    if schema.type_name != "array" {
+        return coerce_item(&schema.type_name, value, api);
+    }
+
+    let item_type = schema.items.as_deref().unwrap_or("any");
+    let items = match value {
+        Value::Array(items) => items,
+        Value::String(s) => s
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(Value::from)
+            .collect(),
+        Value::Null => return Value::Null,
+        value => vec![value],
+    };
+    items
+        .into_iter()
+        .map(|item| coerce_item(item_type, item, api))
+        .collect()
+}
+
+fn coerce_item(type_name: &str, value: Value, api: RestApi) -> Value {
+    match (type_name, value) {
+        ("date", Value::String(s)) => match s.split_once('T') {
+            Some((date, _)) => Value::from(date),
+            None => Value::String(s),
+        },
+        ("datetime", Value::String(s)) if !s.contains('T') && s.len() == "YYYY-MM-DD".len() => {
+            Value::from(format!("{}T00:00:00.000+0000", s))
+        }
+        ("number", Value::String(s)) => {
+            let number = s.trim();
+            if let Ok(integer) = number.parse::<i64>() {
+                Value::from(integer)
+            } else if let Some(float) = number
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+            {
+                Value::Number(float)
+            } else {
+                Value::String(s)
+            }
+        }
+        ("string", Value::Number(n)) => Value::from(n.to_string()),
+        ("string", Value::Bool(b)) => Value::from(b.to_string()),
+        ("option", Value::String(s)) => json!({ "value": s }),
+        // Jira Cloud knows users by account ID, Data Center by username. Other
+        // strings are left for the user search.
+        ("user", Value::String(s)) => match api {
+            RestApi::V2 => json!({ "name": s }),
+            RestApi::V3 if is_account_id(&s) => json!({ "accountId": s }),
+            RestApi::V3 => Value::String(s),
+        },
+        (_, value) => value,
+    }
+}
+
+/// Whether `s` has the shape of a Jira Cloud account ID: 24 hexadecimal digits
+/// (`5b10ac8d82e05b22cc7d4ef5`), or a number and a UUID separated by a colon
+/// (`557058:f58131cb-b67d-43c7-b30d-6b58d40bd077`).
+fn is_account_id(s: &str) -> bool {
+    match s.split_once(':') {
+        Some((prefix, uuid)) => {
+            !prefix.is_empty()
+                && prefix.bytes().all(|b| b.is_ascii_digit())
+                && uuid.len() == 36
+                && uuid.bytes().enumerate().all(|(i, b)| match i {
+                    8 | 13 | 18 | 23 => b == b'-',
+                    _ => b.is_ascii_hexdigit(),
+                })
+        }
+        None => s.len() == 24 && s.bytes().all(|b| b.is_ascii_hexdigit()),
+    }
+}
+
+/// Whether a value has the shape Jira expects for a field of this schema type.
+/// Types whose shape is not known accept any value.
+fn has_type(type_name: &str, value: &Value) -> bool {
+    match type_name {
+        // Rich text fields also take an Atlassian Document.
+        "string" => value.is_string() || value["type"] == "doc",
+        "date" | "datetime" => value.is_string(),
+        "number" => value.is_number(),
+        "array" => value.is_array(),
+        "option" | "option-with-child" | "priority" | "issuetype" | "user" | "group"
+        | "component" | "version" | "project" | "resolution" | "securitylevel" => value.is_object(),
+        _ => true,
+    }
+}
+
+/// How the values of a schema type are described in messages.
+fn expected(type_name: &str) -> &str {
+    match type_name {
+        "string" => "text",
+        "date" => "a date (YYYY-MM-DD)",
+        "datetime" => "a date and time (ISO 8601)",
+        "number" => "a number",
+        "array" => "a list",
+        _ => "an object such as {\"id\": ...}",
+    }
+}
+
+/// The problem with a value that references none of the allowed values of its
+/// field, by ID, value or name.
+fn disallowed(allowed: &[Value], value: &Value) -> Option<String> {
+    if allowed.is_empty() {
+        return None;
+    }
+    let (key, wanted) = ["id", "value", "name"]
+        .into_iter()
+        .find_map(|key| Some((key, value.get(key)?.as_str()?)))?;
+    if allowed.iter().any(|choice| choice[key] == wanted) {
+        return None;
+    }
+
+    let names: Vec<&str> = allowed
+        .iter()
+        .filter_map(|choice| choice[key].as_str())
+        .collect();
+    Some(format!(
+        "`{}` is not an allowed value{}",
+        wanted,
+        hint(wanted, &names)
+    ))
+}
+
+/// The name a value refers to: `{"name": "Bug"}` or `"@name:Bug"`.
+fn referenced_name(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(s) => s.strip_prefix(NAME_PREFIX),
+        Value::Object(map) if map.len() == 1 => map.get("name")?.as_str(),
+        _ => None,
+    }
+}
+
+/// How the items of a field are called in messages.
+fn label(field: &str) -> &str {
+    match field {
+        "issuetype" => "issue type",
+        "components" => "component",
+        "fixVersions" | "versions" => "version",
+        field => field,
+    }
+}
+
+fn issue_type_id(fields: &Map<String, Value>) -> Option<String> {
+    fields.get("issuetype")?["id"].as_str().map(str::to_string)
+}
+
+fn choice(id: &str, name: &str) -> Choice {
+    Choice {
+        id: id.to_string(),
+        name: name.to_string(),
+    }
+}
+
+/// Finds a choice by name, ignoring case.
+fn find<'c>(choices: &'c [Choice], name: &str) -> Option<&'c Choice> {
+    choices
+        .iter()
+        .find(|choice| choice.name.eq_ignore_ascii_case(name.trim()))
+}
+
+fn unknown(field: &str, name: &str, choices: &[Choice]) -> FieldError {
+    let names: Vec<&str> = choices.iter().map(|c| c.name.as_str()).collect();
+    FieldError::UnknownName {
+        field: label(field).to_string(),
+        name: name.to_string(),
+        hint: hint(name, &names),
+    }
+}
+
+/// What follows an unknown name in messages: the names close to it, or the
+/// names it could have been.
+fn hint(name: &str, names: &[&str]) -> String {
+    let suggestions = suggestions(name, names);
+
+    let quoted = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| format!("`{}`", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if !suggestions.is_empty() {
+        format!(", did you mean {}?", quoted(&suggestions))
+    } else if names.is_empty() {
+        " (there are none in the project)".to_string()
+    } else if names.len() > MAX_LISTED {
+        format!(", expected one of {}, ...", quoted(&names[..MAX_LISTED]))
+    } else {
+        format!(", expected one of {}", quoted(names))
+    }
+}
+
+/// The names closest to `name`: a few typos away, or containing it.
+fn suggestions<'n>(name: &str, names: &[&'n str]) -> Vec<&'n str> {
+    let name = name.trim().to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(2);
+
+    let mut close: Vec<(usize, &str)> = names
+        .iter()
+        .filter_map(|candidate| {
+            let lowercase = candidate.to_lowercase();
+            let distance = edit_distance(&name, &lowercase);
+            (distance <= max_distance || lowercase.contains(&name))
+                .then_some((distance, *candidate))
+        })
+        .collect();
+    close.sort();
+    close
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// The Levenshtein distance between two strings, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auth::BearerAuth,
+        config::{ApiVersion, Origin},
+        testing::StandIn,
+    };
+
+    const PROJECT: &str = r#"{
+        "id": "10000", "key": "PROJ", "name": "Project",
+        "issueTypes": [
+            { "id": "10001", "name": "Task", "description": "", "subtask": false },
+            { "id": "10004", "name": "Bug", "description": "", "subtask": false }
+        ],
+        "components": [
+            { "id": "11001", "name": "Frontend" },
+            { "id": "11002", "name": "Backend" }
+        ],
+        "versions": [{ "id": "12001", "name": "v1.0", "released": false }]
+    }"#;
+
+    /// The issue types of `PROJ`, from `issue/createmeta/PROJ/issuetypes`.
+    const ISSUE_TYPES: &str = r#"{ "startAt": 0, "maxResults": 50, "total": 2, "issueTypes": [
+        { "id": "10001", "name": "Story", "description": "" },
+        { "id": "10004", "name": "Bug", "description": "" }
+    ] }"#;
+
+    fn client(url: String) -> JiraClient {
+        let auth = BearerAuth {
+            token: "pat".to_string(),
+        };
+        JiraClient::new(url, "PROJ".to_string(), Box::new(auth), ApiVersion::V3)
+    }
+
+    #[test]
+    fn test_referenced_name() {
+        assert_eq!(referenced_name(&json!({ "name": "Bug" })), Some("Bug"));
+        assert_eq!(referenced_name(&json!("@name:Bug")), Some("Bug"));
+        assert_eq!(referenced_name(&json!({ "id": "10004" })), None);
+        assert_eq!(referenced_name(&json!({ "id": "1", "name": "Bug" })), None);
+        assert_eq!(referenced_name(&json!("Bug")), None);
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let names = ["Highest", "High", "Medium", "Low", "Lowest"];
+        assert_eq!(suggestions("Hgih", &names), vec!["High"]);
+        assert_eq!(suggestions("low", &names), vec!["Low", "Lowest"]);
+        assert!(suggestions("Urgent", &names).is_empty());
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[tokio::test]
+    async fn test_resolves_names_from_the_project() {
+        let stand_in = StandIn::start(vec![(200, PROJECT)]);
+        let client = client(stand_in.url.clone());
+        let resolver = FieldResolver::new(&client);
+
+        let mut fields = json!({
+            "issuetype": { "name": "bug" },
+            "components": ["@name:Frontend", { "id": "11002" }],
+            "fixVersions": [{ "name": "v1.0" }],
+            "labels": ["@name:kept"],
+        });
+        resolver.resolve("PROJ", &mut fields).await.unwrap();
+
+        assert_eq!(
+            fields,
+            json!({
+                "issuetype": { "id": "10004" },
+                "components": [{ "id": "11001" }, { "id": "11002" }],
+                "fixVersions": [{ "id": "12001" }],
+                "labels": ["@name:kept"],
+            })
+        );
+        assert_eq!(stand_in.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ids_need_no_lookup() {
+        let stand_in = StandIn::start(vec![]);
+        let client = client(stand_in.url.clone());
+
+        let mut fields = json!({ "issuetype": { "id": "10004" }, "summary": "Bug" });
+        FieldResolver::new(&client)
+            .resolve("PROJ", &mut fields)
+            .await
+            .unwrap();
+
+        assert!(stand_in.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_priority_names_come_from_createmeta() {
+        let fields = r#"{ "startAt": 0, "maxResults": 50, "total": 1, "fields": [{
+            "required": false, "name": "Priority", "fieldId": "priority",
+            "schema": { "type": "priority" },
+            "allowedValues": [{ "id": "2", "name": "High" }, { "id": "3", "name": "Medium" }]
+        }] }"#;
+        let stand_in = StandIn::start(vec![(200, ISSUE_TYPES), (200, fields)]);
+        let client = client(stand_in.url.clone());
+
+        let mut fields = json!({ "issuetype": { "id": "10004" }, "priority": "@name:high" });
+        FieldResolver::new(&client)
+            .resolve("PROJ", &mut fields)
+            .await
+            .unwrap();
+
+        assert_eq!(fields["priority"], json!({ "id": "2" }));
+        assert_eq!(
+            stand_in.requests()[1].path,
+            "/rest/api/3/issue/createmeta/PROJ/issuetypes/10004"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expands_aliases_of_display_names() {
+        let stand_in = StandIn::start(vec![(
+            200,
+            r#"[
+                { "id": "summary", "name": "Summary", "custom": false },
+                { "id": "customfield_10016", "name": "Story Points", "custom": true }
+            ]"#,
+        )]);
+        let client = client(stand_in.url.clone());
+        let aliases = BTreeMap::from([
+            ("storyPoints".to_string(), "@name:story points".to_string()),
+            ("team".to_string(), "@name:Team".to_string()),
+        ]);
+        let profile = Origin::Profile {
+            name: "story".to_string(),
+            file: None,
+        };
+
+        let mut fields = json!({ "storyPoints": 5, "labels": ["a"] });
+        let mut origins = Origins::uniform(&fields, &profile);
+        FieldResolver::new(&client)
+            .expand_aliases(&aliases, &mut fields, Some(&mut origins))
+            .await
+            .unwrap();
+
+        assert_eq!(fields, json!({ "customfield_10016": 5, "labels": ["a"] }));
+        assert_eq!(origins.at(&["customfield_10016"]), Some(&profile));
+        assert_eq!(origins.at(&["storyPoints"]), None);
+        assert_eq!(stand_in.requests()[0].path, "/rest/api/3/field");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_name_suggests_close_ones() {
+        let stand_in = StandIn::start(vec![(200, PROJECT)]);
+        let client = client(stand_in.url.clone());
+
+        let mut fields = json!({ "components": [{ "name": "Frontnd" }] });
+        let error = FieldResolver::new(&client)
+            .resolve("PROJ", &mut fields)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Unknown component `Frontnd`, did you mean `Frontend`?"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_reports_each_problem() {
+        let fields = r#"{ "startAt": 0, "maxResults": 50, "total": 7, "fields": [
+            { "required": true, "name": "Summary", "fieldId": "summary",
+                "schema": { "type": "string" } },
+            { "required": true, "name": "Issue Type", "fieldId": "issuetype",
+                "schema": { "type": "issuetype" } },
+            { "required": true, "hasDefaultValue": true, "name": "Reporter",
+                "fieldId": "reporter", "schema": { "type": "user" } },
+            { "required": true, "name": "Severity",
+                "fieldId": "customfield_10020", "schema": { "type": "option" },
+                "allowedValues": [{ "id": "1", "value": "Minor" }, { "id": "2", "value": "Major" }] },
+            { "required": false, "name": "Story Points",
+                "fieldId": "customfield_10016", "schema": { "type": "number" } },
+            { "required": false, "name": "Components", "fieldId": "components",
+                "schema": { "type": "array", "items": "component" },
+                "allowedValues": [{ "id": "11001", "name": "Frontend" }] },
+            { "required": true, "name": "Environment", "fieldId": "environment",
+                "schema": { "type": "string" } }
+        ] }"#;
+        let stand_in = StandIn::start(vec![(200, ISSUE_TYPES), (200, fields)]);
+        let client = client(stand_in.url.clone());
+        let resolver = FieldResolver::new(&client);
+
+        let fields = json!({
+            "issuetype": { "id": "10004" },
+            "customfield_10020": { "value": "Majr" },
+            "customfield_10016": "5",
+            "components": [{ "id": "11001" }, { "id": "11002" }],
+            "duedate": "2024-05-20",
+        });
+        let problems = resolver.check("PROJ", &fields).await.unwrap();
+
+        assert_eq!(
+            problems,
+            BTreeMap::from([
+                (
+                    "components".to_string(),
+                    "`11002` is not an allowed value, did you mean `11001`?".to_string()
+                ),
+                (
+                    "customfield_10016".to_string(),
+                    "expects a number, got \"5\"".to_string()
+                ),
+                (
+                    "customfield_10020".to_string(),
+                    "`Majr` is not an allowed value, did you mean `Major`?".to_string()
+                ),
+                (
+                    "duedate".to_string(),
+                    "is not on the create screen of Bug".to_string()
+                ),
+                ("environment".to_string(), "is required for Bug".to_string()),
+            ])
+        );
+
+        let fields = json!({
+            "issuetype": { "id": "10004" },
+            "customfield_10020": { "value": "Minor" },
+            "environment": "Staging",
+        });
+        assert!(resolver.check("PROJ", &fields).await.unwrap().is_empty());
+        assert_eq!(stand_in.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_check_needs_an_issue_type() {
+        let stand_in = StandIn::start(vec![]);
+        let client = client(stand_in.url.clone());
+
+        let problems = FieldResolver::new(&client)
+            .check("PROJ", &Value::Null)
+            .await
+            .unwrap();
+
+        assert_eq!(problems["issuetype"], "is required");
+        assert!(stand_in.requests().is_empty());
+    }
+
+    fn coerced(type_name: &str, items: Option<&str>, value: Value) -> Value {
+        let schema = FieldType {
+            type_name: type_name.to_string(),
+            items: items.map(str::to_string),
+            custom: None,
+        };
+        coerce(&schema, value, RestApi::V3)
+    }
+
+    #[test]
+    fn test_coerce_number() {
+        assert_eq!(coerced("number", None, json!("5")), json!(5));
+        assert_eq!(coerced("number", None, json!(" 2.5 ")), json!(2.5));
+        assert_eq!(coerced("number", None, json!("five")), json!("five"));
+        assert_eq!(coerced("number", None, json!(3)), json!(3));
+    }
+
+    #[test]
+    fn test_coerce_array() {
+        assert_eq!(
+            coerced("array", Some("string"), json!("a, b,")),
+            json!(["a", "b"])
+        );
+        assert_eq!(coerced("array", Some("string"), json!([1])), json!(["1"]));
+        assert_eq!(
+            coerced("array", Some("option"), json!("x")),
+            json!([{ "value": "x" }])
+        );
+        assert_eq!(
+            coerced("array", Some("component"), json!({ "id": "1" })),
+            json!([{ "id": "1" }])
+        );
+    }
+
+    #[test]
+    fn test_coerce_option_and_user() {
+        assert_eq!(
+            coerced("option", None, json!("Major")),
+            json!({ "value": "Major" })
+        );
+        assert_eq!(
+            coerced("option", None, json!({ "id": "2" })),
+            json!({ "id": "2" })
+        );
+        assert_eq!(
+            coerced("user", None, json!("5b10ac8d82e05b22cc7d4ef5")),
+            json!({ "accountId": "5b10ac8d82e05b22cc7d4ef5" })
+        );
+        assert_eq!(
+            coerced("user", None, json!("jane@example.com")),
+            json!("jane@example.com")
+        );
+        let schema = FieldType {
+            type_name: "user".to_string(),
+            items: None,
+            custom: None,
+        };
+        assert_eq!(
+            coerce(&schema, json!("jdoe"), RestApi::V2),
+            json!({ "name": "jdoe" })
+        );
+    }
+
+    #[test]
+    fn test_is_account_id() {
+        assert!(is_account_id("5b10ac8d82e05b22cc7d4ef5"));
+        assert!(is_account_id("557058:f58131cb-b67d-43c7-b30d-6b58d40bd077"));
+        assert!(!is_account_id("5b10ac8d"));
+        assert!(!is_account_id("jdoe"));
+        assert!(!is_account_id("qm:f58131cb-b67d-43c7-b30d-6b58d40bd077"));
+        assert!(!is_account_id("557058:jdoe"));
+    }
+
+    #[tokio::test]
+    async fn test_coerce_looks_up_users_by_name() {
+        let fields = r#"{ "startAt": 0, "maxResults": 50, "total": 2, "fields": [
+            { "required": false, "name": "Reporter", "fieldId": "reporter",
+                "schema": { "type": "user" } },
+            { "required": false, "name": "Reviewers",
+                "fieldId": "customfield_10030", "schema": { "type": "array", "items": "user" } }
+        ] }"#;
+        let users = r#"[{ "accountId": "5b10ac8d82e05b22cc7d4ef5", "displayName": "Jane Doe" }]"#;
+        let stand_in = StandIn::start(vec![(200, ISSUE_TYPES), (200, fields), (200, users)]);
+        let client = client(stand_in.url.clone());
+
+        let mut fields = json!({
+            "issuetype": { "id": "10004" },
+            "reporter": "jane@example.com",
+            "customfield_10030": "557058:f58131cb-b67d-43c7-b30d-6b58d40bd077, jane@example.com",
+        });
+        FieldResolver::new(&client)
+            .coerce("PROJ", &mut fields)
+            .await
+            .unwrap();
+
+        let jane = json!({ "accountId": "5b10ac8d82e05b22cc7d4ef5" });
+        assert_eq!(fields["reporter"], jane);
+        assert_eq!(
+            fields["customfield_10030"],
+            json!([{ "accountId": "557058:f58131cb-b67d-43c7-b30d-6b58d40bd077" }, jane])
+        );
+        let requests = stand_in.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(
+            requests[2]
+                .path
+                .starts_with("/rest/api/3/user/search?query=jane")
+        );
+    }
+
+    #[test]
+    fn test_coerce_string_and_dates() {
+        assert_eq!(coerced("string", None, json!(42)), json!("42"));
+        assert_eq!(coerced("string", None, json!(true)), json!("true"));
+        assert_eq!(
+            coerced("date", None, json!("2024-05-20T10:00")),
+            json!("2024-05-20")
+        );
+        assert_eq!(
+            coerced("datetime", None, json!("2024-05-20")),
+            json!("2024-05-20T00:00:00.000+0000")
+        );
+        assert_eq!(
+            coerced("datetime", None, json!("2024-05-20T10:00:00.000+0200")),
+            json!("2024-05-20T10:00:00.000+0200")
+        );
+    }
+}
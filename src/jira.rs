@@ -1,54 +1,255 @@
-use std::time::Duration;
-
-use reqwest::{Client, RequestBuilder, Response};
-use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    time::Duration,
+};
+
+use futures::{Stream, TryStreamExt, stream};
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::{Value, json};
 use thiserror::Error;
-use tracing::debug;
+use tokio::sync::OnceCell;
+use tracing::{debug, warn};
+
+use crate::{
+    adf,
+    auth::{AuthError, AuthStrategy},
+    config::ApiVersion,
+    retry::RetryPolicy,
+};
 
 #[derive(Error, Debug)]
 pub enum JiraError {
     #[error("HTTP request failed: {0}")]
     Request(#[from] reqwest::Error),
 
-    #[error("Failed to create ticket: {0}")]
-    CreateTicket(String),
+    #[error("Authentication failed: {0}")]
+    Auth(#[from] AuthError),
 
-    #[error("Failed to get ticket: {0}")]
-    GetTicket(String),
+    #[error("Failed to {action}: {error}")]
+    Api {
+        action: &'static str,
+        error: JiraApiError,
+    },
 
-    #[error("Failed to get epics: {0}")]
-    GetEpics(String),
+    #[error("Failed to {action}: {reason}")]
+    UnexpectedResponse {
+        action: &'static str,
+        reason: String,
+    },
 
-    #[error("Failed to get boards: {0}")]
-    GetBoard(String),
+    #[error("No user matching '{0}'")]
+    UserNotFound(String),
 
-    #[error("Failed to get fields: {0}")]
-    GetFields(String),
+    #[error("Invalid Jira URL: {0}")]
+    InvalidUrl(String),
+}
 
-    #[error("Failed to get project: {0}")]
-    GetProject(String),
+/// An error answered by Jira, from its
+/// `{"errorMessages": [...], "errors": {"field": "message"}}` body.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraApiError {
+    /// The HTTP status, if the error came with one.
+    #[serde(skip)]
+    pub status: Option<u16>,
+    /// The messages that are not about a field.
+    #[serde(default)]
+    pub error_messages: Vec<String>,
+    /// The messages about a field, by field ID.
+    #[serde(default)]
+    pub errors: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Serialize)]
-struct IssueFields {
-    project: Project,
-    summary: String,
+/// Error bodies that are not Jira errors, such as proxy pages, are cut to this
+/// many characters.
+const MAX_RAW_BODY: usize = 200;
+
+impl JiraApiError {
+    /// Parses an error response. A body that is not a Jira error, including
+    /// JSON without any of its messages, becomes the only message.
+    pub fn parse(status: Option<u16>, body: &str) -> Self {
+        let mut error = serde_json::from_str::<Self>(body).unwrap_or_default();
+        error.error_messages.retain(|message| !message.is_empty());
+        if error.error_messages.is_empty() && error.errors.is_empty() && !body.trim().is_empty() {
+            let body = body.trim();
+            let mut message: String = body.chars().take(MAX_RAW_BODY).collect();
+            if message.len() < body.len() {
+                message.push_str("...");
+            }
+            error.error_messages.push(message);
+        }
+        error.status = status;
+        error
+    }
+
+    /// An error with a single message and no status.
+    pub fn message(message: impl Into<String>) -> Self {
+        Self {
+            error_messages: vec![message.into()],
+            ..Default::default()
+        }
+    }
+
+    /// The error of a failed request, as reported for one ticket of a batch.
+    pub fn from_failure(error: &JiraError) -> Self {
+        match error {
+            JiraError::Api { error, .. } => error.clone(),
+            other => Self::message(other.to_string()),
+        }
+    }
+
+    /// The messages, with each field error introduced by `label(field)`.
+    pub fn describe(&self, label: impl Fn(&str) -> String) -> String {
+        let messages: Vec<String> = self
+            .error_messages
+            .iter()
+            .cloned()
+            .chain(
+                self.errors
+                    .iter()
+                    .map(|(field, message)| format!("{}: {}", label(field), message)),
+            )
+            .collect();
+
+        match (self.status, messages.is_empty()) {
+            (Some(status), true) => format!("Status {}", status),
+            (Some(status), false) => format!("Status {}: {}", status, messages.join("; ")),
+            (None, true) => "Unknown error".to_string(),
+            (None, false) => messages.join("; "),
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
-struct Project {
-    key: String,
+impl fmt::Display for JiraApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.describe(|field| field.to_string()))
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct CreateIssueResponse {
-    key: String,
+/// A version of the Jira REST API, once `auto` is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestApi {
+    V2,
+    V3,
 }
 
-#[derive(Debug)]
+impl RestApi {
+    /// The version used when it cannot be detected: `auto` is taken as 3.
+    pub fn assumed(version: ApiVersion) -> Self {
+        match version {
+            ApiVersion::V2 => Self::V2,
+            ApiVersion::V3 | ApiVersion::Auto => Self::V3,
+        }
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            Self::V2 => "rest/api/2",
+            Self::V3 => "rest/api/3",
+        }
+    }
+}
+
+impl fmt::Display for RestApi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V2 => write!(f, "2"),
+            Self::V3 => write!(f, "3"),
+        }
+    }
+}
+
+/// A created ticket, as returned by Jira.
+#[derive(Debug, Clone, Deserialize)]
 pub struct TicketInfo {
+    pub id: String,
     pub key: String,
+    /// The REST API URL of the ticket.
+    #[serde(rename = "self")]
+    pub self_url: String,
+}
+
+/// Maximum number of issues Jira accepts in one bulk create request.
+pub const BULK_CREATE_LIMIT: usize = 50;
+
+/// A ticket to create with [`JiraClient::create_tickets_bulk`].
+#[derive(Debug, Clone)]
+pub struct NewIssue {
+    pub project_key: String,
+    pub summary: String,
+    pub fields: Option<Value>,
+}
+
+/// The outcome of a bulk creation. Indexes refer to the input slice.
+#[derive(Debug, Default)]
+pub struct BulkCreateResult {
+    pub created: Vec<(usize, TicketInfo)>,
+    pub failed: Vec<BulkCreateFailure>,
+}
+
+#[derive(Debug)]
+pub struct BulkCreateFailure {
+    pub index: usize,
+    pub error: JiraApiError,
+}
+
+#[derive(Debug, Deserialize)]
+struct BulkCreateResponse {
+    #[serde(default)]
+    issues: Vec<TicketInfo>,
+    #[serde(default)]
+    errors: Vec<BulkElementError>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkElementError {
+    status: Option<u16>,
+    #[serde(default)]
+    element_errors: JiraApiError,
+    failed_element_number: usize,
+}
+
+impl BulkCreateResult {
+    /// Maps one chunk response back to the input rows. Jira lists the created
+    /// issues in input order, skipping the failed elements it reports by number.
+    fn record_chunk(&mut self, offset: usize, len: usize, response: BulkCreateResponse) {
+        let mut failed_elements = HashMap::new();
+        for error in response.errors {
+            failed_elements.insert(error.failed_element_number, error);
+        }
+
+        let mut issues = response.issues.into_iter();
+        for element in 0..len {
+            let index = offset + element;
+
+            if let Some(element_error) = failed_elements.remove(&element) {
+                let mut error = element_error.element_errors;
+                error.status = element_error.status;
+                self.failed.push(BulkCreateFailure { index, error });
+                continue;
+            }
+
+            match issues.next() {
+                Some(issue) => self.created.push((index, issue)),
+                None => self.failed.push(BulkCreateFailure {
+                    index,
+                    error: JiraApiError::message("Missing from the Jira response"),
+                }),
+            }
+        }
+    }
+
+    fn fail_chunk(&mut self, offset: usize, len: usize, error: &JiraError) {
+        for index in offset..offset + len {
+            self.failed.push(BulkCreateFailure {
+                index,
+                error: JiraApiError::from_failure(error),
+            });
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -109,6 +310,100 @@ pub struct ProjectInfo {
     pub project_key: String,
 }
 
+/// A Jira user: identified by `account_id` on Jira Cloud, and by `name` on
+/// Jira Server and Data Center.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct User {
+    #[serde(rename = "accountId")]
+    pub account_id: Option<String>,
+    pub name: Option<String>,
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+    #[serde(rename = "emailAddress")]
+    pub email_address: Option<String>,
+}
+
+impl User {
+    /// The identifier of the user, as shown to people.
+    pub fn id(&self) -> &str {
+        self.account_id
+            .as_deref()
+            .or(self.name.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// The value that sets this user in a user field, such as `assignee`.
+    pub fn reference(&self) -> Value {
+        match (&self.account_id, &self.name) {
+            (None, Some(name)) => json!({ "name": name }),
+            _ => json!({ "accountId": self.id() }),
+        }
+    }
+}
+
+/// A page of a list endpoint: offset-based like the Agile API (`startAt`,
+/// `isLast`), or token-based like the new search APIs (`nextPageToken`).
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Page<T> {
+    #[serde(
+        default = "Vec::new",
+        alias = "issues",
+        alias = "issueTypes",
+        alias = "fields"
+    )]
+    values: Vec<T>,
+    start_at: Option<u64>,
+    max_results: Option<u64>,
+    total: Option<u64>,
+    is_last: Option<bool>,
+    next_page_token: Option<String>,
+}
+
+/// Where the next page of a list starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Cursor {
+    StartAt(u64),
+    Token(String),
+}
+
+impl<T> Page<T> {
+    /// The cursor of the next page, or `None` if this page is the last one.
+    fn next(&self) -> Option<Cursor> {
+        if let Some(token) = &self.next_page_token {
+            return Some(Cursor::Token(token.clone()));
+        }
+        if self.is_last == Some(true) || self.values.is_empty() {
+            return None;
+        }
+
+        let count = self.values.len() as u64;
+        let next = self.start_at? + count;
+        match (self.is_last, self.total, self.max_results) {
+            (_, Some(total), _) if next >= total => None,
+            // Without `isLast`, a short page is the last one.
+            (None, _, Some(max_results)) if count < max_results => None,
+            _ => Some(Cursor::StartAt(next)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerInfo {
+    /// `Cloud`, `Server` or `DataCenter`; missing on old Jira Server versions.
+    deployment_type: Option<String>,
+}
+
+/// A field of the Jira instance, system or custom.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Field {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub custom: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct IssueTypeInfo {
     pub id: String,
@@ -120,11 +415,14 @@ pub struct IssueTypeInfo {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FieldMeta {
     pub required: bool,
+    /// Whether Jira fills the field when it is not set, such as the reporter.
+    #[serde(default, rename = "hasDefaultValue")]
+    pub has_default_value: bool,
     pub name: String,
     pub key: String,
     #[serde(rename = "schema")]
     pub field_type: FieldType,
-    #[serde(default)]
+    #[serde(default, rename = "allowedValues")]
     pub allowed_values: Vec<Value>,
 }
 
@@ -136,16 +434,79 @@ pub struct FieldType {
     pub custom: Option<String>,
 }
 
+/// Joins `path` to the Jira base URL, keeping its context path:
+/// `https://corp/jira` and `rest/api/2/issue` give
+/// `https://corp/jira/rest/api/2/issue`.
+pub fn endpoint(base_url: &str, path: &str) -> Result<Url, JiraError> {
+    let invalid = |e| JiraError::InvalidUrl(format!("{}: {}", base_url, e));
+
+    let mut base = Url::parse(base_url).map_err(invalid)?;
+    if !base.path().ends_with('/') {
+        let with_slash = format!("{}/", base.path());
+        base.set_path(&with_slash);
+    }
+    base.join(path).map_err(invalid)
+}
+
+/// Returns the endpoint used to create a ticket.
+pub fn create_issue_url(base_url: &str, api: RestApi) -> Result<Url, JiraError> {
+    endpoint(base_url, &format!("{}/issue", api.path()))
+}
+
+/// Returns the page of a ticket in the Jira web UI.
+pub fn browse_url(base_url: &str, key: &str) -> Result<Url, JiraError> {
+    endpoint(base_url, &format!("browse/{}", key))
+}
+
+/// Builds the `{"fields": ...}` body sent to create a ticket: the project and
+/// summary, extended with the resolved profile fields. A text `description`
+/// is sent as is on v2, and converted from Markdown to ADF on v3.
+pub fn create_issue_body(
+    api: RestApi,
+    project_key: &str,
+    title: &str,
+    additional_fields: Option<Value>,
+) -> Value {
+    let mut fields_value = json!({
+        "project": { "key": project_key },
+        "summary": title,
+    });
+
+    if let Some(additional) = additional_fields
+        && let (Some(fields_map), Some(additional_map)) =
+            (fields_value.as_object_mut(), additional.as_object())
+    {
+        fields_map.extend(additional_map.clone());
+    }
+
+    if api == RestApi::V3
+        && let Some(description) = fields_value.get_mut("description")
+        && let Value::String(markdown) = description
+    {
+        *description = adf::markdown_to_adf(markdown);
+    }
+
+    json!({ "fields": fields_value })
+}
+
 pub struct JiraClient {
     client: Client,
     base_url: String,
     project_key: String,
-    api_token: String,
-    email: String,
+    auth: Box<dyn AuthStrategy>,
+    api_version: ApiVersion,
+    /// The version in use, detected on the first request for `auto`.
+    api: OnceCell<RestApi>,
+    retry: RetryPolicy,
 }
 
 impl JiraClient {
-    pub fn new(base_url: String, project_key: String, api_token: String, email: String) -> Self {
+    pub fn new(
+        base_url: String,
+        project_key: String,
+        auth: Box<dyn AuthStrategy>,
+        api_version: ApiVersion,
+    ) -> Self {
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .build()
@@ -155,156 +516,356 @@ impl JiraClient {
             client,
             base_url,
             project_key,
-            api_token,
-            email,
+            auth,
+            api_version,
+            api: OnceCell::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The version of the REST API in use. For `auto`, it is detected from
+    /// the deployment type of the server: v3 on Cloud, v2 otherwise.
+    pub async fn api(&self) -> RestApi {
+        *self
+            .api
+            .get_or_init(|| async {
+                match self.api_version {
+                    ApiVersion::Auto => self.detect_api().await,
+                    version => RestApi::assumed(version),
+                }
+            })
+            .await
+    }
+
+    async fn detect_api(&self) -> RestApi {
+        match self.get_server_info().await {
+            Ok(ServerInfo {
+                deployment_type: Some(deployment),
+            }) if deployment == "Cloud" => RestApi::V3,
+            Ok(info) => {
+                debug!(
+                    "Jira {} detected, using the REST API v2",
+                    info.deployment_type.as_deref().unwrap_or("Server")
+                );
+                RestApi::V2
+            }
+            Err(e) => {
+                warn!(
+                    "Could not detect the Jira REST API version, using v3: {}",
+                    e
+                );
+                RestApi::V3
+            }
         }
     }
 
-    fn authenticate(&self, builder: RequestBuilder) -> RequestBuilder {
-        builder
-            .basic_auth(&self.email, Some(&self.api_token))
+    async fn get_server_info(&self) -> Result<ServerInfo, JiraError> {
+        let url = endpoint(&self.base_url, "rest/api/2/serverInfo")?;
+
+        let request = self.client.get(url);
+        let response = self.send(request).await?;
+
+        Self::handle_response(response, "get server info").await
+    }
+
+    /// The URL of `path` in the REST API in use, e.g. `issue/KAN-1`.
+    async fn api_url(&self, path: &str) -> Result<Url, JiraError> {
+        endpoint(
+            &self.base_url,
+            &format!("{}/{}", self.api().await.path(), path),
+        )
+    }
+
+    /// The URL of `path` in the Jira Software REST API, e.g. `board`.
+    fn agile_url(&self, path: &str) -> Result<Url, JiraError> {
+        endpoint(&self.base_url, &format!("rest/agile/1.0/{}", path))
+    }
+
+    async fn authenticate(&self, builder: RequestBuilder) -> Result<RequestBuilder, JiraError> {
+        let builder = self.auth.authorize(builder).await?;
+        Ok(builder
             .header("Accept", "application/json")
-            .header("Content-Type", "application/json")
+            .header("Content-Type", "application/json"))
+    }
+
+    /// Authenticates and sends a request, retrying it as the retry policy
+    /// allows. The last response is returned, successful or not.
+    async fn send(&self, builder: RequestBuilder) -> Result<Response, JiraError> {
+        let request = self.authenticate(builder).await?.build()?;
+
+        let mut attempt = 0;
+        loop {
+            // Requests with a streamed body cannot be sent twice.
+            let Some(copy) = request.try_clone() else {
+                return Ok(self.client.execute(request).await?);
+            };
+
+            let outcome = self.client.execute(copy).await;
+            let Some(delay) = self.retry.delay(request.method(), &outcome, attempt) else {
+                return Ok(outcome?);
+            };
+
+            let reason = match &outcome {
+                Ok(response) => response.status().to_string(),
+                Err(e) => e.to_string(),
+            };
+            warn!(
+                "{} {} failed ({}), retrying in {:.1}s",
+                request.method(),
+                request.url().path(),
+                reason,
+                delay.as_secs_f64()
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
     }
 
-    async fn handle_response<T, F>(response: Response, error_mapper: F) -> Result<T, JiraError>
+    /// Parses a successful response, or the Jira error of a failed one.
+    /// `action` describes the request in errors, e.g. `get project`.
+    async fn handle_response<T>(response: Response, action: &'static str) -> Result<T, JiraError>
     where
         T: for<'de> Deserialize<'de>,
-        F: FnOnce(String) -> JiraError,
     {
         let status = response.status();
 
         if !status.is_success() {
-            let error_text = response
+            let body = response
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unable to read error response".to_string());
-            return Err(error_mapper(format!(
-                "Status: {}, Body: {}",
-                status, error_text
-            )));
+            return Err(JiraError::Api {
+                action,
+                error: JiraApiError::parse(Some(status.as_u16()), &body),
+            });
         }
 
         response
             .json()
             .await
-            .map_err(|e| error_mapper(format!("Failed to parse response: {}", e)))
+            .map_err(|e| JiraError::UnexpectedResponse {
+                action,
+                reason: format!("Failed to parse response: {}", e),
+            })
     }
 
     fn resolve_project_key(&self, project_key: Option<String>) -> String {
         project_key.unwrap_or_else(|| self.project_key.clone())
     }
 
-    pub async fn create_ticket(
-        &self,
-        title: &str,
-        additional_fields: Option<Value>,
-    ) -> Result<TicketInfo, JiraError> {
-        let url = format!("{}/rest/api/3/issue", self.base_url);
+    /// Creates a ticket from the body built by [`create_issue_body`].
+    pub async fn create_ticket(&self, request_body: &Value) -> Result<TicketInfo, JiraError> {
+        let url = create_issue_url(&self.base_url, self.api().await)?;
 
         debug!(
-            r#"Creating Jira issue:
-            - Jira instance: {}
-            - Project key: {}
-            "#,
-            self.base_url, self.project_key
+            "Creating a Jira issue on {}: {}",
+            self.base_url,
+            serde_json::to_string_pretty(request_body).unwrap_or_default()
         );
 
-        let base_fields = IssueFields {
-            project: Project {
-                key: self.project_key.clone(),
-            },
-            summary: title.to_string(),
-        };
+        let response = self.send(self.client.post(url).json(request_body)).await?;
 
-        let mut fields_value = serde_json::to_value(base_fields).map_err(|e| {
-            JiraError::CreateTicket(format!("Failed to serialize base fields: {}", e))
-        })?;
+        Self::handle_response(response, "create ticket").await
+    }
 
-        if let Some(additional) = additional_fields {
-            if let (Some(fields_map), Some(additional_map)) =
-                (fields_value.as_object_mut(), additional.as_object())
-            {
-                fields_map.extend(additional_map.clone());
+    /// Creates tickets with the bulk endpoint, in chunks of [`BULK_CREATE_LIMIT`].
+    /// A failed chunk marks all of its tickets as failed instead of aborting.
+    pub async fn create_tickets_bulk(
+        &self,
+        issues: &[NewIssue],
+    ) -> Result<BulkCreateResult, JiraError> {
+        let api = self.api().await;
+        let url = self.api_url("issue/bulk").await?;
+        let mut result = BulkCreateResult::default();
+
+        for (chunk_index, chunk) in issues.chunks(BULK_CREATE_LIMIT).enumerate() {
+            let offset = chunk_index * BULK_CREATE_LIMIT;
+
+            let issue_updates: Vec<Value> = chunk
+                .iter()
+                .map(|issue| {
+                    create_issue_body(
+                        api,
+                        &issue.project_key,
+                        &issue.summary,
+                        issue.fields.clone(),
+                    )
+                })
+                .collect();
+            let request_body = json!({ "issueUpdates": issue_updates });
+
+            debug!(
+                "Creating {} issues in bulk (from #{})",
+                chunk.len(),
+                offset + 1
+            );
+
+            match self.send_bulk_chunk(url.clone(), &request_body).await {
+                Ok(response) => result.record_chunk(offset, chunk.len(), response),
+                Err(e) => result.fail_chunk(offset, chunk.len(), &e),
             }
         }
 
-        let request_body = json!({ "fields": fields_value });
-
-        debug!(
-            "Jira request body: {}",
-            serde_json::to_string_pretty(&request_body).unwrap_or_default()
-        );
+        Ok(result)
+    }
 
-        let request = self
-            .authenticate(self.client.post(&url))
-            .json(&request_body);
-        let response = request.send().await?;
+    async fn send_bulk_chunk(
+        &self,
+        url: Url,
+        request_body: &Value,
+    ) -> Result<BulkCreateResponse, JiraError> {
+        let response = self.send(self.client.post(url).json(request_body)).await?;
 
-        let create_response: CreateIssueResponse =
-            Self::handle_response(response, JiraError::CreateTicket).await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        // Jira answers 201 when some issues were created and 400 when none were,
+        // both with the per-element errors.
+        if (status.is_success() || status == StatusCode::BAD_REQUEST)
+            && let Ok(parsed) = serde_json::from_str::<BulkCreateResponse>(&body)
+            && (status.is_success() || !parsed.errors.is_empty())
+        {
+            return Ok(parsed);
+        }
 
-        Ok(TicketInfo {
-            key: create_response.key,
+        Err(JiraError::Api {
+            action: "create tickets",
+            error: JiraApiError::parse(Some(status.as_u16()), &body),
         })
     }
 
     pub async fn get_project(&self, project_key: Option<String>) -> Result<JiraProject, JiraError> {
         let project_key = self.resolve_project_key(project_key);
-        let url = format!("{}/rest/api/3/project/{}", self.base_url, project_key);
+        let url = self.api_url(&format!("project/{}", project_key)).await?;
 
-        let request = self.client.get(&url);
-        let response = self.authenticate(request).send().await?;
+        let request = self.client.get(url);
+        let response = self.send(request).await?;
 
-        Self::handle_response(response, JiraError::GetProject).await
+        Self::handle_response(response, "get project").await
     }
 
     pub async fn get_ticket(&self, ticket_key: &str) -> Result<Value, JiraError> {
-        let url = format!("{}/rest/api/3/issue/{}", self.base_url, ticket_key);
+        let url = self.api_url(&format!("issue/{}", ticket_key)).await?;
 
-        let request = self.client.get(&url);
-        let response = self.authenticate(request).send().await?;
+        let request = self.client.get(url);
+        let response = self.send(request).await?;
 
-        Self::handle_response(response, JiraError::GetTicket).await
+        Self::handle_response(response, "get ticket").await
     }
 
-    pub async fn get_epics_by_board(&self, board_id: u64) -> Result<Vec<Epic>, JiraError> {
-        let url = format!("{}/rest/agile/1.0/board/{}/epic", self.base_url, board_id);
+    pub async fn get_myself(&self) -> Result<User, JiraError> {
+        let url = self.api_url("myself").await?;
 
-        let request = self.client.get(&url);
-        let response = self.authenticate(request).send().await?;
+        let request = self.client.get(url);
+        let response = self.send(request).await?;
 
-        #[derive(Deserialize)]
-        struct EpicResponse {
-            values: Vec<Epic>,
-        }
+        Self::handle_response(response, "get user").await
+    }
+
+    /// Returns the first user matching `query` (name, display name or email).
+    pub async fn find_user(&self, query: &str) -> Result<User, JiraError> {
+        let url = self.api_url("user/search").await?;
 
-        let epic_response: EpicResponse =
-            Self::handle_response(response, JiraError::GetEpics).await?;
-        Ok(epic_response.values)
+        // Jira Server and Data Center search users by `username` instead.
+        let parameter = match self.api().await {
+            RestApi::V2 => "username",
+            RestApi::V3 => "query",
+        };
+        let request = self.client.get(url).query(&[(parameter, query)]);
+        let response = self.send(request).await?;
+
+        let users: Vec<User> = Self::handle_response(response, "search users").await?;
+        users
+            .into_iter()
+            .next()
+            .ok_or_else(|| JiraError::UserNotFound(query.to_string()))
     }
 
-    pub async fn get_boards(&self, project_key: Option<&str>) -> Result<Vec<Board>, JiraError> {
-        let project_key = project_key.or_else(|| Some(self.project_key.as_ref()));
+    /// Streams the items of a list endpoint, page by page. The next page is
+    /// only requested once the items of the previous one are consumed.
+    fn paginate<'a, T>(
+        &'a self,
+        url: Url,
+        query: Vec<(&'static str, String)>,
+        action: &'static str,
+    ) -> impl Stream<Item = Result<T, JiraError>> + 'a
+    where
+        T: DeserializeOwned + 'a,
+    {
+        // `None` once the last page is read, `Some(None)` for the first page.
+        let pages = stream::try_unfold(Some(None), move |state: Option<Option<Cursor>>| {
+            let url = url.clone();
+            let query = query.clone();
+            async move {
+                let Some(cursor) = state else {
+                    return Ok(None);
+                };
+
+                let mut request = self.client.get(url).query(&query);
+                match &cursor {
+                    Some(Cursor::StartAt(start_at)) => {
+                        request = request.query(&[("startAt", start_at)]);
+                    }
+                    Some(Cursor::Token(token)) => {
+                        request = request.query(&[("nextPageToken", token)]);
+                    }
+                    None => {}
+                }
+                let response = self.send(request).await?;
+                let page: Page<T> = Self::handle_response(response, action).await?;
+
+                // A cursor that does not move would loop forever.
+                let next = page.next().filter(|next| cursor.as_ref() != Some(next));
+                Ok::<_, JiraError>(Some((page.values, next.map(Some))))
+            }
+        });
 
-        let mut url = format!("{}/rest/agile/1.0/board", self.base_url);
-        if let Some(key) = project_key {
-            url.push_str(&format!("?projectKeyOrId={}", key));
-        }
+        pages
+            .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+            .try_flatten()
+    }
 
-        let request = self.client.get(&url);
-        let response = self.authenticate(request).send().await?;
+    /// The epics of a board, fetched page by page.
+    pub fn epics_by_board(
+        &self,
+        board_id: u64,
+    ) -> Result<impl Stream<Item = Result<Epic, JiraError>> + '_, JiraError> {
+        let url = self.agile_url(&format!("board/{}/epic", board_id))?;
+        Ok(self.paginate(url, vec![], "get epics"))
+    }
 
-        #[derive(Deserialize)]
-        struct BoardResponse {
-            values: Vec<Board>,
-        }
+    /// The boards of a project (the client's project by default), fetched page
+    /// by page.
+    pub fn boards(
+        &self,
+        project_key: Option<&str>,
+    ) -> Result<impl Stream<Item = Result<Board, JiraError>> + '_, JiraError> {
+        let project_key = project_key.unwrap_or(&self.project_key);
+
+        let url = self.agile_url("board")?;
+        let query = vec![("projectKeyOrId", project_key.to_string())];
+        Ok(self.paginate(url, query, "get boards"))
+    }
+
+    /// Every field of the instance, with its display name.
+    pub async fn get_all_fields(&self) -> Result<Vec<Field>, JiraError> {
+        let url = self.api_url("field").await?;
+
+        let request = self.client.get(url);
+        let response = self.send(request).await?;
 
-        let board_response: BoardResponse =
-            Self::handle_response(response, JiraError::GetBoard).await?;
-        Ok(board_response.values)
+        Self::handle_response(response, "list fields").await
     }
 
+    /// The create screen of the issue types of a project: all of them, or only
+    /// `issue_type_id`. Jira versions without the per-issue-type endpoints
+    /// (before Jira 9 on Server and Data Center) answer 404 and get the legacy
+    /// `issue/createmeta` call.
     pub async fn get_fields(
         &self,
         project_key: Option<String>,
@@ -312,17 +873,94 @@ impl JiraClient {
     ) -> Result<Vec<IssueTypeInfo>, JiraError> {
         let project_key = self.resolve_project_key(project_key);
 
-        let mut url = format!(
-            "{}/rest/api/2/issue/createmeta?projectKeys={}&expand=projects.issuetypes.fields",
-            self.base_url, project_key
-        );
+        match self
+            .get_issue_type_fields(&project_key, issue_type_id.as_deref())
+            .await
+        {
+            Err(JiraError::Api { error, .. }) if error.status == Some(404) => {
+                debug!("No createmeta endpoint per issue type, using the legacy one");
+                self.get_legacy_fields(&project_key, issue_type_id).await
+            }
+            result => result,
+        }
+    }
 
-        if let Some(issue_type) = issue_type_id {
-            url.push_str(&format!("&issuetypeIds={}", issue_type));
+    async fn get_issue_type_fields(
+        &self,
+        project_key: &str,
+        issue_type_id: Option<&str>,
+    ) -> Result<Vec<IssueTypeInfo>, JiraError> {
+        #[derive(Deserialize)]
+        struct IssueTypeMeta {
+            id: String,
+            name: String,
+            #[serde(default)]
+            description: String,
+        }
+
+        let url = self
+            .api_url(&format!("issue/createmeta/{}/issuetypes", project_key))
+            .await?;
+        let mut issue_types: Vec<IssueTypeMeta> = self
+            .paginate(url, vec![], "get fields")
+            .try_collect()
+            .await?;
+        if let Some(id) = issue_type_id {
+            issue_types.retain(|issue_type| issue_type.id == id);
+        }
+
+        let mut infos = Vec::with_capacity(issue_types.len());
+        for issue_type in issue_types {
+            let url = self
+                .api_url(&format!(
+                    "issue/createmeta/{}/issuetypes/{}",
+                    project_key, issue_type.id
+                ))
+                .await?;
+            let fields: Vec<Value> = self
+                .paginate(url, vec![], "get fields")
+                .try_collect()
+                .await?;
+
+            // The fields are a list here, each with its ID, instead of a map.
+            let fields = fields
+                .into_iter()
+                .filter_map(|mut field| {
+                    let id = field.get("fieldId")?.as_str()?.to_string();
+                    if let Some(field) = field.as_object_mut() {
+                        field.entry("key").or_insert_with(|| json!(id));
+                    }
+                    Some((id, field))
+                })
+                .collect::<serde_json::Map<_, _>>();
+
+            infos.push(IssueTypeInfo {
+                fields: Self::field_metas(&issue_type.name, Value::Object(fields))?,
+                id: issue_type.id,
+                name: issue_type.name,
+                description: issue_type.description,
+            });
         }
+        Ok(infos)
+    }
+
+    /// `issue/createmeta` with all the fields of the project, deprecated in
+    /// Jira Cloud and removed in Jira 9.
+    async fn get_legacy_fields(
+        &self,
+        project_key: &str,
+        issue_type_id: Option<String>,
+    ) -> Result<Vec<IssueTypeInfo>, JiraError> {
+        let url = self.api_url("issue/createmeta").await?;
 
-        let request = self.client.get(&url);
-        let response = self.authenticate(request).send().await?;
+        let mut request = self.client.get(url).query(&[
+            ("projectKeys", project_key),
+            ("expand", "projects.issuetypes.fields"),
+        ]);
+        if let Some(issue_type) = issue_type_id {
+            request = request.query(&[("issuetypeIds", issue_type)]);
+        }
+        let response = self.send(request).await?;
 
         #[derive(Deserialize)]
         struct CreateMetaResponse {
@@ -342,34 +980,604 @@ impl JiraClient {
             fields: Value,
         }
 
-        let create_meta: CreateMetaResponse =
-            Self::handle_response(response, JiraError::GetFields).await?;
+        let create_meta: CreateMetaResponse = Self::handle_response(response, "get fields").await?;
 
-        let first_project = create_meta
-            .projects
-            .into_iter()
-            .next()
-            .ok_or_else(|| JiraError::GetFields("No project found in response".to_string()))?;
+        let first_project = create_meta.projects.into_iter().next().ok_or_else(|| {
+            JiraError::UnexpectedResponse {
+                action: "get fields",
+                reason: "No project found in response".to_string(),
+            }
+        })?;
 
         first_project
             .issuetypes
             .into_iter()
             .map(|issue_type_meta| {
-                let fields: std::collections::HashMap<String, FieldMeta> =
-                    serde_json::from_value(issue_type_meta.fields).map_err(|e| {
-                        JiraError::GetFields(format!(
-                            "Failed to deserialize fields for issue type '{}': {}",
-                            issue_type_meta.name, e
-                        ))
-                    })?;
-
                 Ok(IssueTypeInfo {
+                    fields: Self::field_metas(&issue_type_meta.name, issue_type_meta.fields)?,
                     id: issue_type_meta.id,
                     name: issue_type_meta.name,
                     description: issue_type_meta.description,
-                    fields,
                 })
             })
             .collect()
     }
+
+    /// The fields of an issue type, by ID.
+    fn field_metas(
+        issue_type: &str,
+        fields: Value,
+    ) -> Result<HashMap<String, FieldMeta>, JiraError> {
+        serde_json::from_value(fields).map_err(|e| JiraError::UnexpectedResponse {
+            action: "get fields",
+            reason: format!(
+                "Failed to deserialize fields for issue type '{}': {}",
+                issue_type, e
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    use crate::{auth::BearerAuth, config::RetryConfig, testing::StandIn};
+
+    #[test]
+    fn test_create_issue_body_merges_fields() {
+        let body = create_issue_body(
+            RestApi::V3,
+            "TEST",
+            "Fix bug",
+            Some(json!({
+                "issuetype": { "id": "10004" },
+                "labels": ["a"]
+            })),
+        );
+
+        assert_eq!(
+            body,
+            json!({
+                "fields": {
+                    "project": { "key": "TEST" },
+                    "summary": "Fix bug",
+                    "issuetype": { "id": "10004" },
+                    "labels": ["a"]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_issue_body_without_fields() {
+        let body = create_issue_body(RestApi::V3, "TEST", "Fix bug", None);
+        assert_eq!(
+            body,
+            json!({ "fields": { "project": { "key": "TEST" }, "summary": "Fix bug" } })
+        );
+    }
+
+    fn bulk_response(value: Value) -> BulkCreateResponse {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_bulk_record_chunk_maps_errors_to_rows() {
+        let response = bulk_response(json!({
+            "issues": [
+                { "id": "1", "key": "T-1", "self": "https://e/1" },
+                { "id": "3", "key": "T-3", "self": "https://e/3" }
+            ],
+            "errors": [{
+                "status": 400,
+                "elementErrors": {
+                    "errorMessages": ["Bad issue"],
+                    "errors": { "priority": "Invalid", "issuetype": "Required" }
+                },
+                "failedElementNumber": 1
+            }]
+        }));
+
+        let mut result = BulkCreateResult::default();
+        result.record_chunk(50, 3, response);
+
+        let created: Vec<_> = result
+            .created
+            .iter()
+            .map(|(index, ticket)| (*index, ticket.key.as_str()))
+            .collect();
+        assert_eq!(created, vec![(50, "T-1"), (52, "T-3")]);
+
+        assert_eq!(result.failed.len(), 1);
+        assert_eq!(result.failed[0].index, 51);
+        assert_eq!(
+            result.failed[0].error.to_string(),
+            "Status 400: Bad issue; issuetype: Required; priority: Invalid"
+        );
+    }
+
+    #[test]
+    fn test_bulk_record_chunk_all_failed() {
+        let response = bulk_response(json!({
+            "issues": [],
+            "errors": [
+                { "status": 400, "elementErrors": { "errors": { "summary": "Too long" } }, "failedElementNumber": 0 },
+                { "status": 400, "elementErrors": {}, "failedElementNumber": 1 }
+            ]
+        }));
+
+        let mut result = BulkCreateResult::default();
+        result.record_chunk(0, 2, response);
+
+        assert!(result.created.is_empty());
+        assert_eq!(
+            result.failed[0].error.to_string(),
+            "Status 400: summary: Too long"
+        );
+        assert_eq!(result.failed[1].error.to_string(), "Status 400");
+    }
+
+    #[test]
+    fn test_bulk_record_chunk_missing_issues() {
+        let response = bulk_response(
+            json!({ "issues": [{ "id": "1", "key": "T-1", "self": "https://e/1" }] }),
+        );
+
+        let mut result = BulkCreateResult::default();
+        result.record_chunk(0, 2, response);
+
+        assert_eq!(result.created.len(), 1);
+        assert_eq!(result.failed.len(), 1);
+        assert_eq!(result.failed[0].index, 1);
+    }
+
+    #[test]
+    fn test_bulk_fail_chunk() {
+        let error = JiraError::Api {
+            action: "create tickets",
+            error: JiraApiError::parse(Some(500), "<html>Internal error</html>"),
+        };
+        let mut result = BulkCreateResult::default();
+        result.fail_chunk(50, 2, &error);
+
+        let indexes: Vec<_> = result.failed.iter().map(|f| f.index).collect();
+        assert_eq!(indexes, vec![50, 51]);
+        assert!(
+            result
+                .failed
+                .iter()
+                .all(|f| f.error.to_string() == "Status 500: <html>Internal error</html>")
+        );
+    }
+
+    #[test]
+    fn test_parse_api_error() {
+        let error = JiraApiError::parse(
+            Some(400),
+            r#"{"errorMessages":[],"errors":{"customfield_10011":"Field cannot be set","priority":"Invalid"}}"#,
+        );
+
+        assert_eq!(error.status, Some(400));
+        assert!(error.error_messages.is_empty());
+        assert_eq!(error.errors["customfield_10011"], "Field cannot be set");
+        assert_eq!(
+            error.describe(|field| format!("`{}`", field)),
+            "Status 400: `customfield_10011`: Field cannot be set; `priority`: Invalid"
+        );
+    }
+
+    #[test]
+    fn test_parse_api_error_without_json() {
+        let body = "x".repeat(MAX_RAW_BODY + 10);
+        let error = JiraApiError::parse(Some(502), &body);
+
+        assert_eq!(
+            error.error_messages,
+            vec![format!("{}...", "x".repeat(MAX_RAW_BODY))]
+        );
+        assert!(error.errors.is_empty());
+    }
+
+    #[test]
+    fn test_parse_api_error_without_messages() {
+        let error =
+            JiraApiError::parse(Some(401), r#"{"message": "Client must be authenticated"}"#);
+        assert_eq!(
+            error.to_string(),
+            r#"Status 401: {"message": "Client must be authenticated"}"#
+        );
+
+        let error = JiraApiError::parse(Some(404), "");
+        assert_eq!(error.to_string(), "Status 404");
+    }
+
+    #[test]
+    fn test_create_issue_body_description_by_version() {
+        let fields = Some(json!({ "description": "Some **bold** text" }));
+
+        let v2 = create_issue_body(RestApi::V2, "TEST", "Fix bug", fields.clone());
+        assert_eq!(v2["fields"]["description"], "Some **bold** text");
+
+        let v3 = create_issue_body(RestApi::V3, "TEST", "Fix bug", fields);
+        assert_eq!(
+            v3["fields"]["description"],
+            adf::markdown_to_adf("Some **bold** text")
+        );
+    }
+
+    #[test]
+    fn test_create_issue_url() {
+        assert_eq!(
+            create_issue_url("https://example.atlassian.net", RestApi::V3)
+                .unwrap()
+                .as_str(),
+            "https://example.atlassian.net/rest/api/3/issue"
+        );
+        assert_eq!(
+            create_issue_url("https://example.atlassian.net", RestApi::V2)
+                .unwrap()
+                .as_str(),
+            "https://example.atlassian.net/rest/api/2/issue"
+        );
+    }
+
+    #[test]
+    fn test_endpoint_keeps_the_context_path() {
+        for base_url in ["https://corp.example/jira", "https://corp.example/jira/"] {
+            assert_eq!(
+                endpoint(base_url, "rest/api/2/issue").unwrap().as_str(),
+                "https://corp.example/jira/rest/api/2/issue"
+            );
+        }
+        assert!(matches!(
+            endpoint("corp.example", "rest/api/2/issue"),
+            Err(JiraError::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_user_reference() {
+        let cloud: User = serde_json::from_value(json!({
+            "accountId": "5b10a2844c20165700ede21g",
+            "displayName": "Jane"
+        }))
+        .unwrap();
+        assert_eq!(
+            cloud.reference(),
+            json!({ "accountId": "5b10a2844c20165700ede21g" })
+        );
+
+        let server: User = serde_json::from_value(json!({
+            "name": "jdoe",
+            "key": "JIRAUSER10100",
+            "displayName": "Jane"
+        }))
+        .unwrap();
+        assert_eq!(server.reference(), json!({ "name": "jdoe" }));
+    }
+
+    fn client(url: String, api_version: ApiVersion) -> JiraClient {
+        let auth = BearerAuth {
+            token: "pat".to_string(),
+        };
+        let retry = RetryConfig {
+            max_retries: 2,
+            initial_delay_ms: 1,
+            max_delay_ms: 1_000,
+        };
+        JiraClient::new(url, "TEST".to_string(), Box::new(auth), api_version)
+            .with_retry_policy(RetryPolicy::from(&retry))
+    }
+
+    #[tokio::test]
+    async fn test_detects_v2_on_data_center() {
+        let stand_in = StandIn::start(vec![
+            (200, r#"{"deploymentType":"DataCenter"}"#),
+            (200, r#"{"name":"jdoe","displayName":"Jane"}"#),
+            (200, r#"{"name":"jdoe","displayName":"Jane"}"#),
+        ]);
+        let client = client(format!("{}/jira", stand_in.url), ApiVersion::Auto);
+
+        client.get_myself().await.unwrap();
+        client.get_myself().await.unwrap();
+
+        let paths: Vec<_> = stand_in.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/jira/rest/api/2/serverInfo",
+                "/jira/rest/api/2/myself",
+                "/jira/rest/api/2/myself"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detection_falls_back_to_v3() {
+        let stand_in = StandIn::start(vec![(404, "{}")]);
+        let client = client(stand_in.url.clone(), ApiVersion::Auto);
+
+        assert_eq!(client.api().await, RestApi::V3);
+        assert_eq!(client.api().await, RestApi::V3);
+        assert_eq!(stand_in.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_configured_version_skips_detection() {
+        let stand_in = StandIn::start(vec![(200, "[]")]);
+        let client = client(stand_in.url.clone(), ApiVersion::V2);
+
+        client.find_user("jdoe").await.unwrap_err();
+
+        let requests = stand_in.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/rest/api/2/user/search?username=jdoe");
+    }
+
+    #[tokio::test]
+    async fn test_get_is_retried_on_server_errors() {
+        let stand_in = StandIn::start(vec![
+            (503, "{}"),
+            (502, "{}"),
+            (200, r#"{"accountId":"1","displayName":"Jane"}"#),
+        ]);
+        let client = client(stand_in.url.clone(), ApiVersion::V3);
+
+        let user = client.get_myself().await.unwrap();
+
+        assert_eq!(user.display_name, "Jane");
+        assert_eq!(stand_in.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retries_are_limited() {
+        let stand_in = StandIn::start(vec![(503, "{}"), (503, "{}"), (503, "{}")]);
+        let client = client(stand_in.url.clone(), ApiVersion::V3);
+
+        let error = client.get_myself().await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            JiraError::Api {
+                error: JiraApiError {
+                    status: Some(503),
+                    ..
+                },
+                ..
+            }
+        ));
+        assert_eq!(stand_in.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_post_is_not_retried_on_server_errors() {
+        let stand_in = StandIn::start(vec![
+            (500, "{}"),
+            (
+                201,
+                r#"{"id":"10000","key":"TEST-1","self":"https://e/10000"}"#,
+            ),
+        ]);
+        let client = client(stand_in.url.clone(), ApiVersion::V3);
+
+        let body = create_issue_body(RestApi::V3, "TEST", "Fix bug", None);
+        client.create_ticket(&body).await.unwrap_err();
+
+        assert_eq!(stand_in.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_post_is_retried_after_rate_limit() {
+        let stand_in = StandIn::start_with_headers(vec![
+            (429, vec![("Retry-After", "0")], "{}"),
+            (
+                201,
+                vec![],
+                r#"{"id":"10000","key":"TEST-1","self":"https://e/10000"}"#,
+            ),
+        ]);
+        let client = client(stand_in.url.clone(), ApiVersion::V3);
+
+        let body = create_issue_body(RestApi::V3, "TEST", "Fix bug", None);
+        let ticket = client.create_ticket(&body).await.unwrap();
+
+        assert_eq!(ticket.key, "TEST-1");
+        assert_eq!(ticket.id, "10000");
+        assert_eq!(ticket.self_url, "https://e/10000");
+        let requests = stand_in.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].body, requests[1].body);
+    }
+
+    #[tokio::test]
+    async fn test_long_retry_after_is_not_waited_for() {
+        let stand_in = StandIn::start_with_headers(vec![
+            (429, vec![("Retry-After", "3600")], "{}"),
+            (200, vec![], "{}"),
+        ]);
+        let client = client(stand_in.url.clone(), ApiVersion::V3);
+
+        client.get_ticket("TEST-1").await.unwrap_err();
+
+        assert_eq!(stand_in.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_page_next() {
+        let page = |value: Value| serde_json::from_value::<Page<u64>>(value).unwrap().next();
+
+        assert_eq!(
+            page(json!({ "startAt": 0, "maxResults": 2, "isLast": false, "values": [1, 2] })),
+            Some(Cursor::StartAt(2))
+        );
+        assert_eq!(
+            page(json!({ "startAt": 2, "maxResults": 2, "isLast": true, "values": [3] })),
+            None
+        );
+        assert_eq!(
+            page(json!({ "startAt": 0, "maxResults": 2, "total": 2, "values": [1, 2] })),
+            None
+        );
+        assert_eq!(
+            page(json!({ "startAt": 0, "maxResults": 50, "values": [1, 2] })),
+            None
+        );
+        assert_eq!(
+            page(json!({ "issues": [1], "nextPageToken": "abc" })),
+            Some(Cursor::Token("abc".to_string()))
+        );
+        assert_eq!(page(json!({ "issues": [1], "isLast": true })), None);
+    }
+
+    #[tokio::test]
+    async fn test_boards_follow_pages() {
+        let board = |id: u64| json!({ "id": id, "name": format!("Board {}", id), "type": "scrum" });
+        let first = json!({ "startAt": 0, "maxResults": 2, "isLast": false, "values": [board(1), board(2)] });
+        let last = json!({ "startAt": 2, "maxResults": 2, "isLast": true, "values": [board(3)] });
+        let (first, last) = (first.to_string(), last.to_string());
+        let stand_in = StandIn::start(vec![(200, &first), (200, &last)]);
+        let client = client(stand_in.url.clone(), ApiVersion::V3);
+
+        let boards: Vec<Board> = client.boards(None).unwrap().try_collect().await.unwrap();
+
+        let ids: Vec<u64> = boards.iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        let paths: Vec<_> = stand_in.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/rest/agile/1.0/board?projectKeyOrId=TEST",
+                "/rest/agile/1.0/board?projectKeyOrId=TEST&startAt=2"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_pages() {
+        let stand_in = StandIn::start(vec![
+            (200, r#"{"issues":[1,2],"nextPageToken":"abc"}"#),
+            (200, r#"{"issues":[3],"isLast":true}"#),
+        ]);
+        let client = client(stand_in.url.clone(), ApiVersion::V3);
+        let url = endpoint(&stand_in.url, "rest/api/3/search/jql").unwrap();
+
+        let items: Vec<u64> = client
+            .paginate::<u64>(url, vec![("jql", "project = TEST".to_string())], "search")
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, vec![1, 2, 3]);
+        assert_eq!(
+            stand_in.requests()[1].path,
+            "/rest/api/3/search/jql?jql=project+%3D+TEST&nextPageToken=abc"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pages_are_fetched_on_demand() {
+        let stand_in = StandIn::start(vec![
+            (
+                200,
+                r#"{"startAt":0,"maxResults":1,"isLast":false,"values":[{"id":1,"key":"E-1","name":"E","summary":"S","done":false}]}"#,
+            ),
+            (
+                200,
+                r#"{"startAt":1,"maxResults":1,"isLast":true,"values":[]}"#,
+            ),
+        ]);
+        let client = client(stand_in.url.clone(), ApiVersion::V3);
+
+        let epics: Vec<Epic> = client
+            .epics_by_board(7)
+            .unwrap()
+            .take(1)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(epics.len(), 1);
+        assert_eq!(stand_in.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fields_of_an_issue_type_follow_pages() {
+        let field = |id: &str| json!({ "fieldId": id, "name": id, "required": false, "schema": { "type": "string" } });
+        let issue_types = json!({ "startAt": 0, "maxResults": 50, "total": 2, "issueTypes": [
+            { "id": "10001", "name": "Story" },
+            { "id": "10004", "name": "Bug", "description": "A problem" }
+        ] });
+        let first =
+            json!({ "startAt": 0, "maxResults": 1, "total": 2, "fields": [field("summary")] });
+        let last =
+            json!({ "startAt": 1, "maxResults": 1, "total": 2, "fields": [field("environment")] });
+        let (issue_types, first, last) =
+            (issue_types.to_string(), first.to_string(), last.to_string());
+        let stand_in = StandIn::start(vec![(200, &issue_types), (200, &first), (200, &last)]);
+        let client = client(stand_in.url.clone(), ApiVersion::V3);
+
+        let issue_types = client
+            .get_fields(None, Some("10004".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(issue_types.len(), 1);
+        assert_eq!(issue_types[0].name, "Bug");
+        assert_eq!(issue_types[0].description, "A problem");
+        let mut ids: Vec<_> = issue_types[0].fields.keys().cloned().collect();
+        ids.sort();
+        assert_eq!(ids, vec!["environment", "summary"]);
+        assert_eq!(issue_types[0].fields["summary"].key, "summary");
+        let paths: Vec<_> = stand_in.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/rest/api/3/issue/createmeta/TEST/issuetypes",
+                "/rest/api/3/issue/createmeta/TEST/issuetypes/10004",
+                "/rest/api/3/issue/createmeta/TEST/issuetypes/10004?startAt=1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fields_fall_back_to_legacy_createmeta_on_404() {
+        let legacy = r#"{ "projects": [{ "issuetypes": [{
+            "id": "10004", "name": "Bug", "description": "",
+            "fields": { "summary": { "required": true, "name": "Summary", "key": "summary",
+                "schema": { "type": "string" } } }
+        }] }] }"#;
+        let stand_in = StandIn::start(vec![(404, "{}"), (200, legacy)]);
+        let client = client(stand_in.url.clone(), ApiVersion::V2);
+
+        let issue_types = client
+            .get_fields(None, Some("10004".to_string()))
+            .await
+            .unwrap();
+
+        assert!(issue_types[0].fields["summary"].required);
+        let requests = stand_in.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(
+            requests[1]
+                .path
+                .starts_with("/rest/api/2/issue/createmeta?projectKeys=TEST")
+        );
+        assert!(requests[1].path.ends_with("&issuetypeIds=10004"));
+    }
+
+    #[tokio::test]
+    async fn test_fields_only_fall_back_on_404() {
+        let stand_in = StandIn::start(vec![(403, r#"{"errorMessages":["Forbidden"]}"#)]);
+        let client = client(stand_in.url.clone(), ApiVersion::V3);
+
+        let error = client.get_fields(None, None).await.unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Failed to get fields: Status 403: Forbidden"
+        );
+        assert_eq!(stand_in.requests().len(), 1);
+    }
 }
@@ -3,16 +3,29 @@ use std::io;
 use tracing::{debug, error};
 use tracing_subscriber::EnvFilter;
 
+mod adf;
+mod auth;
+mod batch;
 mod cli;
 mod commands;
 mod config;
+mod credential_store;
+mod editor;
 mod env;
+mod fields;
 mod jira;
+mod output;
+mod retry;
+#[cfg(test)]
+mod testing;
+mod title;
 
 use cli::Args;
 use config::{CliOverrides, ConfigFile};
 use env::Credentials;
 use jira::JiraClient;
+use output::OutputFormat;
+use retry::RetryPolicy;
 
 #[derive(Debug, thiserror::Error)]
 enum AppError {
@@ -25,11 +38,39 @@ enum AppError {
     #[error("Jira API error: {0}")]
     Jira(#[from] jira::JiraError),
 
+    #[error("{}", commands::create::describe_rejection(source, field_origins.as_ref()))]
+    CreateTicket {
+        source: Box<jira::JiraError>,
+        /// The origins of the fields of the ticket, to attribute field errors.
+        field_origins: Option<config::Origins>,
+    },
+
     #[error("JSON serialization/deserialization error: {0}")]
     Json(String),
 
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Credentials store error: {0}")]
+    Store(#[from] credential_store::StoreError),
+
+    #[error("Field error: {0}")]
+    Field(#[from] fields::FieldError),
+
+    #[error("Output error: {0}")]
+    Output(#[from] output::OutputError),
+
+    #[error("Tickets input error: {0}")]
+    Batch(#[from] batch::BatchError),
+
+    #[error("Configuration has {errors} error(s) and {warnings} warning(s)")]
+    InvalidConfig { errors: usize, warnings: usize },
+
+    #[error("{failed} of {total} tickets failed")]
+    BulkCreate { failed: usize, total: usize },
 }
 
 #[tokio::main]
@@ -44,42 +85,62 @@ async fn main() {
 }
 
 async fn run(args: Args) -> Result<(), AppError> {
-    let config_file = ConfigFile::load()?;
+    let output_format = args.output;
     let cli_overrides = CliOverrides {
         jira_url: args.jira_url,
         project_key: args.project_key,
     };
 
-    let profile_names = match &args.command {
-        cli::Commands::Create(cmd) => cmd.profile.as_slice(),
-        cli::Commands::Info(cmd) => cmd.profile.as_slice(),
-        _ => &[],
+    // These commands must work when the configuration does not load, or does not exist yet.
+    let command = match args.command {
+        cli::Commands::Config(cmd) => {
+            return commands::config::handle_command(cmd, args.config.as_deref(), cli_overrides);
+        }
+        cli::Commands::Init(cmd) => return commands::init::handle_command(cmd).await,
+        command => command,
     };
 
-    let resolved_config = config_file.resolve(profile_names, cli_overrides)?;
-
-    debug!("Resolved configuration: {:?}", resolved_config);
-
-    let credentials = Credentials::load()?;
+    let config_file = ConfigFile::load(args.config.as_deref())?;
 
-    let client = JiraClient::new(
-        resolved_config.jira_url.clone(),
-        resolved_config.project_key.clone(),
-        credentials.api_token,
-        credentials.email,
-    );
-
-    match args.command {
+    match command {
         cli::Commands::Create(cmd) => {
-            commands::create::handle_command(cmd, &client, &resolved_config).await?
+            commands::create::handle_command(cmd, &config_file, cli_overrides, output_format)
+                .await?
+        }
+        cli::Commands::Auth(cmd) => {
+            commands::auth::handle_command(cmd, &config_file, cli_overrides).await?
+        }
+        cli::Commands::Info(cmd) => {
+            let resolved_config = config_file.resolve(&cmd.profile, cli_overrides)?;
+            debug!("Resolved configuration: {:?}", resolved_config);
+
+            let client = build_client(&resolved_config)?;
+            let format = output_format.unwrap_or_else(OutputFormat::detect);
+            let field_aliases = &resolved_config.instance.field_aliases;
+            commands::info::handle_command(cmd, &client, field_aliases, format).await?
+        }
+        cli::Commands::Config(_) | cli::Commands::Init(_) => {
+            unreachable!("handled before loading the configuration")
         }
-        cli::Commands::Info(cmd) => commands::info::handle_command(cmd, &client).await?,
-        cli::Commands::Init(cmd) => commands::init::handle_command(cmd).await?,
     }
 
     Ok(())
 }
 
+pub(crate) fn build_client(
+    resolved_config: &config::ResolvedConfig,
+) -> Result<JiraClient, AppError> {
+    let credentials = Credentials::load(&resolved_config.instance, &resolved_config.jira_url)?;
+
+    Ok(JiraClient::new(
+        resolved_config.jira_url.clone(),
+        resolved_config.project_key.clone(),
+        auth::strategy(credentials, &resolved_config.instance),
+        resolved_config.instance.api_version,
+    )
+    .with_retry_policy(RetryPolicy::from(&resolved_config.retry)))
+}
+
 fn init_tracing(verbose: bool) {
     let default_log_level = if verbose { "debug" } else { "info" };
 
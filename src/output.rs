@@ -0,0 +1,515 @@
+use std::io::{self, IsTerminal, Write};
+
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::jira::{Board, Epic, FieldMeta, IssueTypeInfo, JiraProject};
+
+/// The space between two columns of a table.
+const COLUMN_GAP: &str = "  ";
+
+#[derive(Error, Debug)]
+pub enum OutputError {
+    #[error("Failed to write JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Failed to write YAML: {0}")]
+    Yaml(#[from] serde_yaml_ng::Error),
+
+    #[error("Failed to write CSV: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("Failed to write the output: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// How `info` prints what it fetched, and `create` the tickets it created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Pretty-printed JSON, as returned by Jira.
+    Json,
+    /// The same data as YAML.
+    Yaml,
+    /// Aligned columns, truncated to the terminal width.
+    Table,
+    /// The table columns as CSV, with a header row.
+    Csv,
+    /// One identifier per line (board IDs, epic keys, field keys...).
+    Keys,
+}
+
+impl OutputFormat {
+    /// The format used without `--output`: a table in a terminal, and JSON
+    /// when stdout is redirected so that scripts keep working.
+    pub fn detect() -> Self {
+        if io::stdout().is_terminal() {
+            Self::Table
+        } else {
+            Self::Json
+        }
+    }
+}
+
+/// Data that can be shown as the rows of a table, or as a list of keys.
+pub trait Tabular {
+    const COLUMNS: &'static [&'static str];
+
+    /// The rows, with one cell per column.
+    fn rows(&self) -> Vec<Vec<String>>;
+
+    /// The identifiers printed by `--output keys`.
+    fn keys(&self) -> Vec<String>;
+}
+
+impl<T: Tabular> Tabular for Vec<T> {
+    const COLUMNS: &'static [&'static str] = T::COLUMNS;
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.iter().flat_map(Tabular::rows).collect()
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.iter().flat_map(Tabular::keys).collect()
+    }
+}
+
+impl Tabular for Board {
+    const COLUMNS: &'static [&'static str] = &["ID", "NAME", "TYPE", "PROJECT"];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let project = self.project.as_ref().map(|p| p.project_key.clone());
+        vec![vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.board_type.clone(),
+            project.unwrap_or_default(),
+        ]]
+    }
+
+    fn keys(&self) -> Vec<String> {
+        vec![self.id.to_string()]
+    }
+}
+
+impl Tabular for Epic {
+    const COLUMNS: &'static [&'static str] = &["KEY", "NAME", "SUMMARY", "DONE"];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        vec![vec![
+            self.key.clone(),
+            self.name.clone(),
+            self.summary.clone(),
+            yes_no(self.done),
+        ]]
+    }
+
+    fn keys(&self) -> Vec<String> {
+        vec![self.key.clone()]
+    }
+}
+
+/// One row per issue type, component and version of the project.
+impl Tabular for JiraProject {
+    const COLUMNS: &'static [&'static str] = &["KIND", "ID", "NAME", "DETAILS"];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let issue_types = self.issue_types.iter().map(|issue_type| {
+            let kind = if issue_type.is_subtask {
+                "subtask type"
+            } else {
+                "issue type"
+            };
+            [
+                kind,
+                &issue_type.id,
+                &issue_type.name,
+                &issue_type.description,
+            ]
+        });
+        let components = self
+            .components
+            .iter()
+            .map(|component| ["component", &component.id, &component.name, ""]);
+        let versions = self.versions.iter().map(|version| {
+            let details = if version.released {
+                "released"
+            } else {
+                "unreleased"
+            };
+            ["version", &version.id, &version.name, details]
+        });
+
+        issue_types
+            .chain(components)
+            .chain(versions)
+            .map(|row| row.map(str::to_string).to_vec())
+            .collect()
+    }
+
+    fn keys(&self) -> Vec<String> {
+        vec![self.key.clone()]
+    }
+}
+
+/// One row per field of the issue type, required fields first.
+impl Tabular for IssueTypeInfo {
+    const COLUMNS: &'static [&'static str] = &[
+        "ISSUE TYPE",
+        "FIELD",
+        "NAME",
+        "TYPE",
+        "REQUIRED",
+        "ALLOWED VALUES",
+    ];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        sorted_fields(self)
+            .into_iter()
+            .map(|field| {
+                let type_name = match &field.field_type.items {
+                    Some(items) => format!("{}<{}>", field.field_type.type_name, items),
+                    None => field.field_type.type_name.clone(),
+                };
+                let allowed_values: Vec<String> = field.allowed_values.iter().map(cell).collect();
+                vec![
+                    self.name.clone(),
+                    field.key.clone(),
+                    field.name.clone(),
+                    type_name,
+                    yes_no(field.required),
+                    allowed_values.join(", "),
+                ]
+            })
+            .collect()
+    }
+
+    fn keys(&self) -> Vec<String> {
+        sorted_fields(self)
+            .into_iter()
+            .map(|field| field.key.clone())
+            .collect()
+    }
+}
+
+/// A ticket as returned by Jira: one row per field that has a value.
+impl Tabular for Value {
+    const COLUMNS: &'static [&'static str] = &["FIELD", "VALUE"];
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let mut rows = vec![vec!["key".to_string(), cell(&self["key"])]];
+        if let Some(fields) = self["fields"].as_object() {
+            let mut fields: Vec<_> = fields.iter().filter(|(_, v)| !is_empty(v)).collect();
+            fields.sort_by_key(|(name, _)| *name);
+            rows.extend(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| vec![name.clone(), cell(value)]),
+            );
+        }
+        rows
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self["key"]
+            .as_str()
+            .map(str::to_string)
+            .into_iter()
+            .collect()
+    }
+}
+
+fn sorted_fields(issue_type: &IssueTypeInfo) -> Vec<&FieldMeta> {
+    let mut fields: Vec<_> = issue_type.fields.values().collect();
+    fields.sort_by(|a, b| b.required.cmp(&a.required).then_with(|| a.key.cmp(&b.key)));
+    fields
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(items) => items.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+/// A Jira value in a few words: the name of objects such as a status or a
+/// user, and compact JSON for anything else.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(cell).collect::<Vec<_>>().join(", "),
+        Value::Object(map) => ["name", "displayName", "value", "key", "id"]
+            .iter()
+            .find_map(|key| map.get(*key).and_then(Value::as_str))
+            .map_or_else(|| value.to_string(), str::to_string),
+        _ => value.to_string(),
+    }
+}
+
+/// Prints `data` to stdout. Tables are fitted to the terminal, if there is one.
+pub fn print<T>(data: &T, format: OutputFormat) -> Result<(), OutputError>
+where
+    T: Serialize + Tabular + ?Sized,
+{
+    let width = terminal_size::terminal_size().map(|(width, _)| usize::from(width.0));
+    let output = render(data, format, width)?;
+    if !write(&mut io::stdout().lock(), &output)? {
+        std::process::exit(0);
+    }
+    Ok(())
+}
+
+/// Writes `output`, returning `false` if the reader is gone, as when the
+/// output is piped to `head`.
+fn write(out: &mut impl Write, output: &str) -> Result<bool, OutputError> {
+    match out.write_all(output.as_bytes()).and_then(|()| out.flush()) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Formats `data`, fitting tables in `width` columns when given.
+pub fn render<T>(
+    data: &T,
+    format: OutputFormat,
+    width: Option<usize>,
+) -> Result<String, OutputError>
+where
+    T: Serialize + Tabular + ?Sized,
+{
+    let output = match format {
+        OutputFormat::Json => serde_json::to_string_pretty(data)? + "\n",
+        OutputFormat::Yaml => serde_yaml_ng::to_string(data)?,
+        OutputFormat::Table => render_table(T::COLUMNS, &data.rows(), width),
+        OutputFormat::Csv => render_csv(T::COLUMNS, &data.rows())?,
+        OutputFormat::Keys => data.keys().iter().map(|key| format!("{}\n", key)).collect(),
+    };
+    Ok(output)
+}
+
+fn render_csv(columns: &[&str], rows: &[Vec<String>]) -> Result<String, OutputError> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(columns)?;
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn render_table(columns: &[&str], rows: &[Vec<String>], width: Option<usize>) -> String {
+    // Cells are shown on one line.
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.split_whitespace().collect::<Vec<_>>().join(" "))
+                .collect()
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    if let Some(width) = width {
+        let gaps = COLUMN_GAP.len() * columns.len().saturating_sub(1);
+        fit(&mut widths, columns, width.saturating_sub(gaps));
+    }
+
+    let header = columns.iter().map(|c| c.to_string()).collect();
+    std::iter::once(&header)
+        .chain(&rows)
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", truncate(cell, *width)))
+                .collect();
+            format!("{}\n", cells.join(COLUMN_GAP).trim_end())
+        })
+        .collect()
+}
+
+/// Narrows the widest columns until they all fit in `available`, without
+/// making a column narrower than its header.
+fn fit(widths: &mut [usize], columns: &[&str], available: usize) {
+    while widths.iter().sum::<usize>() > available {
+        let widest = widths
+            .iter()
+            .enumerate()
+            .filter(|(i, width)| **width > columns[*i].chars().count())
+            .max_by_key(|(_, width)| **width)
+            .map(|(i, _)| i);
+        match widest {
+            Some(i) => widths[i] -= 1,
+            None => break,
+        }
+    }
+}
+
+fn truncate(cell: &str, width: usize) -> String {
+    if cell.chars().count() <= width {
+        return cell.to_string();
+    }
+    let mut truncated: String = cell.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::jira::{Component, IssueType, Version};
+
+    fn epic(key: &str, summary: &str) -> Epic {
+        Epic {
+            id: 1,
+            key: key.to_string(),
+            name: "Name".to_string(),
+            summary: summary.to_string(),
+            done: false,
+        }
+    }
+
+    #[test]
+    fn test_table_fits_the_terminal() {
+        let epics = vec![
+            epic("PROJ-1", "A summary far too long for a narrow terminal"),
+            epic("PROJ-22", "Short"),
+        ];
+
+        let table = render(&epics, OutputFormat::Table, Some(40)).unwrap();
+
+        assert_eq!(
+            table,
+            "KEY      NAME  SUMMARY              DONE\n\
+             PROJ-1   Name  A summary far too …  no\n\
+             PROJ-22  Name  Short                no\n"
+        );
+        assert!(table.lines().all(|line| line.chars().count() <= 40));
+
+        let wide = render(&epics, OutputFormat::Table, None).unwrap();
+        assert!(wide.contains("A summary far too long for a narrow terminal"));
+    }
+
+    #[test]
+    fn test_csv_and_keys() {
+        let epics = vec![epic("PROJ-1", "Say \"hi\", twice")];
+
+        assert_eq!(
+            render(&epics, OutputFormat::Csv, None).unwrap(),
+            "KEY,NAME,SUMMARY,DONE\nPROJ-1,Name,\"Say \"\"hi\"\", twice\",no\n"
+        );
+        assert_eq!(
+            render(&epics, OutputFormat::Keys, None).unwrap(),
+            "PROJ-1\n"
+        );
+    }
+
+    #[test]
+    fn test_yaml() {
+        let epics = vec![epic("PROJ-1", "Summary")];
+
+        assert_eq!(
+            render(&epics, OutputFormat::Yaml, None).unwrap(),
+            "- id: 1\n  key: PROJ-1\n  name: Name\n  summary: Summary\n  done: false\n"
+        );
+    }
+
+    #[test]
+    fn test_project_rows() {
+        let project = JiraProject {
+            id: "10000".to_string(),
+            key: "PROJ".to_string(),
+            name: "Project".to_string(),
+            issue_types: vec![IssueType {
+                id: "10001".to_string(),
+                name: "Task".to_string(),
+                description: "A task".to_string(),
+                is_subtask: false,
+            }],
+            components: vec![Component {
+                id: "10100".to_string(),
+                name: "Backend".to_string(),
+            }],
+            versions: vec![Version {
+                id: "10200".to_string(),
+                name: "v1.0".to_string(),
+                released: true,
+            }],
+        };
+
+        assert_eq!(
+            project.rows(),
+            vec![
+                vec!["issue type", "10001", "Task", "A task"],
+                vec!["component", "10100", "Backend", ""],
+                vec!["version", "10200", "v1.0", "released"],
+            ]
+        );
+        assert_eq!(project.keys(), vec!["PROJ"]);
+    }
+
+    #[test]
+    fn test_ticket_rows() {
+        let ticket = json!({
+            "key": "PROJ-1",
+            "fields": {
+                "summary": "Fix it",
+                "status": { "id": "3", "name": "In Progress" },
+                "labels": ["a", "b"],
+                "assignee": null,
+            }
+        });
+
+        assert_eq!(
+            ticket.rows(),
+            vec![
+                vec!["key", "PROJ-1"],
+                vec!["labels", "a, b"],
+                vec!["status", "In Progress"],
+                vec!["summary", "Fix it"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_stops_quietly_on_a_closed_pipe() {
+        struct ClosedPipe(io::ErrorKind);
+
+        impl Write for ClosedPipe {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(self.0.into())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut out = vec![];
+        assert!(write(&mut out, "PROJ-1\n").unwrap());
+        assert_eq!(out, b"PROJ-1\n");
+
+        assert!(!write(&mut ClosedPipe(io::ErrorKind::BrokenPipe), "PROJ-1\n").unwrap());
+        assert!(matches!(
+            write(&mut ClosedPipe(io::ErrorKind::PermissionDenied), "PROJ-1\n"),
+            Err(OutputError::Io(_))
+        ));
+    }
+}
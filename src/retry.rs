@@ -0,0 +1,214 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::{Method, Response, StatusCode, header::HeaderMap};
+
+use crate::config::RetryConfig;
+
+/// Decides whether a failed request is sent again, and after how long.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from(&RetryConfig::default())
+    }
+}
+
+impl From<&RetryConfig> for RetryPolicy {
+    fn from(config: &RetryConfig) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            initial_delay: Duration::from_millis(config.initial_delay_ms),
+            max_delay: Duration::from_millis(config.max_delay_ms),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay before retrying a request that got `outcome` on its
+    /// `attempt`-th retry (0 for the first request), or `None` to give up.
+    ///
+    /// Rate limits (429) and connection failures are retried for every
+    /// method, since Jira did not process the request. Server errors and
+    /// timeouts are only retried for idempotent methods: a `POST` may have
+    /// created the ticket anyway.
+    pub fn delay(
+        &self,
+        method: &Method,
+        outcome: &Result<Response, reqwest::Error>,
+        attempt: u32,
+    ) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+
+        let requested = match outcome {
+            Ok(response) => {
+                let status = response.status();
+                let retryable = status == StatusCode::TOO_MANY_REQUESTS
+                    || (is_transient(status) && method.is_idempotent());
+                if !retryable {
+                    return None;
+                }
+                requested_delay(response.headers())
+            }
+            Err(e) if e.is_connect() => None,
+            Err(e) if e.is_timeout() && method.is_idempotent() => None,
+            Err(_) => return None,
+        };
+
+        match requested {
+            // Retrying earlier would only hit the limit again.
+            Some(delay) if delay > self.max_delay => None,
+            Some(delay) => Some(delay),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    /// The exponential delay of a retry, with jitter: between half and all of
+    /// `initial_delay * 2^attempt`, capped at `max_delay`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        delay / 2 + delay.mul_f64(jitter() / 2.0)
+    }
+}
+
+/// Statuses of errors that usually go away, such as a node restarting.
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// The delay asked by Jira: `Retry-After` in seconds, or the time until
+/// `X-RateLimit-Reset` (an ISO 8601 timestamp such as `2024-05-20T16:10Z`).
+fn requested_delay(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name)?.to_str().ok().map(str::trim);
+
+    if let Some(seconds) = header("retry-after").and_then(|value| value.parse::<u64>().ok()) {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let reset = parse_timestamp(header("x-ratelimit-reset")?)?;
+    Some(reset.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// Parses a UTC timestamp: `YYYY-MM-DDTHH:MM[:SS[.fff]]Z`.
+fn parse_timestamp(value: &str) -> Option<SystemTime> {
+    let value = value.strip_suffix('Z')?;
+    let (date, time) = value.split_once('T')?;
+
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    // The header comes from the server: out of range values are ignored
+    // rather than overflowing.
+    if !(0..=9999).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut time = time.splitn(3, ':');
+    let hours: u64 = time.next()?.parse().ok()?;
+    let minutes: u64 = time.next()?.parse().ok()?;
+    let seconds: f64 = time.next().map_or(Some(0.0), |s| s.parse().ok())?;
+
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    let whole = days
+        .checked_mul(86_400)?
+        .checked_add(hours.checked_mul(3_600)?)?
+        .checked_add(minutes.checked_mul(60)?)?;
+    let since_epoch =
+        Duration::from_secs(whole).checked_add(Duration::try_from_secs_f64(seconds).ok()?)?;
+    UNIX_EPOCH.checked_add(since_epoch)
+}
+
+/// The number of days from 1970-01-01 to a date of the Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// A number in `[0, 1)`, random enough to spread out the retries of
+/// concurrent requests.
+fn jitter() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    f64::from(nanos % 1_000) / 1_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_with_jitter() {
+        let policy = policy(5);
+        for (attempt, full) in [(0, 100), (1, 200), (2, 400), (3, 800), (4, 1000)] {
+            let delay = policy.backoff(attempt);
+            let full = Duration::from_millis(full);
+            assert!(
+                delay >= full / 2 && delay <= full,
+                "{:?} for attempt {}",
+                delay,
+                attempt
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let expected = UNIX_EPOCH + Duration::from_secs(1_716_221_400);
+        assert_eq!(parse_timestamp("2024-05-20T16:10Z"), Some(expected));
+        assert_eq!(
+            parse_timestamp("2024-05-20T16:10:30.5Z"),
+            Some(expected + Duration::from_millis(30_500))
+        );
+        assert_eq!(parse_timestamp("2024-05-20 16:10"), None);
+    }
+
+    #[test]
+    fn test_parse_out_of_range_timestamp() {
+        assert_eq!(parse_timestamp("2024-05-20T16:10:-1Z"), None);
+        assert_eq!(parse_timestamp("2024-05-20T16:10:NaNZ"), None);
+        assert_eq!(parse_timestamp("2024-05-20T16:10:1e300Z"), None);
+        assert_eq!(parse_timestamp("2024-05-20T18446744073709551615:00Z"), None);
+        assert_eq!(parse_timestamp("9223372036854775807-01-01T00:00Z"), None);
+        assert_eq!(parse_timestamp("2024-13-01T00:00Z"), None);
+    }
+
+    #[test]
+    fn test_requested_delay() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(requested_delay(&headers), None);
+
+        headers.insert("x-ratelimit-reset", "2000-01-01T00:00Z".parse().unwrap());
+        assert_eq!(requested_delay(&headers), Some(Duration::ZERO));
+
+        headers.insert("retry-after", "7".parse().unwrap());
+        assert_eq!(requested_delay(&headers), Some(Duration::from_secs(7)));
+    }
+}
@@ -0,0 +1,433 @@
+use std::collections::HashSet;
+
+use serde_json::{Map, Value, json};
+
+use crate::config::{ConfigError, Origin, Origins, TitleSyntax, Traced};
+
+/// The assignee requested with an `@` token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Assignee {
+    /// `@me`: the authenticated user.
+    Me,
+    /// `@alice`: a user search query.
+    User(String),
+}
+
+/// A ticket title with its inline tokens extracted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedTitle {
+    pub summary: String,
+    pub labels: Vec<String>,
+    pub components: Vec<String>,
+    pub priority: Option<String>,
+    pub parent: Option<String>,
+    pub assignee: Option<Assignee>,
+    /// `:name` tokens matching a profile defined in the config.
+    pub profiles: Vec<String>,
+    /// The last `:name` token that is not a profile, used as the issue type name.
+    pub issue_type: Option<String>,
+}
+
+/// Parses the inline title syntax, e.g. `Fix login #auth +backend !high ^SHOP-42 @me :bug`.
+///
+/// Tokens are whitespace-separated words starting with a sigil. A sigil inside a
+/// word (`C#`) or on its own (`a + b`) is kept as text, and a leading backslash
+/// (`\#1`) escapes a token.
+pub struct TitleParser {
+    syntax: TitleSyntax,
+    profile_names: HashSet<String>,
+}
+
+impl TitleParser {
+    pub fn new<I, S>(syntax: &TitleSyntax, profile_names: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        syntax.validate()?;
+
+        Ok(Self {
+            syntax: syntax.clone(),
+            profile_names: profile_names.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    pub fn parse(&self, title: &str) -> ParsedTitle {
+        if !self.syntax.enabled {
+            return ParsedTitle {
+                summary: title.split_whitespace().collect::<Vec<_>>().join(" "),
+                ..ParsedTitle::default()
+            };
+        }
+
+        let mut parsed = ParsedTitle::default();
+        let mut words = Vec::new();
+
+        for word in title.split_whitespace() {
+            if let Some(escaped) = word.strip_prefix('\\')
+                && escaped.starts_with(|c| self.is_sigil(c))
+            {
+                words.push(escaped);
+                continue;
+            }
+
+            if !self.apply_token(&mut parsed, word) {
+                words.push(word);
+            }
+        }
+
+        parsed.summary = words.join(" ");
+        parsed
+    }
+
+    fn is_sigil(&self, c: char) -> bool {
+        let s = &self.syntax;
+        [
+            s.label,
+            s.component,
+            s.priority,
+            s.parent,
+            s.assignee,
+            s.profile,
+        ]
+        .contains(&c)
+    }
+
+    /// Records `word` on `parsed` if it is a token. Returns false for plain words.
+    fn apply_token(&self, parsed: &mut ParsedTitle, word: &str) -> bool {
+        let mut chars = word.chars();
+        let Some(sigil) = chars.next() else {
+            return false;
+        };
+        let value = chars.as_str();
+        if value.is_empty() {
+            return false;
+        }
+
+        let s = &self.syntax;
+        if sigil == s.label {
+            parsed.labels.push(value.to_string());
+        } else if sigil == s.component {
+            parsed.components.push(value.to_string());
+        } else if sigil == s.priority {
+            parsed.priority = Some(value.to_string());
+        } else if sigil == s.parent {
+            parsed.parent = Some(value.to_string());
+        } else if sigil == s.assignee {
+            parsed.assignee = Some(if value.eq_ignore_ascii_case("me") {
+                Assignee::Me
+            } else {
+                Assignee::User(value.to_string())
+            });
+        } else if sigil == s.profile {
+            if self.profile_names.contains(value) {
+                parsed.profiles.push(value.to_string());
+            } else {
+                parsed.issue_type = Some(value.to_string());
+            }
+        } else {
+            return false;
+        }
+
+        true
+    }
+}
+
+impl ParsedTitle {
+    /// Builds the Jira fields described by the tokens, except the assignee
+    /// which needs a user lookup.
+    pub fn fields(&self) -> Map<String, Value> {
+        let mut fields = Map::new();
+
+        if !self.labels.is_empty() {
+            fields.insert("labels".to_string(), json!(self.labels));
+        }
+        if !self.components.is_empty() {
+            let components: Vec<Value> = self
+                .components
+                .iter()
+                .map(|name| json!({ "name": name }))
+                .collect();
+            fields.insert("components".to_string(), Value::Array(components));
+        }
+        if let Some(priority) = &self.priority {
+            fields.insert("priority".to_string(), json!({ "name": priority }));
+        }
+        if let Some(parent) = &self.parent {
+            fields.insert("parent".to_string(), json!({ "key": parent }));
+        }
+        if let Some(issue_type) = &self.issue_type {
+            fields.insert("issuetype".to_string(), json!({ "name": issue_type }));
+        }
+
+        fields
+    }
+
+    /// Merges the title fields, and the `extra` fields set from `extra_origin`,
+    /// into the resolved profile fields, recording where each value comes from.
+    /// Arrays (labels, components) are appended, other fields are replaced so that
+    /// `!high` does not end up next to the profile's priority id.
+    pub fn merge_into(
+        &self,
+        base: Option<Traced>,
+        extra: Map<String, Value>,
+        extra_origin: &Origin,
+    ) -> Option<Traced> {
+        let title_fields = Traced::new(Value::Object(self.fields()), &Origin::Title)
+            .deep_merge(Traced::new(Value::Object(extra), extra_origin));
+        let Value::Object(fields) = &title_fields.value else {
+            unreachable!("title fields are an object");
+        };
+        if fields.is_empty() {
+            return base;
+        }
+
+        let mut merged = base.unwrap_or_else(|| Traced {
+            value: json!({}),
+            origins: Origins::default(),
+        });
+        for (key, value) in fields {
+            if !(value.is_array() && merged.value.get(key).is_some_and(Value::is_array)) {
+                merged.remove(&[key]);
+            }
+        }
+
+        Some(merged.deep_merge(title_fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser() -> TitleParser {
+        TitleParser::new(&TitleSyntax::default(), ["bug", "backend"]).unwrap()
+    }
+
+    #[test]
+    fn test_plain_title_is_unchanged() {
+        let parsed = parser().parse("Fix login page error");
+        assert_eq!(parsed.summary, "Fix login page error");
+        assert_eq!(
+            parsed,
+            ParsedTitle {
+                summary: "Fix login page error".to_string(),
+                ..ParsedTitle::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_extracts_every_token_kind() {
+        let parsed = parser().parse("Fix login #auth +web !high ^SHOP-42 @me :bug");
+
+        assert_eq!(parsed.summary, "Fix login");
+        assert_eq!(parsed.labels, vec!["auth"]);
+        assert_eq!(parsed.components, vec!["web"]);
+        assert_eq!(parsed.priority.as_deref(), Some("high"));
+        assert_eq!(parsed.parent.as_deref(), Some("SHOP-42"));
+        assert_eq!(parsed.assignee, Some(Assignee::Me));
+        assert_eq!(parsed.profiles, vec!["bug"]);
+        assert_eq!(parsed.issue_type, None);
+    }
+
+    #[test]
+    fn test_tokens_anywhere_in_title() {
+        let parsed = parser().parse("#auth Fix !high login   page #ui");
+        assert_eq!(parsed.summary, "Fix login page");
+        assert_eq!(parsed.labels, vec!["auth", "ui"]);
+    }
+
+    #[test]
+    fn test_repeated_tokens() {
+        let parsed = parser().parse("Title +api +web !low !high ^A-1 ^B-2 @bob @alice");
+        assert_eq!(parsed.components, vec!["api", "web"]);
+        assert_eq!(parsed.priority.as_deref(), Some("high"));
+        assert_eq!(parsed.parent.as_deref(), Some("B-2"));
+        assert_eq!(parsed.assignee, Some(Assignee::User("alice".to_string())));
+    }
+
+    #[test]
+    fn test_assignee_me_is_case_insensitive() {
+        let parsed = parser().parse("Title @ME");
+        assert_eq!(parsed.assignee, Some(Assignee::Me));
+    }
+
+    #[test]
+    fn test_profile_token_vs_issue_type() {
+        let parsed = parser().parse("Title :backend :Story :bug");
+        assert_eq!(parsed.profiles, vec!["backend", "bug"]);
+        assert_eq!(parsed.issue_type.as_deref(), Some("Story"));
+    }
+
+    #[test]
+    fn test_sigil_inside_word_is_text() {
+        let parsed = parser().parse("Port C# code to foo@bar.com ratio 1:2");
+        assert_eq!(parsed.summary, "Port C# code to foo@bar.com ratio 1:2");
+        assert!(parsed.labels.is_empty());
+        assert!(parsed.assignee.is_none());
+        assert!(parsed.issue_type.is_none());
+    }
+
+    #[test]
+    fn test_lone_sigil_is_text() {
+        let parsed = parser().parse("Add a + b ! #");
+        assert_eq!(parsed.summary, "Add a + b ! #");
+        assert!(parsed.components.is_empty());
+    }
+
+    #[test]
+    fn test_escaped_token_is_text() {
+        let parsed = parser().parse(r"Handle \#1 and \@here");
+        assert_eq!(parsed.summary, "Handle #1 and @here");
+        assert!(parsed.labels.is_empty());
+        assert!(parsed.assignee.is_none());
+    }
+
+    #[test]
+    fn test_backslash_without_sigil_is_kept() {
+        let parsed = parser().parse(r"Fix C:\temp path");
+        assert_eq!(parsed.summary, r"Fix C:\temp path");
+    }
+
+    #[test]
+    fn test_title_with_only_tokens() {
+        let parsed = parser().parse("#a #b");
+        assert_eq!(parsed.summary, "");
+        assert_eq!(parsed.labels, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_disabled_syntax() {
+        let syntax = TitleSyntax {
+            enabled: false,
+            ..TitleSyntax::default()
+        };
+        let parser = TitleParser::new(&syntax, Vec::<String>::new()).unwrap();
+
+        let parsed = parser.parse("Fix  #auth !high");
+        assert_eq!(parsed.summary, "Fix #auth !high");
+        assert!(parsed.labels.is_empty());
+        assert!(parsed.priority.is_none());
+    }
+
+    #[test]
+    fn test_custom_sigils() {
+        let syntax = TitleSyntax {
+            label: '%',
+            profile: '~',
+            ..TitleSyntax::default()
+        };
+        let parser = TitleParser::new(&syntax, ["bug"]).unwrap();
+
+        let parsed = parser.parse("Fix #1 %auth ~bug :x");
+        assert_eq!(parsed.summary, "Fix #1 :x");
+        assert_eq!(parsed.labels, vec!["auth"]);
+        assert_eq!(parsed.profiles, vec!["bug"]);
+    }
+
+    #[test]
+    fn test_invalid_syntax_is_rejected() {
+        let syntax = TitleSyntax {
+            label: '!',
+            ..TitleSyntax::default()
+        };
+        assert!(TitleParser::new(&syntax, Vec::<String>::new()).is_err());
+    }
+
+    #[test]
+    fn test_fields() {
+        let parsed = parser().parse("T #a +web !High ^SHOP-42 :Task");
+        let fields = Value::Object(parsed.fields());
+
+        assert_eq!(
+            fields,
+            json!({
+                "labels": ["a"],
+                "components": [{ "name": "web" }],
+                "priority": { "name": "High" },
+                "parent": { "key": "SHOP-42" },
+                "issuetype": { "name": "Task" }
+            })
+        );
+    }
+
+    #[test]
+    fn test_fields_empty_for_plain_title() {
+        assert!(parser().parse("Plain").fields().is_empty());
+    }
+
+    fn profile() -> Origin {
+        Origin::Profile {
+            name: "bug".to_string(),
+            file: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_into_appends_arrays_and_replaces_objects() {
+        let base = json!({
+            "labels": ["team"],
+            "priority": { "id": "3" },
+            "customfield_10011": "kept"
+        });
+
+        let parsed = parser().parse("T #auth !High");
+        let merged = parsed
+            .merge_into(Some(Traced::new(base, &profile())), Map::new(), &profile())
+            .unwrap();
+
+        assert_eq!(
+            merged.value,
+            json!({
+                "labels": ["team", "auth"],
+                "priority": { "name": "High" },
+                "customfield_10011": "kept"
+            })
+        );
+        assert_eq!(merged.origins.at(&["labels", "0"]), Some(&profile()));
+        assert_eq!(merged.origins.at(&["labels", "1"]), Some(&Origin::Title));
+        assert_eq!(
+            merged.origins.at(&["priority", "name"]),
+            Some(&Origin::Title)
+        );
+        assert_eq!(merged.origins.at(&["priority", "id"]), None);
+        assert_eq!(merged.origins.at(&["customfield_10011"]), Some(&profile()));
+    }
+
+    #[test]
+    fn test_merge_into_without_base() {
+        let parsed = parser().parse("T #auth");
+        let merged = parsed.merge_into(None, Map::new(), &profile()).unwrap();
+        assert_eq!(merged.value, json!({ "labels": ["auth"] }));
+        assert_eq!(merged.origins.at(&["labels", "0"]), Some(&Origin::Title));
+    }
+
+    #[test]
+    fn test_merge_into_keeps_base_when_no_tokens() {
+        let base = Traced::new(json!({ "labels": ["team"] }), &profile());
+        let merged = parser()
+            .parse("T")
+            .merge_into(Some(base.clone()), Map::new(), &profile());
+        assert_eq!(merged, Some(base));
+
+        assert_eq!(
+            parser().parse("T").merge_into(None, Map::new(), &profile()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_merge_into_with_extra_fields() {
+        let mut extra = Map::new();
+        extra.insert("description".to_string(), json!("Steps"));
+
+        let origin = Origin::Cli("--description");
+        let merged = parser()
+            .parse("T")
+            .merge_into(None, extra, &origin)
+            .unwrap();
+        assert_eq!(merged.value, json!({ "description": "Steps" }));
+        assert_eq!(merged.origins.at(&["description"]), Some(&origin));
+    }
+}